@@ -1,7 +1,11 @@
-use crate::{id::ShortId, subject_name::SubjectName};
+use crate::{cert::KeyAlgorithm, error::Error, id::ShortId, subject_name::SubjectName};
 use base64::{engine::general_purpose, Engine as _};
 use serde_default::DefaultFromSerde;
 use serde_derive::{Deserialize, Serialize};
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
 use utoipa::ToSchema;
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
@@ -13,6 +17,72 @@ pub struct Acme {
     pub identifiers: Vec<SubjectName>,
     #[schema(value_type = String, example = "http-01")]
     pub challenge_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_challenge: Option<DnsChallengeConfig>,
+}
+
+impl Acme {
+    pub fn validate(&self) -> Result<(), Error> {
+        self.config.validate()?;
+        match self.challenge_type.as_str() {
+            "http-01" | "tls-alpn-01" => {
+                if let Some(name) = self
+                    .identifiers
+                    .iter()
+                    .find(|id| matches!(id, SubjectName::WildcardDnsName(_)))
+                {
+                    return Err(Error::WildcardRequiresDnsChallenge {
+                        name: name.to_string(),
+                    });
+                }
+            }
+            "dns-01" => {
+                if self.dns_challenge.is_none() {
+                    return Err(Error::DnsChallengeConfigMissing);
+                }
+            }
+            _ => {
+                return Err(Error::UnsupportedAcmeChallengeType {
+                    challenge_type: self.challenge_type.clone(),
+                })
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct DnsChallengeConfig {
+    #[schema(value_type = String, example = "https://dns.example.com/acme")]
+    pub webhook_url: String,
+    /// Sent to the webhook as HTTP basic authentication.
+    ///
+    /// Stored with the other secrets and never returned, exported or recorded in the history.
+    #[serde(default, skip_serializing)]
+    pub webhook_credentials: Option<WebhookCredentials>,
+    #[serde(with = "humantime_serde", default = "default_propagation_delay")]
+    #[schema(value_type = String, example = "30s")]
+    pub propagation_delay: Duration,
+}
+
+fn default_propagation_delay() -> Duration {
+    Duration::from_secs(30)
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct WebhookCredentials {
+    #[schema(example = "taxy")]
+    pub username: String,
+    #[schema(example = "secret")]
+    pub password: String,
+}
+
+impl fmt::Debug for WebhookCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookCredentials")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, DefaultFromSerde, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
@@ -22,9 +92,32 @@ pub struct AcmeConfig {
     #[serde(default)]
     #[schema(example = "Let's Encrypt")]
     pub provider: String,
-    #[schema(example = "60")]
-    #[serde(default = "default_renewal_days")]
-    pub renewal_days: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "30")]
+    pub renew_before_days: Option<u64>,
+    #[serde(default = "default_renew_before_percent")]
+    #[schema(example = "33")]
+    pub renew_before_percent: u8,
+    #[serde(default)]
+    pub key_algorithm: KeyAlgorithm,
+}
+
+impl AcmeConfig {
+    pub fn validate(&self) -> Result<(), Error> {
+        // ACME CAs do not issue certificates for Ed25519 keys.
+        if self.key_algorithm == KeyAlgorithm::Ed25519 {
+            return Err(Error::UnsupportedKeyAlgorithm {
+                algorithm: self.key_algorithm.to_string(),
+            });
+        }
+        // 0% would never renew early and 100% would renew right after issuance.
+        if !(1..=99).contains(&self.renew_before_percent) {
+            return Err(Error::InvalidRenewBeforePercent {
+                percent: self.renew_before_percent,
+            });
+        }
+        Ok(())
+    }
 }
 
 fn default_active() -> bool {
@@ -35,13 +128,15 @@ fn is_true(b: &bool) -> bool {
     *b
 }
 
-fn default_renewal_days() -> u64 {
-    60
+fn default_renew_before_percent() -> u8 {
+    33
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct AcmeInfo {
     pub id: ShortId,
+    #[schema(value_type = String, example = "f9cf7e3faa1aca7e")]
+    pub account_id: ShortId,
     #[schema(inline)]
     #[serde(flatten)]
     pub config: AcmeConfig,
@@ -51,10 +146,107 @@ pub struct AcmeInfo {
     pub challenge_type: String,
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AcmeStatus {
+    pub order: AcmeOrderStatus,
+    #[serde(
+        serialize_with = "serialize_timestamp",
+        deserialize_with = "deserialize_timestamp"
+    )]
+    #[schema(value_type = Option<u64>)]
+    pub last_attempt_at: Option<SystemTime>,
+    #[serde(
+        serialize_with = "serialize_timestamp",
+        deserialize_with = "deserialize_timestamp"
+    )]
+    #[schema(value_type = Option<u64>)]
+    pub last_success_at: Option<SystemTime>,
+    #[schema(example = "order is invalid")]
+    pub last_error: Option<String>,
+    #[serde(
+        serialize_with = "serialize_timestamp",
+        deserialize_with = "deserialize_timestamp"
+    )]
+    #[schema(value_type = Option<u64>)]
+    pub next_renewal_at: Option<SystemTime>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AcmeOrderStatus {
+    #[default]
+    Idle,
+    Requesting,
+    Validating,
+}
+
+fn serialize_timestamp<S>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    if let Some(time) = time {
+        let time = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        serializer.serialize_some(&time)
+    } else {
+        serializer.serialize_none()
+    }
+}
+
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    let time = Option::<u64>::deserialize(deserializer)?;
+    Ok(time.map(|time| SystemTime::UNIX_EPOCH + Duration::from_secs(time)))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct AcmeRevokeRequest {
+    #[schema(value_type = String, example = "f9cf7e3faa1aca7e")]
+    pub cert_id: ShortId,
+    #[serde(default)]
+    pub reason: RevocationReason,
+}
+
+/// Revocation reason codes defined in RFC 5280.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    #[default]
+    Unspecified,
+    KeyCompromise,
+    AffiliationChanged,
+    Superseded,
+    CessationOfOperation,
+}
+
+impl RevocationReason {
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Unspecified => 0,
+            Self::KeyCompromise => 1,
+            Self::AffiliationChanged => 3,
+            Self::Superseded => 4,
+            Self::CessationOfOperation => 5,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
 pub struct AcmeRequest {
+    /// An existing ACME account to use. If omitted, an account with the same
+    /// server URL and contacts is reused, or a new one is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "f9cf7e3faa1aca7e")]
+    pub account_id: Option<ShortId>,
+    #[serde(default)]
     #[schema(example = "https://acme-staging-v02.api.letsencrypt.org/directory")]
     pub server_url: String,
+    #[serde(default)]
     #[schema(example = json!(["mailto:admin@example.com"]))]
     pub contacts: Vec<String>,
     #[serde(default)]
@@ -64,6 +256,53 @@ pub struct AcmeRequest {
     pub acme: Acme,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AcmeAccountInfo {
+    #[schema(value_type = String, example = "f9cf7e3faa1aca7e")]
+    pub id: ShortId,
+    #[schema(example = "https://acme-staging-v02.api.letsencrypt.org/directory")]
+    pub server_url: String,
+    #[schema(example = json!(["mailto:admin@example.com"]))]
+    pub contacts: Vec<String>,
+    pub status: AcmeAccountStatus,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AcmeAccountStatus {
+    #[default]
+    Valid,
+    Deactivated,
+    Revoked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct AcmeAccountRequest {
+    #[schema(example = "https://acme-staging-v02.api.letsencrypt.org/directory")]
+    pub server_url: String,
+    #[schema(example = json!(["mailto:admin@example.com"]))]
+    pub contacts: Vec<String>,
+    #[serde(default)]
+    pub eab: Option<ExternalAccountBinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct AcmeAccountUpdate {
+    #[schema(example = json!(["mailto:admin@example.com"]))]
+    pub contacts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct AcmeAccountImport {
+    /// Account credentials in the JSON format of instant-acme.
+    #[schema(value_type = Object, example = json!({
+        "id": "https://acme-staging-v02.api.letsencrypt.org/acme/acct/123456",
+        "key_pkcs8": "MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQg...",
+        "directory": "https://acme-staging-v02.api.letsencrypt.org/directory"
+    }))]
+    pub credentials: serde_json::Value,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
 pub struct ExternalAccountBinding {
     #[schema(example = "f9cf7e3faa1aca7e6086")]
@@ -93,3 +332,26 @@ where
         .decode(hmac_key.as_bytes())
         .map_err(serde::de::Error::custom)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_acme_config_renew_before_percent() {
+        let config = |renew_before_percent| AcmeConfig {
+            renew_before_percent,
+            ..Default::default()
+        };
+        assert!(config(1).validate().is_ok());
+        assert!(config(99).validate().is_ok());
+        assert!(matches!(
+            config(0).validate(),
+            Err(Error::InvalidRenewBeforePercent { percent: 0 })
+        ));
+        assert!(matches!(
+            config(100).validate(),
+            Err(Error::InvalidRenewBeforePercent { percent: 100 })
+        ));
+    }
+}
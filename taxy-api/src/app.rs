@@ -1,3 +1,4 @@
+use crate::{id::ShortId, subject_name::SubjectName};
 use serde_default::DefaultFromSerde;
 use serde_derive::{Deserialize, Serialize};
 use std::{net::SocketAddr, path::PathBuf, time::Duration};
@@ -18,6 +19,19 @@ pub struct AppConfig {
     #[serde(default = "default_http_challenge_addr")]
     #[schema(value_type = String, example = "0.0.0.0:80")]
     pub http_challenge_addr: SocketAddr,
+
+    #[serde(default = "default_tls_alpn_challenge_addr")]
+    #[schema(value_type = String, example = "0.0.0.0:443")]
+    pub tls_alpn_challenge_addr: SocketAddr,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_demand_tls: Option<OnDemandTlsConfig>,
+
+    #[serde(default)]
+    pub cert_expiry_alert: CertExpiryAlertConfig,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_watch: Option<CertWatchConfig>,
 }
 
 fn default_background_task_interval() -> Duration {
@@ -28,6 +42,78 @@ fn default_http_challenge_addr() -> SocketAddr {
     SocketAddr::from(([0, 0, 0, 0], 80))
 }
 
+fn default_tls_alpn_challenge_addr() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 443))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OnDemandTlsConfig {
+    #[schema(value_type = String, example = "f9cf7e3faa1aca7e")]
+    pub account_id: ShortId,
+
+    #[serde(default = "default_on_demand_challenge_type")]
+    #[schema(example = "tls-alpn-01")]
+    pub challenge_type: String,
+
+    #[serde(default)]
+    #[schema(value_type = [String], example = json!(["*.example.com"]))]
+    pub allowed_names: Vec<SubjectName>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "http://localhost:5555/check")]
+    pub ask_url: Option<String>,
+}
+
+impl OnDemandTlsConfig {
+    pub fn is_allowed_name(&self, name: &str) -> bool {
+        self.allowed_names.iter().any(|pattern| pattern.test(name))
+    }
+}
+
+fn default_on_demand_challenge_type() -> String {
+    "tls-alpn-01".to_string()
+}
+
+#[derive(Debug, DefaultFromSerde, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CertExpiryAlertConfig {
+    #[serde(with = "humantime_serde", default = "default_cert_expiry_threshold")]
+    #[schema(value_type = String, example = "14days")]
+    pub threshold: Duration,
+
+    #[serde(
+        with = "humantime_serde",
+        default = "default_cert_expiry_repeat_interval"
+    )]
+    #[schema(value_type = String, example = "1day")]
+    pub repeat_interval: Duration,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "https://hooks.example.com/services/taxy")]
+    pub webhook_url: Option<String>,
+}
+
+fn default_cert_expiry_threshold() -> Duration {
+    Duration::from_secs(60 * 60 * 24 * 14)
+}
+
+fn default_cert_expiry_repeat_interval() -> Duration {
+    Duration::from_secs(60 * 60 * 24)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CertWatchConfig {
+    #[schema(value_type = [String], example = json!(["/etc/letsencrypt/live"]))]
+    pub dirs: Vec<PathBuf>,
+
+    #[serde(with = "humantime_serde", default = "default_cert_watch_interval")]
+    #[schema(value_type = String, example = "10s")]
+    pub interval: Duration,
+}
+
+fn default_cert_watch_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
 #[derive(Clone, Serialize, ToSchema)]
 pub struct AppInfo {
     #[schema(example = "0.0.0")]
@@ -44,6 +130,10 @@ pub struct AppInfo {
     pub config_path: PathBuf,
     #[schema(value_type = String, example = "/home/taxy/.config/taxy")]
     pub log_path: PathBuf,
+    /// The file that ports, proxies and ACME entries are loaded from, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "/etc/taxy/taxy.toml")]
+    pub config_file: Option<PathBuf>,
 }
 
 #[derive(Debug, DefaultFromSerde, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
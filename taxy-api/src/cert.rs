@@ -28,6 +28,46 @@ impl fmt::Display for CertKind {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAlgorithm {
+    #[default]
+    EcdsaP256,
+    EcdsaP384,
+    Ed25519,
+    Rsa2048,
+    Rsa3072,
+    Rsa4096,
+}
+
+impl KeyAlgorithm {
+    pub fn rsa_bits(&self) -> Option<usize> {
+        match self {
+            Self::Rsa2048 => Some(2048),
+            Self::Rsa3072 => Some(3072),
+            Self::Rsa4096 => Some(4096),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                KeyAlgorithm::EcdsaP256 => "ecdsa_p256",
+                KeyAlgorithm::EcdsaP384 => "ecdsa_p384",
+                KeyAlgorithm::Ed25519 => "ed25519",
+                KeyAlgorithm::Rsa2048 => "rsa2048",
+                KeyAlgorithm::Rsa3072 => "rsa3072",
+                KeyAlgorithm::Rsa4096 => "rsa4096",
+            }
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct CertInfo {
     #[schema(example = "a13e1ecc080e42cfcdd5")]
@@ -47,6 +87,72 @@ pub struct CertInfo {
     pub is_ca: bool,
     pub has_private_key: bool,
     pub metadata: Option<CertMetadata>,
+    /// The directory the certificate is loaded from. Such certificates are read-only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "/etc/letsencrypt/live/example.com")]
+    pub source: Option<String>,
+}
+
+/// A server name served by a TLS port, either configured in the port itself or as a vhost of an HTTP proxy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CertUsage {
+    #[schema(value_type = String, example = "example.com")]
+    pub name: SubjectName,
+    #[schema(example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub port: ShortId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ShortId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CertUsageEntry {
+    #[schema(example = "a13e1ecc080e42cfcdd5")]
+    pub id: ShortId,
+    pub used_by: Vec<CertUsage>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CertUsageGraph {
+    pub certs: Vec<CertUsageEntry>,
+    /// Server names that are not covered by any valid certificate.
+    pub uncovered: Vec<CertUsage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CertExpiryStatus {
+    Expiring,
+    Expired,
+    NotYetValid,
+}
+
+impl fmt::Display for CertExpiryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CertExpiryStatus::Expiring => "expiring",
+                CertExpiryStatus::Expired => "expired",
+                CertExpiryStatus::NotYetValid => "not yet valid",
+            }
+        )
+    }
+}
+
+/// An alert for a certificate in use that is about to expire or is not valid.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CertExpiryAlert {
+    #[schema(example = "a13e1ecc080e42cfcdd5")]
+    pub id: ShortId,
+    pub status: CertExpiryStatus,
+    #[schema(value_type = [String], example = json!(["localhost"]))]
+    pub san: Vec<SubjectName>,
+    #[schema(example = "67090118400")]
+    pub not_after: i64,
+    #[schema(example = "157766400")]
+    pub not_before: i64,
+    pub used_by: Vec<CertUsage>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
@@ -55,6 +161,8 @@ pub struct SelfSignedCertRequest {
     pub san: Vec<SubjectName>,
     #[schema(example = "f9cf7e3faa1aca7e6086")]
     pub ca_cert: Option<ShortId>,
+    #[serde(default)]
+    pub key_algorithm: KeyAlgorithm,
 }
 
 #[derive(DefaultFromSerde, Clone, Serialize, Deserialize, IntoParams)]
@@ -62,6 +170,15 @@ pub struct SelfSignedCertRequest {
 pub struct UploadQuery {
     #[serde(default = "default_cert_kind")]
     pub kind: CertKind,
+    /// Reject the certificate instead of returning warnings when the chain cannot be validated.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CertUploadResult {
+    pub info: CertInfo,
+    pub warnings: Vec<String>,
 }
 
 fn default_cert_kind() -> CertKind {
@@ -104,4 +221,58 @@ pub struct CertPostBody {
     pub chain: String,
     #[schema(format = Binary)]
     pub key: String,
+    #[schema(format = Binary)]
+    pub pkcs12: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CsrRequest {
+    #[serde(default = "default_cert_kind")]
+    pub kind: CertKind,
+    #[schema(value_type = [String], example = json!(["example.com"]))]
+    pub san: Vec<SubjectName>,
+    /// Defaults to the first subject alternative name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "example.com")]
+    pub common_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Example Inc.")]
+    pub organization: Option<String>,
+    #[serde(default)]
+    pub key_algorithm: KeyAlgorithm,
+}
+
+/// A pending certificate signing request. The private key never leaves taxy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CsrInfo {
+    #[schema(value_type = String, example = "f9cf7e3faa1aca7e")]
+    pub id: ShortId,
+    pub kind: CertKind,
+    #[schema(value_type = [String], example = json!(["example.com"]))]
+    pub san: Vec<SubjectName>,
+    #[schema(example = "example.com")]
+    pub common_name: String,
+    pub key_algorithm: KeyAlgorithm,
+    #[schema(example = "1690000000")]
+    pub created_at: i64,
+    /// The PEM encoded CSR to submit to the CA.
+    #[schema(example = "-----BEGIN CERTIFICATE REQUEST-----\n...")]
+    pub csr: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CsrCompleteRequest {
+    /// The PEM encoded certificate chain signed by the CA.
+    #[schema(example = "-----BEGIN CERTIFICATE-----\n...")]
+    pub chain: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Pkcs12ExportRequest {
+    #[schema(example = "passw0rd")]
+    pub password: String,
+    /// Use 3DES and SHA-1 instead of AES-256 and SHA-256 for older Windows versions.
+    #[serde(default)]
+    pub legacy: bool,
 }
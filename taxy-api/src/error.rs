@@ -45,6 +45,21 @@ pub enum Error {
     #[error("failed to read certificate")]
     FailedToReadCertificate,
 
+    #[error("failed to generate key pair")]
+    FailedToGenerateKeyPair,
+
+    #[error("unsupported key algorithm: {algorithm}")]
+    UnsupportedKeyAlgorithm { algorithm: String },
+
+    #[error("renew_before_percent must be between 1 and 99: {percent}")]
+    InvalidRenewBeforePercent { percent: u8 },
+
+    #[error("failed to read pkcs#12 file")]
+    FailedToReadPkcs12,
+
+    #[error("failed to write pkcs#12 file")]
+    FailedToWritePkcs12,
+
     #[error("failed to read private key")]
     FailedToReadPrivateKey,
 
@@ -60,6 +75,72 @@ pub enum Error {
     #[error("acme account creation failed")]
     AcmeAccountCreationFailed,
 
+    #[error("unsupported acme challenge type: {challenge_type}")]
+    UnsupportedAcmeChallengeType { challenge_type: String },
+
+    #[error("wildcard identifier requires dns-01 challenge: {name}")]
+    WildcardRequiresDnsChallenge { name: String },
+
+    #[error("missing DNS challenge config")]
+    DnsChallengeConfigMissing,
+
+    #[error("certificate {id} was not issued by acme {acme_id}")]
+    CertNotIssuedByAcme { id: ShortId, acme_id: ShortId },
+
+    #[error("acme certificate revocation failed: {detail}")]
+    AcmeRevocationFailed { detail: String },
+
+    #[error("invalid acme account credentials")]
+    InvalidAcmeAccountCredentials,
+
+    #[error("acme account request failed: {detail}")]
+    AcmeAccountRequestFailed { detail: String },
+
+    #[error("acme account is deactivated: {id}")]
+    AcmeAccountDeactivated { id: ShortId },
+
+    #[error("acme account is in use: {id}")]
+    AcmeAccountInUse { id: ShortId },
+
+    #[error("certificate is not a ca with a private key: {id}")]
+    InvalidCaCert { id: ShortId },
+
+    #[error("ca already exists: {id}")]
+    CaAlreadyExists { id: ShortId },
+
+    #[error("cannot issue {kind} certificates")]
+    UnsupportedIssueCertKind { kind: String },
+
+    #[error("invalid validity period: {days} days")]
+    InvalidValidityDays { days: u64 },
+
+    #[error("certificate already revoked: {serial}")]
+    CertAlreadyRevoked { serial: String },
+
+    #[error("certificate does not match the key of csr: {id}")]
+    CsrKeyMismatch { id: ShortId },
+
+    #[error("private key does not match the certificate")]
+    CertKeyMismatch,
+
+    #[error("certificate is managed externally: {id}")]
+    ReadOnlyCert { id: ShortId },
+
+    #[error("invalid certificate chain: {reason}")]
+    InvalidCertChain { reason: String },
+
+    #[error("invalid config document: {reason}")]
+    InvalidConfigDocument { reason: String },
+
+    #[error("port is not an http port: {id}")]
+    NotHttpPort { id: ShortId },
+
+    #[error("invalid test request: {reason}")]
+    InvalidTestRequest { reason: String },
+
+    #[error("configuration is managed by a file: {path}")]
+    ManagedByConfigFile { path: String },
+
     #[error("unauthorized")]
     Unauthorized,
 
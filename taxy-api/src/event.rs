@@ -1,6 +1,7 @@
-use crate::acme::AcmeInfo;
+use crate::acme::{AcmeAccountInfo, AcmeInfo, AcmeStatus};
 use crate::app::AppConfig;
-use crate::cert::CertInfo;
+use crate::ca::CaInfo;
+use crate::cert::{CertExpiryAlert, CertInfo};
 use crate::id::ShortId;
 use crate::port::PortStatus;
 use crate::proxy::ProxyStatus;
@@ -11,13 +12,18 @@ use utoipa::ToSchema;
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case", tag = "event")]
 #[non_exhaustive]
+#[allow(clippy::large_enum_variant)]
 pub enum ServerEvent {
     AppConfigUpdated { config: AppConfig },
     PortTableUpdated { entries: Vec<PortEntry> },
     PortStatusUpdated { id: ShortId, status: PortStatus },
     CertsUpdated { entries: Vec<CertInfo> },
+    CertExpiryAlerted { alert: CertExpiryAlert },
     ProxiesUpdated { entries: Vec<ProxyEntry> },
     ProxyStatusUpdated { id: ShortId, status: ProxyStatus },
     AcmeUpdated { entries: Vec<AcmeInfo> },
+    AcmeStatusUpdated { id: ShortId, status: AcmeStatus },
+    AcmeAccountsUpdated { entries: Vec<AcmeAccountInfo> },
+    CaUpdated { entries: Vec<CaInfo> },
     Shutdown,
 }
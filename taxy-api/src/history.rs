@@ -0,0 +1,162 @@
+use crate::{acme::Acme, app::AppConfig, id::ShortId, port::PortEntry, proxy::ProxyEntry};
+use serde_derive::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::{IntoParams, ToSchema};
+
+/// The configuration that can be changed through the API.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConfigSnapshot {
+    #[serde(default)]
+    pub config: AppConfig,
+    #[serde(default)]
+    pub ports: Vec<PortEntry>,
+    #[serde(default)]
+    pub proxies: Vec<ProxyEntry>,
+    #[serde(default)]
+    pub acme: Vec<AcmeSnapshot>,
+    /// IDs of the stored certificates. Certificates are not restored by a rollback.
+    #[serde(default)]
+    #[schema(value_type = [String], example = json!(["f9cf7e3faa1aca7e"]))]
+    pub certs: Vec<ShortId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AcmeSnapshot {
+    #[schema(value_type = String, example = "f9cf7e3faa1aca7e")]
+    pub id: ShortId,
+    #[schema(value_type = String, example = "f9cf7e3faa1aca7e")]
+    pub account_id: ShortId,
+    #[schema(inline)]
+    #[serde(flatten)]
+    pub acme: Acme,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConfigVersion {
+    #[schema(example = 42)]
+    pub version: u64,
+    /// The user who made the change, or `None` for changes made outside the API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "admin")]
+    pub author: Option<String>,
+    #[serde(
+        serialize_with = "serialize_timestamp",
+        deserialize_with = "deserialize_timestamp"
+    )]
+    #[schema(value_type = u64)]
+    pub timestamp: SystemTime,
+    pub snapshot: ConfigSnapshot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConfigVersionInfo {
+    #[schema(example = 42)]
+    pub version: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "admin")]
+    pub author: Option<String>,
+    #[serde(
+        serialize_with = "serialize_timestamp",
+        deserialize_with = "deserialize_timestamp"
+    )]
+    #[schema(value_type = u64)]
+    pub timestamp: SystemTime,
+    /// Changes from the previous version, without the values.
+    pub changes: Vec<ConfigChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConfigChange {
+    pub resource: ConfigResource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub id: Option<String>,
+    pub action: ChangeAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConfigDiffQuery {
+    /// The version to compare with. Defaults to the previous version.
+    pub from: Option<u64>,
+}
+
+/// The format of an exported or imported config document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Yaml,
+    Json,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConfigExportQuery {
+    /// Defaults to TOML.
+    #[serde(default)]
+    pub format: Option<ConfigFormat>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConfigImportQuery {
+    /// Defaults to the format given by the `Content-Type` header, or TOML.
+    #[serde(default)]
+    pub format: Option<ConfigFormat>,
+    /// Validate the document and compute the plan without applying it.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// The changes needed to reach an imported config document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConfigImportPlan {
+    pub changes: Vec<ConfigChange>,
+    /// `false` for a dry run or if the document matches the current config.
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigResource {
+    AppConfig,
+    Port,
+    Proxy,
+    Acme,
+    Cert,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Added,
+    Updated,
+    Removed,
+}
+
+fn serialize_timestamp<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let timestamp = time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| serde::ser::Error::custom("invalid timestamp"))?;
+    serializer.serialize_u64(timestamp.as_secs())
+}
+
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    let timestamp = u64::deserialize(deserializer)?;
+    Ok(UNIX_EPOCH + Duration::from_secs(timestamp))
+}
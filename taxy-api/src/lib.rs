@@ -1,13 +1,17 @@
 pub mod acme;
 pub mod app;
 pub mod auth;
+pub mod ca;
 pub mod cert;
 pub mod error;
 pub mod event;
+pub mod history;
 pub mod id;
 pub mod log;
 pub mod multiaddr;
 pub mod port;
 pub mod proxy;
+pub mod route_test;
 pub mod subject_name;
 pub mod tls;
+pub mod validation;
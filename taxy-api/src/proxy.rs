@@ -2,6 +2,7 @@ use crate::subject_name::SubjectName;
 use crate::{id::ShortId, port::UpstreamServer};
 use serde_default::DefaultFromSerde;
 use serde_derive::{Deserialize, Serialize};
+use std::fmt;
 use url::Url;
 use utoipa::ToSchema;
 
@@ -61,9 +62,78 @@ pub enum ProxyState {
     Unknown,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct ProxyStatus {
     pub state: ProxyState,
+    /// Routes of this proxy that an earlier route takes precedence over.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<RouteConflict>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RouteConflict {
+    pub kind: RouteConflictKind,
+    /// The port both routes are bound to.
+    #[schema(value_type = String, example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub port: ShortId,
+    /// Path of the route that does not match.
+    #[schema(example = "/api")]
+    pub path: String,
+    /// The proxy whose route takes precedence. This may be the proxy itself.
+    #[schema(value_type = String, example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub proxy: ShortId,
+    /// Path of the route that takes precedence.
+    #[schema(example = "/")]
+    pub by_path: String,
+    /// The vhosts that never reach the route, for `overlapping` conflicts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = [String], example = json!(["api.example.com"]))]
+    pub vhosts: Vec<SubjectName>,
+}
+
+impl fmt::Display for RouteConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            port,
+            path,
+            proxy,
+            by_path,
+            ..
+        } = self;
+        match self.kind {
+            RouteConflictKind::Duplicate => write!(
+                f,
+                "route {path} on port {port} is shadowed by an identical route of proxy {proxy}"
+            ),
+            RouteConflictKind::Shadowed => write!(
+                f,
+                "route {path} on port {port} is shadowed by route {by_path} of proxy {proxy}"
+            ),
+            RouteConflictKind::Overlapping => {
+                let vhosts = self
+                    .vhosts
+                    .iter()
+                    .map(|vhost| vhost.to_string())
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "requests for {} to route {path} on port {port} are handled by route {by_path} of proxy {proxy}",
+                    vhosts.join(", ")
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteConflictKind {
+    /// The route has the same vhosts and path as an earlier one.
+    Duplicate,
+    /// Every request the route would match is matched by an earlier route.
+    Shadowed,
+    /// Requests for some of the vhosts of the route are matched by an earlier route.
+    Overlapping,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
@@ -0,0 +1,100 @@
+use crate::id::ShortId;
+use serde_derive::{Deserialize, Serialize};
+use std::net::IpAddr;
+use utoipa::ToSchema;
+
+/// A request to be matched against the routes of a port without sending it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RouteTestRequest {
+    #[serde(default = "default_method")]
+    #[schema(example = "GET")]
+    pub method: String,
+    /// An absolute URL, or a path if the `Host` header is given.
+    #[schema(example = "https://example.com/api/v1/users?page=2")]
+    pub url: String,
+    /// Request headers. The `Host` header defaults to the host of the URL.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<HttpHeader>,
+    /// The TLS server name sent by the client.
+    /// Defaults to the host of the URL if the port terminates TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "example.com")]
+    pub sni: Option<String>,
+    /// The client address used for the `Forwarded` header. Defaults to `127.0.0.1`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "192.0.2.1")]
+    pub client_ip: Option<IpAddr>,
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct HttpHeader {
+    #[schema(example = "accept")]
+    pub name: String,
+    #[schema(example = "application/json")]
+    pub value: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RouteTestResult {
+    /// The proxy that handles the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub proxy: Option<ShortId>,
+    /// Path of the matched route.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "/api")]
+    pub route: Option<String>,
+    /// The URI the request is forwarded to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "http://127.0.0.1:8080/v1/users?page=2")]
+    pub upstream: Option<String>,
+    /// Headers added, removed or replaced before forwarding the request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub header_changes: Vec<HeaderChange>,
+    /// Why the request is not forwarded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejection: Option<RouteTestRejection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct HeaderChange {
+    #[schema(example = "host")]
+    pub name: String,
+    /// The value sent by the client, or `None` if the header is added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "example.com")]
+    pub before: Option<String>,
+    /// The value sent upstream, or `None` if the header is removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "127.0.0.1:8080")]
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RouteTestRejection {
+    pub reason: RouteTestRejectionReason,
+    /// The status code returned to the client.
+    #[schema(example = 502)]
+    pub status: u16,
+    #[schema(example = "no route of the proxies on this port accepts host example.com")]
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteTestRejectionReason {
+    /// Plain HTTP requests to a TLS port are redirected to HTTPS.
+    HttpsRedirect,
+    /// The `Host` header does not match the TLS server name.
+    DomainFronting,
+    /// No active HTTP proxy is bound to the port.
+    NoProxies,
+    /// No proxy on the port accepts the host.
+    HostNotMatched,
+    /// Some proxies accept the host, but none of their routes match the path.
+    PathNotMatched,
+}
@@ -0,0 +1,71 @@
+use crate::{id::ShortId, port::Port, proxy::Proxy};
+use serde_derive::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A port configuration to be validated without applying it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PortValidationRequest {
+    /// The port being updated, or `None` for a new port.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub id: Option<ShortId>,
+    #[schema(inline)]
+    #[serde(flatten)]
+    pub port: Port,
+}
+
+/// A proxy configuration to be validated without applying it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ProxyValidationRequest {
+    /// The proxy being updated, or `None` for a new proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub id: Option<ShortId>,
+    #[schema(inline)]
+    #[serde(flatten)]
+    pub proxy: Proxy,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ValidationResult {
+    /// Issues that would make the update fail or leave it unusable.
+    pub errors: Vec<ValidationIssue>,
+    /// Issues that would not prevent the update but are likely mistakes.
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationResult {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ValidationIssue {
+    pub kind: ValidationIssueKind,
+    /// The field the issue refers to, such as `routes[0].servers[1].url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "listen")]
+    pub field: Option<String>,
+    #[schema(example = "address already in use: 127.0.0.1:8080")]
+    pub message: String,
+    /// The port or proxy that conflicts with this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "c56yqmqcvpmp49n14s2lexxl")]
+    pub conflict: Option<ShortId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationIssueKind {
+    InvalidConfig,
+    AddressConflict,
+    AddressAlreadyInUse,
+    PermissionDenied,
+    AddressNotAvailable,
+    PortNotFound,
+    IncompatiblePort,
+    PortTaken,
+    RouteConflict,
+    UncoveredName,
+}
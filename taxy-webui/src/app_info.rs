@@ -0,0 +1,38 @@
+use crate::{store::AppInfoStore, API_ENDPOINT};
+use gloo_net::http::Request;
+use serde_derive::Deserialize;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Deserialize)]
+struct AppInfo {
+    #[serde(default)]
+    config_file: Option<String>,
+}
+
+/// Returns the config file that ports, proxies and ACME entries are managed by, if any.
+#[hook]
+pub fn use_config_file() -> Option<String> {
+    let (info, dispatcher) = use_store::<AppInfoStore>();
+    use_effect_with_deps(
+        move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(info) = get_app_info().await {
+                    dispatcher.set(AppInfoStore {
+                        config_file: info.config_file,
+                    });
+                }
+            });
+        },
+        (),
+    );
+    info.config_file.clone()
+}
+
+async fn get_app_info() -> Result<AppInfo, gloo_net::Error> {
+    Request::get(&format!("{API_ENDPOINT}/app_info"))
+        .send()
+        .await?
+        .json()
+        .await
+}
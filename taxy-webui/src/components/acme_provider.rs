@@ -1,13 +1,30 @@
 use base64::{engine::general_purpose, Engine};
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 use taxy_api::{
-    acme::{Acme, AcmeConfig, AcmeRequest, ExternalAccountBinding},
+    acme::{Acme, AcmeConfig, AcmeRequest, DnsChallengeConfig, ExternalAccountBinding},
+    cert::KeyAlgorithm,
     subject_name::SubjectName,
 };
+use url::Url;
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
+pub const CHALLENGE_TYPES: &[(&str, &str)] = &[
+    ("http-01", "HTTP"),
+    ("tls-alpn-01", "TLS-ALPN"),
+    ("dns-01", "DNS"),
+];
+
+pub const KEY_ALGORITHMS: &[(KeyAlgorithm, &str)] = &[
+    (KeyAlgorithm::EcdsaP256, "ECDSA P-256"),
+    (KeyAlgorithm::EcdsaP384, "ECDSA P-384"),
+    (KeyAlgorithm::Ed25519, "Ed25519"),
+    (KeyAlgorithm::Rsa2048, "RSA 2048"),
+    (KeyAlgorithm::Rsa3072, "RSA 3072"),
+    (KeyAlgorithm::Rsa4096, "RSA 4096"),
+];
+
 #[derive(Properties, PartialEq)]
 pub struct Props {
     pub name: String,
@@ -46,6 +63,24 @@ pub fn letsencrypt(props: &Props) -> Html {
         }
     });
 
+    let challenge_type = use_state(|| CHALLENGE_TYPES[0].0.to_string());
+    let challenge_type_onchange = Callback::from({
+        let challenge_type = challenge_type.clone();
+        move |event: Event| {
+            let target: HtmlSelectElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            challenge_type.set(target.value());
+        }
+    });
+
+    let dns_webhook_url = use_state(String::new);
+    let dns_webhook_url_onchange = Callback::from({
+        let dns_webhook_url = dns_webhook_url.clone();
+        move |event: Event| {
+            let target: HtmlInputElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            dns_webhook_url.set(target.value());
+        }
+    });
+
     let domain_name = use_state(String::new);
     let domain_name_onchange = Callback::from({
         let domain_name = domain_name.clone();
@@ -55,6 +90,20 @@ pub fn letsencrypt(props: &Props) -> Html {
         }
     });
 
+    let key_algorithm = use_state(KeyAlgorithm::default);
+    let key_algorithm_onchange = Callback::from({
+        let key_algorithm = key_algorithm.clone();
+        move |event: Event| {
+            let target: HtmlSelectElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            if let Some((alg, _)) = KEY_ALGORITHMS
+                .iter()
+                .find(|(alg, _)| alg.to_string() == target.value())
+            {
+                key_algorithm.set(*alg);
+            }
+        }
+    });
+
     let prev_entry =
         use_state::<Result<AcmeRequest, HashMap<String, String>>, _>(|| Err(Default::default()));
     let entry = get_request(
@@ -63,7 +112,10 @@ pub fn letsencrypt(props: &Props) -> Html {
         &eab_kid,
         &eab_hmac_key,
         &email,
+        &challenge_type,
+        &dns_webhook_url,
         &domain_name,
+        *key_algorithm,
         &props.url,
     );
     if entry != *prev_entry {
@@ -85,23 +137,45 @@ pub fn letsencrypt(props: &Props) -> Html {
             <input type="email" placeholder="admin@example.com" onchange={email_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
 
             <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Challenge"}</label>
-            <select class="bg-gray-50 border border-gray-300 text-gray-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5">
-                <option selected={true}>{"HTTP"}</option>
+            <select onchange={challenge_type_onchange} class="bg-gray-50 border border-gray-300 text-gray-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5">
+                { CHALLENGE_TYPES.iter().map(|(value, label)| {
+                    html! {
+                        <option selected={&*challenge_type == value} value={*value}>{label}</option>
+                    }
+                }).collect::<Html>() }
             </select>
 
+            if *challenge_type == "dns-01" {
+                <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"DNS Webhook URL"}</label>
+                <input type="url" placeholder="https://dns.example.com/acme" onchange={dns_webhook_url_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
+            }
+
             <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Domain Name"}</label>
             <input type="taxt" autocapitalize="off" placeholder="example.com" onchange={domain_name_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
+
+            <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Key Algorithm"}</label>
+            <select onchange={key_algorithm_onchange} class="bg-gray-50 border border-gray-300 text-gray-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5">
+                { KEY_ALGORITHMS.iter().filter(|(alg, _)| *alg != KeyAlgorithm::Ed25519).map(|(alg, label)| {
+                    html! {
+                        <option selected={*key_algorithm == *alg} value={alg.to_string()}>{label}</option>
+                    }
+                }).collect::<Html>() }
+            </select>
         </>
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn get_request(
     name: &str,
     eab: bool,
     eab_kid: &str,
     eab_hmac_key: &str,
     email: &str,
+    challenge_type: &str,
+    dns_webhook_url: &str,
     domain_name: &str,
+    key_algorithm: KeyAlgorithm,
     server_url: &str,
 ) -> Result<AcmeRequest, HashMap<String, String>> {
     let mut errors = HashMap::new();
@@ -141,21 +215,39 @@ fn get_request(
             return Err(errors);
         }
     };
+    let dns_challenge = if challenge_type == "dns-01" {
+        if Url::parse(dns_webhook_url).is_err() {
+            errors.insert("dns_webhook_url".to_string(), "Invalid URL".to_string());
+        }
+        Some(DnsChallengeConfig {
+            webhook_url: dns_webhook_url.trim().to_string(),
+            webhook_credentials: None,
+            propagation_delay: Duration::from_secs(30),
+        })
+    } else {
+        None
+    };
+    let acme = Acme {
+        config: AcmeConfig {
+            provider: name.to_string(),
+            key_algorithm,
+            ..Default::default()
+        },
+        identifiers: vec![domain_name],
+        challenge_type: challenge_type.to_string(),
+        dns_challenge,
+    };
+    if let Err(err) = acme.validate() {
+        errors.insert("domain_name".to_string(), err.to_string());
+    }
     if !errors.is_empty() {
         return Err(errors);
     }
     Ok(AcmeRequest {
+        account_id: None,
         server_url: server_url.to_string(),
         contacts: vec![format!("mailto:{}", email)],
         eab,
-        acme: Acme {
-            config: AcmeConfig {
-                active: true,
-                provider: name.to_string(),
-                renewal_days: 60,
-            },
-            identifiers: vec![domain_name],
-            challenge_type: "http-01".to_string(),
-        },
+        acme,
     })
 }
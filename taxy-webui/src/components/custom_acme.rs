@@ -1,12 +1,14 @@
+use super::acme_provider::{CHALLENGE_TYPES, KEY_ALGORITHMS};
 use base64::{engine::general_purpose, Engine};
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 use taxy_api::{
-    acme::{Acme, AcmeConfig, AcmeRequest, ExternalAccountBinding},
+    acme::{Acme, AcmeConfig, AcmeRequest, DnsChallengeConfig, ExternalAccountBinding},
+    cert::KeyAlgorithm,
     subject_name::SubjectName,
 };
 use url::Url;
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
@@ -61,6 +63,24 @@ pub fn custom_acme(props: &Props) -> Html {
         }
     });
 
+    let challenge_type = use_state(|| CHALLENGE_TYPES[0].0.to_string());
+    let challenge_type_onchange = Callback::from({
+        let challenge_type = challenge_type.clone();
+        move |event: Event| {
+            let target: HtmlSelectElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            challenge_type.set(target.value());
+        }
+    });
+
+    let dns_webhook_url = use_state(String::new);
+    let dns_webhook_url_onchange = Callback::from({
+        let dns_webhook_url = dns_webhook_url.clone();
+        move |event: Event| {
+            let target: HtmlInputElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            dns_webhook_url.set(target.value());
+        }
+    });
+
     let domain_name = use_state(String::new);
     let domain_name_onchange = Callback::from({
         let domain_name = domain_name.clone();
@@ -70,12 +90,26 @@ pub fn custom_acme(props: &Props) -> Html {
         }
     });
 
-    let renewal = use_state(|| 60);
+    let renewal = use_state(|| None);
     let renewal_onchange = Callback::from({
         let renewal = renewal.clone();
         move |event: Event| {
             let target: HtmlInputElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
-            renewal.set(target.value().parse().unwrap_or(60));
+            renewal.set(target.value().parse().ok());
+        }
+    });
+
+    let key_algorithm = use_state(KeyAlgorithm::default);
+    let key_algorithm_onchange = Callback::from({
+        let key_algorithm = key_algorithm.clone();
+        move |event: Event| {
+            let target: HtmlSelectElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            if let Some((alg, _)) = KEY_ALGORITHMS
+                .iter()
+                .find(|(alg, _)| alg.to_string() == target.value())
+            {
+                key_algorithm.set(*alg);
+            }
         }
     });
 
@@ -87,8 +121,11 @@ pub fn custom_acme(props: &Props) -> Html {
         &eab_kid,
         &eab_hmac_key,
         &email,
+        &challenge_type,
+        &dns_webhook_url,
         &domain_name,
         *renewal,
+        *key_algorithm,
     );
     if entry != *prev_entry {
         prev_entry.set(entry.clone());
@@ -113,27 +150,49 @@ pub fn custom_acme(props: &Props) -> Html {
             <input type="email" placeholder="admin@example.com" onchange={email_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
 
             <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Challenge"}</label>
-            <select class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5">
-                <option selected={true}>{"HTTP"}</option>
+            <select onchange={challenge_type_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5">
+                { CHALLENGE_TYPES.iter().map(|(value, label)| {
+                    html! {
+                        <option selected={&*challenge_type == value} value={*value}>{label}</option>
+                    }
+                }).collect::<Html>() }
             </select>
 
+            if *challenge_type == "dns-01" {
+                <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"DNS Webhook URL"}</label>
+                <input type="url" placeholder="https://dns.example.com/acme" onchange={dns_webhook_url_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
+            }
+
             <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Domain Name"}</label>
             <input type="taxt" autocapitalize="off" placeholder="example.com" onchange={domain_name_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
 
-            <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Renewal Interval (days)"}</label>
-            <input type="number" value={renewal.to_string()} min="1" onchange={renewal_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
+            <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Renew Before Expiry (days, Optional)"}</label>
+            <input type="number" placeholder="30" min="1" onchange={renewal_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
+
+            <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Key Algorithm"}</label>
+            <select onchange={key_algorithm_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5">
+                { KEY_ALGORITHMS.iter().filter(|(alg, _)| *alg != KeyAlgorithm::Ed25519).map(|(alg, label)| {
+                    html! {
+                        <option selected={*key_algorithm == *alg} value={alg.to_string()}>{label}</option>
+                    }
+                }).collect::<Html>() }
+            </select>
         </>
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn get_request(
     name: &str,
     server_url: &str,
     eab_kid: &str,
     eab_hmac_key: &str,
     email: &str,
+    challenge_type: &str,
+    dns_webhook_url: &str,
     domain_name: &str,
-    renewal: u64,
+    renewal: Option<u64>,
+    key_algorithm: KeyAlgorithm,
 ) -> Result<AcmeRequest, HashMap<String, String>> {
     let mut errors = HashMap::new();
 
@@ -182,21 +241,40 @@ fn get_request(
             return Err(errors);
         }
     };
+    let dns_challenge = if challenge_type == "dns-01" {
+        if Url::parse(dns_webhook_url).is_err() {
+            errors.insert("dns_webhook_url".to_string(), "Invalid URL".to_string());
+        }
+        Some(DnsChallengeConfig {
+            webhook_url: dns_webhook_url.trim().to_string(),
+            webhook_credentials: None,
+            propagation_delay: Duration::from_secs(30),
+        })
+    } else {
+        None
+    };
+    let acme = Acme {
+        config: AcmeConfig {
+            provider: name.trim().to_string(),
+            renew_before_days: renewal,
+            key_algorithm,
+            ..Default::default()
+        },
+        identifiers: vec![domain_name],
+        challenge_type: challenge_type.to_string(),
+        dns_challenge,
+    };
+    if let Err(err) = acme.validate() {
+        errors.insert("domain_name".to_string(), err.to_string());
+    }
     if !errors.is_empty() {
         return Err(errors);
     }
     Ok(AcmeRequest {
+        account_id: None,
         server_url: server_url.to_string(),
         contacts: vec![format!("mailto:{}", email)],
         eab,
-        acme: Acme {
-            config: AcmeConfig {
-                active: true,
-                provider: name.trim().to_string(),
-                renewal_days: renewal,
-            },
-            identifiers: vec![domain_name],
-            challenge_type: "http-01".to_string(),
-        },
+        acme,
     })
 }
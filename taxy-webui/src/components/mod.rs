@@ -5,3 +5,4 @@ pub mod navbar;
 pub mod port_config;
 pub mod proxy_config;
 pub mod tcp_proxy_config;
+pub mod validation_messages;
@@ -0,0 +1,41 @@
+use taxy_api::validation::{ValidationIssue, ValidationResult};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub result: ValidationResult,
+}
+
+#[function_component(ValidationMessages)]
+pub fn validation_messages(props: &Props) -> Html {
+    let issues = props
+        .result
+        .errors
+        .iter()
+        .map(|issue| (issue, "text-red-600"))
+        .chain(
+            props
+                .result
+                .warnings
+                .iter()
+                .map(|issue| (issue, "text-amber-600")),
+        )
+        .collect::<Vec<_>>();
+    if issues.is_empty() {
+        return html! {};
+    }
+    html! {
+        <ul class="mt-4 text-sm list-disc list-inside">
+            { issues.into_iter().map(|(issue, color)| html! {
+                <li class={color}>{format_issue(issue)}</li>
+            }).collect::<Html>() }
+        </ul>
+    }
+}
+
+fn format_issue(issue: &ValidationIssue) -> String {
+    match &issue.field {
+        Some(field) => format!("{field}: {}", issue.message),
+        None => issue.message.clone(),
+    }
+}
@@ -48,7 +48,13 @@ pub fn use_event_subscriber() {
                                 certs.set(CertStore { entries });
                             }
                             ServerEvent::AcmeUpdated { entries } => {
-                                acme.set(AcmeStore { entries });
+                                acme.reduce(|state| {
+                                    AcmeStore {
+                                        entries,
+                                        ..(*state).clone()
+                                    }
+                                    .into()
+                                });
                             }
                             ServerEvent::ProxiesUpdated { entries } => {
                                 proxies.reduce(|state| {
@@ -66,6 +72,13 @@ pub fn use_event_subscriber() {
                                     cloned.into()
                                 });
                             }
+                            ServerEvent::AcmeStatusUpdated { id, status } => {
+                                acme.reduce(|state| {
+                                    let mut cloned = (*state).clone();
+                                    cloned.statuses.insert(id, status);
+                                    cloned.into()
+                                });
+                            }
                             ServerEvent::ProxyStatusUpdated { id, status } => {
                                 proxies.reduce(|state| {
                                     let mut cloned = (*state).clone();
@@ -5,6 +5,7 @@ use console_error_panic_hook::set_once as set_panic_hook;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+mod app_info;
 mod auth;
 mod components;
 mod event;
@@ -1,12 +1,16 @@
+use crate::app_info::use_config_file;
 use crate::auth::use_ensure_auth;
 use crate::pages::Route;
 use crate::store::{AcmeStore, CertStore};
 use crate::API_ENDPOINT;
 use gloo_net::http::Request;
 use serde_derive::{Deserialize, Serialize};
-use taxy_api::acme::AcmeInfo;
-use taxy_api::cert::{CertInfo, CertKind, UploadQuery};
+use std::collections::HashMap;
+use std::time::SystemTime;
+use taxy_api::acme::{AcmeInfo, AcmeOrderStatus, AcmeRevokeRequest, AcmeStatus, RevocationReason};
+use taxy_api::cert::{CertInfo, CertKind, CertUsage, CertUsageGraph, UploadQuery};
 use taxy_api::id::ShortId;
+use time::{macros::format_description, OffsetDateTime};
 use yew::prelude::*;
 use yew_router::prelude::*;
 use yewdux::prelude::*;
@@ -42,6 +46,8 @@ const TABS: [CertsTab; 3] = [CertsTab::Server, CertsTab::Root, CertsTab::Acme];
 #[function_component(CertList)]
 pub fn cert_list() -> Html {
     use_ensure_auth();
+    let config_file = use_config_file();
+    let managed = config_file.is_some();
 
     let location = use_location().unwrap();
     let query: CertsQuery = location.query().unwrap_or_default();
@@ -49,14 +55,28 @@ pub fn cert_list() -> Html {
 
     let (certs, certs_dispatcher) = use_store::<CertStore>();
     let (acme, acme_dispatcher) = use_store::<AcmeStore>();
+    let usage = use_state(CertUsageGraph::default);
+    let usage_cloned = usage.clone();
     use_effect_with_deps(
         move |_| {
             wasm_bindgen_futures::spawn_local(async move {
                 if let Ok(res) = get_cert_list().await {
                     certs_dispatcher.set(CertStore { entries: res });
                 }
+                if let Ok(res) = get_cert_usage().await {
+                    usage_cloned.set(res);
+                }
                 if let Ok(res) = get_acme_list().await {
-                    acme_dispatcher.set(AcmeStore { entries: res });
+                    let mut statuses = HashMap::new();
+                    for entry in &res {
+                        if let Ok(status) = get_acme_status(entry.id).await {
+                            statuses.insert(entry.id, status);
+                        }
+                    }
+                    acme_dispatcher.set(AcmeStore {
+                        entries: res,
+                        statuses,
+                    });
                 }
             });
         },
@@ -81,6 +101,7 @@ pub fn cert_list() -> Html {
                 } else {
                     CertKind::Root
                 },
+                ..Default::default()
             },
         );
     });
@@ -152,6 +173,9 @@ pub fn cert_list() -> Html {
                                 <th scope="col" class="px-4 py-3">
                                     {"Digest"}
                                 </th>
+                                <th scope="col" class="px-4 py-3">
+                                    {"Used By"}
+                                </th>
                                 <th scope="col" class="px-4 py-3">
                                     <span class="sr-only">{"Edit"}</span>
                                 </th>
@@ -167,9 +191,21 @@ pub fn cert_list() -> Html {
                             .join(", ");
 
                         let id = entry.id;
+                        let used_by = usage
+                            .certs
+                            .iter()
+                            .find(|usage| usage.id == id)
+                            .map(|usage| usage.used_by.iter().map(format_usage).collect::<Vec<_>>())
+                            .unwrap_or_default();
+                        let used_count = used_by.len();
                         let delete_onclick = Callback::from(move |e: MouseEvent|  {
                             e.prevent_default();
-                            if gloo_dialogs::confirm(&format!("Are you sure to delete {id}?")) {
+                            let message = if used_count > 0 {
+                                format!("Are you sure to delete {id}?\nThis certificate is used by {used_count} server name(s).")
+                            } else {
+                                format!("Are you sure to delete {id}?")
+                            };
+                            if gloo_dialogs::confirm(&message) {
                                 wasm_bindgen_futures::spawn_local(async move {
                                     let _ = delete_server_cert(id).await;
                                 });
@@ -183,6 +219,24 @@ pub fn cert_list() -> Html {
                             }
                         });
 
+                        let acme_id = entry.metadata.as_ref().map(|meta| meta.acme_id);
+                        let revoke_onclick = Callback::from(move |e: MouseEvent|  {
+                            e.prevent_default();
+                            let Some(acme_id) = acme_id else {
+                                return;
+                            };
+                            let message = format!("Are you sure to revoke {id}?\nReason (unspecified, key_compromise, affiliation_changed, superseded, cessation_of_operation):");
+                            if let Some(reason) = gloo_dialogs::prompt(&message, Some("unspecified")) {
+                                let Ok(reason) = serde_json::from_value::<RevocationReason>(serde_json::Value::String(reason)) else {
+                                    gloo_dialogs::alert("Invalid revocation reason.");
+                                    return;
+                                };
+                                wasm_bindgen_futures::spawn_local(async move {
+                                    let _ = revoke_acme_cert(acme_id, AcmeRevokeRequest { cert_id: id, reason }).await;
+                                });
+                            }
+                        });
+
                             html! {
                                 <tr class="border-b">
                                     <th scope="row" class="px-4 py-4 font-medium text-neutral-900 whitespace-nowrap">
@@ -194,9 +248,23 @@ pub fn cert_list() -> Html {
                                     <td class="px-4 py-4">
                                         {entry.id.to_string()}
                                     </td>
+                                    <td class="px-4 py-4">
+                                        if used_by.is_empty() {
+                                            <span class="text-neutral-400">{"Unused"}</span>
+                                        } else {
+                                            { used_by.into_iter().map(|usage| html! { <div>{usage}</div> }).collect::<Html>() }
+                                        }
+                                    </td>
                                     <td class="px-4 py-4 w-0 whitespace-nowrap" align="right">
                                         <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={download_onclick}>{"Download"}</a>
-                                        <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                        if acme_id.is_some() {
+                                            <a class="cursor-pointer font-medium text-red-600 hover:underline mr-5" onclick={revoke_onclick}>{"Revoke"}</a>
+                                        }
+                                        if let Some(source) = &entry.source {
+                                            <span class="font-medium text-neutral-400" title={source.clone()}>{"External"}</span>
+                                        } else {
+                                            <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                        }
                                     </td>
                                 </tr>
                             }
@@ -205,6 +273,12 @@ pub fn cert_list() -> Html {
                     </table>
                 }
                 </div>
+                if !usage.uncovered.is_empty() {
+                    <div class="mt-4 px-4 py-3 bg-white shadow-sm border border-red-300 lg:rounded-md text-sm text-neutral-600">
+                        <p class="mb-2 font-medium text-red-600">{"Server names without a valid certificate"}</p>
+                        { usage.uncovered.iter().map(|usage| html! { <div>{format_usage(usage)}</div> }).collect::<Html>() }
+                    </div>
+                }
         } else if *tab == CertsTab::Root {
             <div class="relative overflow-x-auto bg-white shadow-sm border border-neutral-300 lg:rounded-md">
                 if cert_list.is_empty() {
@@ -274,6 +348,9 @@ pub fn cert_list() -> Html {
                 }
             </div>
             } else if *tab == CertsTab::Acme {
+                if let Some(path) = &config_file {
+                    <p class="mb-4 px-4 lg:px-0 text-sm text-neutral-500">{format!("Managed by {path}. Edit the file to make changes.")}</p>
+                }
                 <div class="relative overflow-x-auto bg-white shadow-sm border border-neutral-300 lg:rounded-md">
                 if acme_list.is_empty() {
                     <p class="mb-8 mt-8 text-xl font-bold text-neutral-500 px-16 text-center">{"List is empty."}</p>
@@ -287,6 +364,12 @@ pub fn cert_list() -> Html {
                             <th scope="col" class="px-4 py-3">
                                 {"Provider"}
                             </th>
+                            <th scope="col" class="px-4 py-3">
+                                {"Status"}
+                            </th>
+                            <th scope="col" class="px-4 py-3">
+                                {"Next Renewal"}
+                            </th>
                             <th scope="col" class="px-4 py-3" align="center">
                                 {"Active"}
                             </th>
@@ -299,6 +382,15 @@ pub fn cert_list() -> Html {
                     { acme_list.into_iter().map(|entry| {
                         let subject_names = entry.identifiers.join(", ");
                         let provider = entry.config.provider.to_string();
+                        let status = acme.statuses.get(&entry.id).cloned().unwrap_or_default();
+                        let (status_text, tag) = match (status.order, &status.last_error) {
+                            (AcmeOrderStatus::Requesting, _) => ("Requesting".to_string(), "bg-blue-500"),
+                            (AcmeOrderStatus::Validating, _) => ("Validating".to_string(), "bg-blue-500"),
+                            (AcmeOrderStatus::Idle, Some(err)) => (format!("Failed: {err}"), "bg-red-500"),
+                            (AcmeOrderStatus::Idle, None) if status.last_success_at.is_some() => ("Issued".to_string(), "bg-green-500"),
+                            (AcmeOrderStatus::Idle, None) => ("Idle".to_string(), "bg-neutral-500"),
+                        };
+                        let next_renewal = status.next_renewal_at.map(format_time).unwrap_or_else(|| "-".to_string());
 
                         let id = entry.id;
                         let delete_onclick = Callback::from(move |e: MouseEvent|  {
@@ -310,6 +402,15 @@ pub fn cert_list() -> Html {
                             }
                         });
 
+                        let renew_onclick = Callback::from(move |e: MouseEvent|  {
+                            e.prevent_default();
+                            if gloo_dialogs::confirm(&format!("Are you sure to request a new certificate for {id} now?")) {
+                                wasm_bindgen_futures::spawn_local(async move {
+                                    let _ = renew_acme(id).await;
+                                });
+                            }
+                        });
+
                         let id = entry.id;
                         let navigator_cloned = navigator.clone();
                         let log_onclick = Callback::from(move |_|  {
@@ -332,15 +433,26 @@ pub fn cert_list() -> Html {
                                 <td class="px-4 py-4">
                                     {provider}
                                 </td>
+                                <td class="px-4 py-4">
+                                    <div class="flex items-center">
+                                        <div class={classes!("h-2.5", "w-2.5", "rounded-full", "mr-2", "shrink-0", tag)}></div> {status_text}
+                                    </div>
+                                </td>
+                                <td class="px-4 py-4 whitespace-nowrap">
+                                    {next_renewal}
+                                </td>
                                 <td class="px-4 py-4 w-0 whitespace-nowrap" align="center">
                                     <label class="relative inline-flex items-center cursor-pointer mt-1">
-                                        <input {onchange} type="checkbox" checked={active} class="sr-only peer" />
+                                        <input {onchange} type="checkbox" checked={active} disabled={managed} class="sr-only peer" />
                                         <div class="w-9 h-4 bg-gray-200 peer-focus:outline-none peer-focus:ring-4 peer-focus:ring-blue-300 rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-3 after:w-4 after:transition-all peer-checked:bg-blue-600"></div>
                                     </label>
                                 </td>
                                 <td class="px-4 py-4 w-0 whitespace-nowrap" align="right">
+                                    <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={renew_onclick}>{"Renew"}</a>
                                     <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={log_onclick}>{"Log"}</a>
-                                    <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                    if !managed {
+                                        <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                    }
                                 </td>
                             </tr>
                         }
@@ -365,7 +477,7 @@ pub fn cert_list() -> Html {
                         <img src="/assets/icons/cloud-upload.svg" class="w-4 h-4 mr-1" />
                         {"Upload"}
                     </button>
-                } else {
+                } else if !managed {
                     <button onclick={new_acme_onclick} class="inline-flex items-center px-4 py-2 text-sm font-medium text-neutral-500 bg-white border border-neutral-300 rounded-lg hover:bg-neutral-100 focus:z-10 focus:ring-4 focus:ring-neutral-200">
                         <img src="/assets/icons/add.svg" class="w-4 h-4 mr-1" />
                         {"Add"}
@@ -384,6 +496,26 @@ async fn get_cert_list() -> Result<Vec<CertInfo>, gloo_net::Error> {
         .await
 }
 
+async fn get_cert_usage() -> Result<CertUsageGraph, gloo_net::Error> {
+    Request::get(&format!("{API_ENDPOINT}/certs/usage"))
+        .send()
+        .await?
+        .json()
+        .await
+}
+
+fn format_usage(usage: &CertUsage) -> String {
+    match usage.proxy {
+        Some(proxy) => format!(
+            "{} (port {}, proxy {})",
+            usage.name.to_string(),
+            usage.port,
+            proxy
+        ),
+        None => format!("{} (port {})", usage.name.to_string(), usage.port),
+    }
+}
+
 async fn get_acme_list() -> Result<Vec<AcmeInfo>, gloo_net::Error> {
     Request::get(&format!("{API_ENDPOINT}/acme"))
         .send()
@@ -399,6 +531,20 @@ async fn delete_server_cert(id: ShortId) -> Result<(), gloo_net::Error> {
     Ok(())
 }
 
+async fn get_acme_status(id: ShortId) -> Result<AcmeStatus, gloo_net::Error> {
+    Request::get(&format!("{API_ENDPOINT}/acme/{id}/status"))
+        .send()
+        .await?
+        .json()
+        .await
+}
+
+fn format_time(time: SystemTime) -> String {
+    let time: OffsetDateTime = time.into();
+    time.format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+        .unwrap_or_default()
+}
+
 async fn delete_acme(id: ShortId) -> Result<(), gloo_net::Error> {
     Request::delete(&format!("{API_ENDPOINT}/acme/{id}"))
         .send()
@@ -406,6 +552,21 @@ async fn delete_acme(id: ShortId) -> Result<(), gloo_net::Error> {
     Ok(())
 }
 
+async fn renew_acme(id: ShortId) -> Result<(), gloo_net::Error> {
+    Request::post(&format!("{API_ENDPOINT}/acme/{id}/renew"))
+        .send()
+        .await?;
+    Ok(())
+}
+
+async fn revoke_acme_cert(id: ShortId, request: AcmeRevokeRequest) -> Result<(), gloo_net::Error> {
+    Request::post(&format!("{API_ENDPOINT}/acme/{id}/revoke"))
+        .json(&request)?
+        .send()
+        .await?;
+    Ok(())
+}
+
 async fn toggle_acme(id: ShortId) -> Result<(), gloo_net::Error> {
     let mut acme: AcmeInfo = Request::get(&format!("{API_ENDPOINT}/acme/{id}"))
         .send()
@@ -14,6 +14,7 @@ mod port_list;
 mod port_view;
 mod proxy_list;
 mod proxy_view;
+mod route_test;
 mod self_sign;
 mod upload;
 
@@ -34,6 +35,8 @@ pub enum Route {
     PortView { id: ShortId },
     #[at("/ports/:id/log")]
     PortLogView { id: ShortId },
+    #[at("/ports/:id/route_test")]
+    PortRouteTest { id: ShortId },
     #[at("/proxies")]
     Proxies,
     #[at("/proxies/:id/log")]
@@ -61,9 +64,11 @@ impl Route {
     pub fn root(&self) -> Option<Route> {
         match self {
             Route::Home => Some(Route::Home),
-            Route::Ports | Route::NewPort | Route::PortView { .. } | Route::PortLogView { .. } => {
-                Some(Route::Ports)
-            }
+            Route::Ports
+            | Route::NewPort
+            | Route::PortView { .. }
+            | Route::PortLogView { .. }
+            | Route::PortRouteTest { .. } => Some(Route::Ports),
             Route::Certs
             | Route::SelfSign
             | Route::Upload
@@ -87,6 +92,7 @@ pub fn switch(routes: Route) -> Html {
         Route::NewPort => html! { <new_port::NewPort /> },
         Route::PortView { id } => html! { <port_view::PortView {id} /> },
         Route::PortLogView { id } => html! { <log_view::LogView id={id.to_string()} /> },
+        Route::PortRouteTest { id } => html! { <route_test::RouteTest {id} /> },
         Route::Proxies => html! { <proxy_list::ProxyList /> },
         Route::ProxyLogView { id } => html! { <log_view::LogView id={id.to_string()} /> },
         Route::ProxyView { id } => html! { <proxy_view::ProxyView {id} /> },
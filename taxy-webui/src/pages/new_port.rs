@@ -1,9 +1,16 @@
 use crate::{
-    auth::use_ensure_auth, components::port_config::PortConfig, pages::Route, API_ENDPOINT,
+    auth::use_ensure_auth,
+    components::{port_config::PortConfig, validation_messages::ValidationMessages},
+    pages::Route,
+    API_ENDPOINT,
 };
 use gloo_net::http::Request;
 use std::collections::HashMap;
-use taxy_api::port::Port;
+use taxy_api::{
+    id::ShortId,
+    port::Port,
+    validation::{PortValidationRequest, ValidationResult},
+};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -19,6 +26,24 @@ pub fn new_port() -> Html {
             entry_cloned.set(updated);
         });
 
+    let validation = use_state(ValidationResult::default);
+    let validation_cloned = validation.clone();
+    use_effect_with_deps(
+        move |entry| {
+            let validation = validation_cloned;
+            if let Ok(port) = entry.clone() {
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Ok(result) = validate_port(None, port).await {
+                        validation.set(result);
+                    }
+                });
+            } else {
+                validation.set(Default::default());
+            }
+        },
+        (*entry).clone(),
+    );
+
     let navigator_cloned = navigator.clone();
     let cancel_onclick = Callback::from(move |_| {
         navigator_cloned.push(&Route::Ports);
@@ -50,6 +75,7 @@ pub fn new_port() -> Html {
         <>
             <form {onsubmit} class="bg-white shadow-sm p-5 border border-neutral-300 lg:rounded-md">
                 <PortConfig {onchanged} />
+                <ValidationMessages result={(*validation).clone()} />
 
                 <div class="flex mt-4 items-center justify-end">
                     <button type="button" onclick={cancel_onclick} class="mr-2 inline-flex items-center text-neutral-500 bg-neutral-50 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
@@ -72,3 +98,15 @@ async fn create_port(entry: &Port) -> Result<(), gloo_net::Error> {
         .json()
         .await
 }
+
+async fn validate_port(
+    id: Option<ShortId>,
+    port: Port,
+) -> Result<ValidationResult, gloo_net::Error> {
+    Request::post(&format!("{API_ENDPOINT}/ports/validate"))
+        .json(&PortValidationRequest { id, port })?
+        .send()
+        .await?
+        .json()
+        .await
+}
@@ -1,9 +1,16 @@
 use crate::{
-    auth::use_ensure_auth, components::proxy_config::ProxyConfig, pages::Route, API_ENDPOINT,
+    auth::use_ensure_auth,
+    components::{proxy_config::ProxyConfig, validation_messages::ValidationMessages},
+    pages::Route,
+    API_ENDPOINT,
 };
 use gloo_net::http::Request;
 use std::collections::HashMap;
-use taxy_api::proxy::Proxy;
+use taxy_api::{
+    id::ShortId,
+    proxy::Proxy,
+    validation::{ProxyValidationRequest, ValidationResult},
+};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -20,6 +27,24 @@ pub fn new_proxy() -> Html {
             entry_cloned.set(updated);
         });
 
+    let validation = use_state(ValidationResult::default);
+    let validation_cloned = validation.clone();
+    use_effect_with_deps(
+        move |entry| {
+            let validation = validation_cloned;
+            if let Ok(proxy) = entry.clone() {
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Ok(result) = validate_proxy(None, proxy).await {
+                        validation.set(result);
+                    }
+                });
+            } else {
+                validation.set(Default::default());
+            }
+        },
+        (*entry).clone(),
+    );
+
     let navigator_cloned = navigator.clone();
     let cancel_onclick = Callback::from(move |_| {
         navigator_cloned.push(&Route::Proxies);
@@ -51,6 +76,7 @@ pub fn new_proxy() -> Html {
         <>
             <form {onsubmit} class="bg-white shadow-sm p-5 border border-neutral-300 lg:rounded-md">
                 <ProxyConfig {onchanged} />
+                <ValidationMessages result={(*validation).clone()} />
 
                 <div class="flex mt-4 items-center justify-end">
                     <button type="button" onclick={cancel_onclick} class="mr-2 inline-flex items-center text-neutral-500 bg-neutral-50 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
@@ -73,3 +99,15 @@ async fn create_port(entry: &Proxy) -> Result<(), gloo_net::Error> {
         .json()
         .await
 }
+
+async fn validate_proxy(
+    id: Option<ShortId>,
+    proxy: Proxy,
+) -> Result<ValidationResult, gloo_net::Error> {
+    Request::post(&format!("{API_ENDPOINT}/proxies/validate"))
+        .json(&ProxyValidationRequest { id, proxy })?
+        .send()
+        .await?
+        .json()
+        .await
+}
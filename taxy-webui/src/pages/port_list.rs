@@ -1,3 +1,4 @@
+use crate::app_info::use_config_file;
 use crate::auth::use_ensure_auth;
 use crate::pages::Route;
 use crate::store::PortStore;
@@ -15,6 +16,8 @@ use yewdux::prelude::*;
 #[function_component(PortList)]
 pub fn post_list() -> Html {
     use_ensure_auth();
+    let config_file = use_config_file();
+    let managed = config_file.is_some();
 
     let (ports, dispatcher) = use_store::<PortStore>();
     use_effect_with_deps(
@@ -47,6 +50,9 @@ pub fn post_list() -> Html {
     let list = ports.entries.clone();
     html! {
         <>
+            if let Some(path) = &config_file {
+                <p class="mb-4 px-4 lg:px-0 text-sm text-neutral-500">{format!("Managed by {path}. Edit the file to make changes.")}</p>
+            }
             <div class="relative overflow-x-auto bg-white shadow-sm border border-neutral-300 lg:rounded-md">
                 if list.is_empty() {
                     <p class="mb-8 mt-8 text-xl font-bold text-neutral-500 px-16 text-center">{"List is empty. Click 'Add' to configure a new port."}</p>
@@ -105,6 +111,12 @@ pub fn post_list() -> Html {
                                 navigator_cloned.push(&Route::PortLogView {id});
                             });
 
+                            let is_http = entry.port.listen.is_http();
+                            let navigator_cloned = navigator.clone();
+                            let route_test_onclick = Callback::from(move |_|  {
+                                navigator_cloned.push(&Route::PortRouteTest {id});
+                            });
+
                             let reset_onclick = Callback::from(move |e: MouseEvent|  {
                                 e.prevent_default();
                                 if gloo_dialogs::confirm(&format!("Are you sure to reset {id}?\nThis operation closes all existing connections. ")) {
@@ -148,15 +160,22 @@ pub fn post_list() -> Html {
                                     </td>
                                     <td class="px-4 py-4 w-0 whitespace-nowrap" align="center">
                                         <label class="relative inline-flex items-center cursor-pointer mt-1">
-                                            <input {onchange} type="checkbox" checked={active} class="sr-only peer" />
+                                            <input {onchange} type="checkbox" checked={active} disabled={managed} class="sr-only peer" />
                                             <div class="w-9 h-4 bg-gray-200 peer-focus:outline-none peer-focus:ring-4 peer-focus:ring-blue-300 rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-3 after:w-4 after:transition-all peer-checked:bg-blue-600"></div>
                                         </label>
                                     </td>
                                     <td class="px-4 py-4 w-0 whitespace-nowrap" align="right">
-                                        <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={config_onclick}>{"Edit"}</a>
+                                        if !managed {
+                                            <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={config_onclick}>{"Edit"}</a>
+                                        }
                                         <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={log_onclick}>{"Log"}</a>
+                                        if is_http {
+                                            <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={route_test_onclick}>{"Test"}</a>
+                                        }
                                         <a class="cursor-pointer font-medium text-orange-600 hover:underline mr-5" onclick={reset_onclick}>{"Reset"}</a>
-                                        <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                        if !managed {
+                                            <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                        }
                                     </td>
                                 </tr>
                             }
@@ -165,6 +184,7 @@ pub fn post_list() -> Html {
                 </table>
                 }
             </div>
+            if !managed {
             <div class="flex items-center justify-end my-4 px-4 lg:px-0">
                 <div>
                     <button onclick={new_port_onclick} class="inline-flex items-center text-neutral-500 bg-white border border-neutral-300 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2" type="button">
@@ -173,6 +193,7 @@ pub fn post_list() -> Html {
                     </button>
                 </div>
             </div>
+            }
         </>
     }
 }
@@ -1,13 +1,17 @@
 use std::collections::HashMap;
 
 use crate::{
-    auth::use_ensure_auth, components::port_config::PortConfig, pages::Route, store::PortStore,
+    auth::use_ensure_auth,
+    components::{port_config::PortConfig, validation_messages::ValidationMessages},
+    pages::Route,
+    store::PortStore,
     API_ENDPOINT,
 };
 use gloo_net::http::Request;
 use taxy_api::{
     id::ShortId,
     port::{Port, PortEntry},
+    validation::{PortValidationRequest, ValidationResult},
 };
 use yew::prelude::*;
 use yew_router::prelude::*;
@@ -51,6 +55,25 @@ pub fn port_view(props: &Props) -> Html {
             entry_cloned.set(updated);
         });
 
+    let validation = use_state(ValidationResult::default);
+    let validation_cloned = validation.clone();
+    let id = props.id;
+    use_effect_with_deps(
+        move |entry| {
+            let validation = validation_cloned;
+            if let Ok(port) = entry.clone() {
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Ok(result) = validate_port(Some(id), port).await {
+                        validation.set(result);
+                    }
+                });
+            } else {
+                validation.set(Default::default());
+            }
+        },
+        (*entry).clone(),
+    );
+
     let is_loading = use_state(|| false);
 
     let id = props.id;
@@ -80,6 +103,7 @@ pub fn port_view(props: &Props) -> Html {
             if let Some(port_entry) = &*port {
                 <form {onsubmit} class="bg-white shadow-sm p-5 border border-neutral-300 lg:rounded-md">
                     <PortConfig port={port_entry.port.clone()} {onchanged} />
+                    <ValidationMessages result={(*validation).clone()} />
 
                     <div class="flex mt-4 items-center justify-end">
                         <button type="button" onclick={cancel_onclick} class="mr-2 inline-flex items-center text-neutral-500 bg-neutral-50 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
@@ -113,3 +137,15 @@ async fn update_port(id: ShortId, entry: &Port) -> Result<(), gloo_net::Error> {
         .json()
         .await
 }
+
+async fn validate_port(
+    id: Option<ShortId>,
+    port: Port,
+) -> Result<ValidationResult, gloo_net::Error> {
+    Request::post(&format!("{API_ENDPOINT}/ports/validate"))
+        .json(&PortValidationRequest { id, port })?
+        .send()
+        .await?
+        .json()
+        .await
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::app_info::use_config_file;
 use crate::auth::use_ensure_auth;
 use crate::pages::Route;
 use crate::store::{PortStore, ProxyStore};
@@ -15,6 +16,8 @@ use yewdux::prelude::*;
 #[function_component(ProxyList)]
 pub fn proxy_list() -> Html {
     use_ensure_auth();
+    let config_file = use_config_file();
+    let managed = config_file.is_some();
 
     let (ports, ports_dispatcher) = use_store::<PortStore>();
     let (proxies, proxies_dispatcher) = use_store::<ProxyStore>();
@@ -64,6 +67,9 @@ pub fn proxy_list() -> Html {
 
     html! {
         <>
+            if let Some(path) = &config_file {
+                <p class="mb-4 px-4 lg:px-0 text-sm text-neutral-500">{format!("Managed by {path}. Edit the file to make changes.")}</p>
+            }
             <div class="relative overflow-x-auto bg-white shadow-sm border border-neutral-300 lg:rounded-md">
                 if list.is_empty() {
                     <p class="mb-8 mt-8 text-xl font-bold text-neutral-500 px-16 text-center">{"List is empty. Click 'Add' to configure a new proxy."}</p>
@@ -150,17 +156,28 @@ pub fn proxy_list() -> Html {
                                     <div class="flex items-center">
                                         <div class={classes!("h-2.5", "w-2.5", "rounded-full", "bg-green-500", "mr-2", tag)}></div> {status_text}
                                     </div>
+                                    if !status.conflicts.is_empty() {
+                                        <ul class="mt-1 text-xs text-amber-600">
+                                            { status.conflicts.iter().map(|conflict| html! {
+                                                <li>{conflict.to_string()}</li>
+                                            }).collect::<Html>() }
+                                        </ul>
+                                    }
                                 </td>
                                 <td class="px-4 py-4 w-0 whitespace-nowrap" align="center">
                                     <label class="relative inline-flex items-center cursor-pointer mt-1">
-                                        <input {onchange} type="checkbox" checked={active} class="sr-only peer" />
+                                        <input {onchange} type="checkbox" checked={active} disabled={managed} class="sr-only peer" />
                                         <div class="w-9 h-4 bg-gray-200 peer-focus:outline-none peer-focus:ring-4 peer-focus:ring-blue-300 rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-3 after:w-4 after:transition-all peer-checked:bg-blue-600"></div>
                                     </label>
                                 </td>
                                 <td class="px-4 py-4 w-0 whitespace-nowrap" align="right">
-                                    <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={config_onclick}>{"Edit"}</a>
+                                    if !managed {
+                                        <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={config_onclick}>{"Edit"}</a>
+                                    }
                                     <a class="cursor-pointer font-medium text-blue-600 hover:underline mr-5" onclick={log_onclick}>{"Log"}</a>
-                                    <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                    if !managed {
+                                        <a class="cursor-pointer font-medium text-red-600 hover:underline" onclick={delete_onclick}>{"Delete"}</a>
+                                    }
                                 </td>
                             </tr>
                         }
@@ -169,6 +186,7 @@ pub fn proxy_list() -> Html {
                 </table>
             }
             </div>
+            if !managed {
             <div class="flex items-center justify-end my-4 px-4 lg:px-0">
                 <div>
                     <button onclick={new_proxy_onclick} class="inline-flex items-center text-neutral-500 bg-white border border-neutral-300 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2" type="button">
@@ -177,6 +195,7 @@ pub fn proxy_list() -> Html {
                     </button>
                 </div>
             </div>
+            }
         </>
     }
 }
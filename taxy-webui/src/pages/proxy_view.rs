@@ -1,5 +1,8 @@
 use crate::{
-    auth::use_ensure_auth, components::proxy_config::ProxyConfig, pages::Route, store::ProxyStore,
+    auth::use_ensure_auth,
+    components::{proxy_config::ProxyConfig, validation_messages::ValidationMessages},
+    pages::Route,
+    store::ProxyStore,
     API_ENDPOINT,
 };
 use gloo_net::http::Request;
@@ -7,6 +10,7 @@ use std::collections::HashMap;
 use taxy_api::{
     id::ShortId,
     proxy::{Proxy, ProxyEntry},
+    validation::{ProxyValidationRequest, ValidationResult},
 };
 use yew::prelude::*;
 use yew_router::prelude::*;
@@ -50,6 +54,25 @@ pub fn proxy_view(props: &Props) -> Html {
             entry_cloned.set(updated);
         });
 
+    let validation = use_state(ValidationResult::default);
+    let validation_cloned = validation.clone();
+    let id = props.id;
+    use_effect_with_deps(
+        move |entry| {
+            let validation = validation_cloned;
+            if let Ok(proxy) = entry.clone() {
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Ok(result) = validate_proxy(Some(id), proxy).await {
+                        validation.set(result);
+                    }
+                });
+            } else {
+                validation.set(Default::default());
+            }
+        },
+        (*entry).clone(),
+    );
+
     let is_loading = use_state(|| false);
 
     let id = props.id;
@@ -79,6 +102,7 @@ pub fn proxy_view(props: &Props) -> Html {
             if let Some(proxy_entry) = &*site {
                 <form {onsubmit} class="bg-white shadow-sm p-5 border border-neutral-300 lg:rounded-md">
                     <ProxyConfig proxy={proxy_entry.proxy.clone()} {onchanged} />
+                    <ValidationMessages result={(*validation).clone()} />
 
                     <div class="flex mt-4 items-center justify-end">
                         <button type="button" onclick={cancel_onclick} class="mr-2 inline-flex items-center text-neutral-500 bg-neutral-50 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
@@ -112,3 +136,15 @@ async fn update_site(id: ShortId, entry: &Proxy) -> Result<(), gloo_net::Error>
         .json()
         .await
 }
+
+async fn validate_proxy(
+    id: Option<ShortId>,
+    proxy: Proxy,
+) -> Result<ValidationResult, gloo_net::Error> {
+    Request::post(&format!("{API_ENDPOINT}/proxies/validate"))
+        .json(&ProxyValidationRequest { id, proxy })?
+        .send()
+        .await?
+        .json()
+        .await
+}
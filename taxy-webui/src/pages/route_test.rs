@@ -0,0 +1,217 @@
+use crate::{auth::use_ensure_auth, pages::Route, API_ENDPOINT};
+use gloo_net::http::Request;
+use taxy_api::{
+    error::ErrorMessage,
+    id::ShortId,
+    route_test::{HttpHeader, RouteTestRequest, RouteTestResult},
+};
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub id: ShortId,
+}
+
+#[function_component(RouteTest)]
+pub fn route_test(props: &Props) -> Html {
+    use_ensure_auth();
+
+    let navigator = use_navigator().unwrap();
+    let back_onclick = Callback::from(move |_| {
+        navigator.push(&Route::Ports);
+    });
+
+    let method = use_state(|| METHODS[0].to_string());
+    let method_onchange = Callback::from({
+        let method = method.clone();
+        move |event: Event| {
+            let target: HtmlSelectElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            method.set(target.value());
+        }
+    });
+
+    let url = use_state(String::new);
+    let url_onchange = Callback::from({
+        let url = url.clone();
+        move |event: Event| {
+            let target: HtmlInputElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            url.set(target.value());
+        }
+    });
+
+    let headers = use_state(String::new);
+    let headers_onchange = Callback::from({
+        let headers = headers.clone();
+        move |event: Event| {
+            let target: HtmlTextAreaElement =
+                event.target().unwrap_throw().dyn_into().unwrap_throw();
+            headers.set(target.value());
+        }
+    });
+
+    let sni = use_state(String::new);
+    let sni_onchange = Callback::from({
+        let sni = sni.clone();
+        move |event: Event| {
+            let target: HtmlInputElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            sni.set(target.value());
+        }
+    });
+
+    let result = use_state(|| Option::<Result<RouteTestResult, String>>::None);
+    let is_loading = use_state(|| false);
+
+    let id = props.id;
+    let request = RouteTestRequest {
+        method: (*method).clone(),
+        url: url.trim().to_string(),
+        headers: parse_headers(&headers),
+        sni: Some(sni.trim().to_string()).filter(|sni| !sni.is_empty()),
+        client_ip: None,
+    };
+    let result_cloned = result.clone();
+    let is_loading_cloned = is_loading;
+    let onsubmit = Callback::from(move |event: SubmitEvent| {
+        event.prevent_default();
+        if *is_loading_cloned {
+            return;
+        }
+        let request = request.clone();
+        let result = result_cloned.clone();
+        let is_loading_cloned = is_loading_cloned.clone();
+        is_loading_cloned.set(true);
+        wasm_bindgen_futures::spawn_local(async move {
+            result.set(Some(test_route(id, &request).await));
+            is_loading_cloned.set(false);
+        });
+    });
+
+    html! {
+        <>
+            <form {onsubmit} class="bg-white shadow-sm p-5 border border-neutral-300 lg:rounded-md">
+                <label class="block mb-2 text-sm font-medium text-neutral-900">{"Request"}</label>
+                <div class="flex">
+                    <select onchange={method_onchange} class="bg-gray-50 border border-gray-300 text-gray-900 text-sm rounded-l-lg focus:ring-blue-500 focus:border-blue-500 block p-2.5">
+                        { METHODS.iter().map(|m| {
+                            html! {
+                                <option selected={*method == *m} value={m.to_string()}>{m}</option>
+                            }
+                        }).collect::<Html>() }
+                    </select>
+                    <input type="text" value={url.to_string()} onchange={url_onchange} class="bg-neutral-50 border border-l-0 border-neutral-300 text-neutral-900 text-sm rounded-r-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" placeholder="https://example.com/api/users" />
+                </div>
+
+                <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Headers"}</label>
+                <textarea value={headers.to_string()} onchange={headers_onchange} rows="3" class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5 font-mono" placeholder="x-forwarded-for: 192.0.2.1" />
+                <p class="mt-2 text-sm text-neutral-500">{"One header per line. The Host header defaults to the host of the URL."}</p>
+
+                <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"TLS Server Name (SNI)"}</label>
+                <input type="text" value={sni.to_string()} onchange={sni_onchange} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" placeholder="example.com" />
+                <p class="mt-2 text-sm text-neutral-500">{"Only used by ports with TLS termination. Defaults to the host of the URL."}</p>
+
+                <div class="flex mt-4 items-center justify-end">
+                    <button type="button" onclick={back_onclick} class="mr-2 inline-flex items-center text-neutral-500 bg-neutral-50 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
+                        {"Back"}
+                    </button>
+                    <button type="submit" disabled={url.trim().is_empty()} class="inline-flex items-center text-neutral-500 bg-neutral-50 border border-neutral-300 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
+                        {"Test"}
+                    </button>
+                </div>
+            </form>
+
+            if let Some(result) = &*result {
+                <div class="mt-4 bg-white shadow-sm p-5 border border-neutral-300 lg:rounded-md text-sm">
+                    { render_result(result) }
+                </div>
+            }
+        </>
+    }
+}
+
+fn render_result(result: &Result<RouteTestResult, String>) -> Html {
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => return html! { <p class="text-red-600">{err}</p> },
+    };
+    if let Some(rejection) = &result.rejection {
+        return html! {
+            <p class="text-red-600">{format!("{}: {}", rejection.status, rejection.message)}</p>
+        };
+    }
+    let row = |label: &str, value: Html| {
+        html! {
+            <tr class="border-b">
+                <th scope="row" class="px-4 py-2 font-medium text-neutral-900 whitespace-nowrap w-0">{label}</th>
+                <td class="px-4 py-2 font-mono break-all">{value}</td>
+            </tr>
+        }
+    };
+    let proxy = result.proxy.map(|id| {
+        html! { <Link<Route> to={Route::ProxyView { id }} classes="text-blue-600 hover:underline">{id.to_string()}</Link<Route>> }
+    });
+    html! {
+        <>
+            <table class="w-full text-left text-neutral-600">
+                <tbody>
+                    { row("Proxy", proxy.unwrap_or_default()) }
+                    { row("Route", html! { result.route.clone().unwrap_or_default() }) }
+                    { row("Upstream", html! { result.upstream.clone().unwrap_or_else(|| "-".into()) }) }
+                </tbody>
+            </table>
+            if !result.header_changes.is_empty() {
+                <table class="w-full mt-4 text-left text-neutral-600">
+                    <thead class="text-xs text-neutral-800 uppercase border-b border-neutral-300">
+                        <tr>
+                            <th scope="col" class="px-4 py-2">{"Header"}</th>
+                            <th scope="col" class="px-4 py-2">{"Sent by Client"}</th>
+                            <th scope="col" class="px-4 py-2">{"Sent Upstream"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { result.header_changes.iter().map(|change| html! {
+                            <tr class="border-b font-mono">
+                                <td class="px-4 py-2">{&change.name}</td>
+                                <td class="px-4 py-2 break-all text-red-600">{change.before.clone().unwrap_or_default()}</td>
+                                <td class="px-4 py-2 break-all text-green-600">{change.after.clone().unwrap_or_default()}</td>
+                            </tr>
+                        }).collect::<Html>() }
+                    </tbody>
+                </table>
+            }
+        </>
+    }
+}
+
+fn parse_headers(headers: &str) -> Vec<HttpHeader> {
+    headers
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| HttpHeader {
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+        })
+        .collect()
+}
+
+async fn test_route(id: ShortId, request: &RouteTestRequest) -> Result<RouteTestResult, String> {
+    let res = Request::post(&format!("{API_ENDPOINT}/ports/{id}/route-test"))
+        .json(request)
+        .map_err(|err| err.to_string())?
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if res.ok() {
+        res.json().await.map_err(|err| err.to_string())
+    } else {
+        Err(res
+            .json::<ErrorMessage>()
+            .await
+            .map(|err| err.message)
+            .unwrap_or_else(|err| err.to_string()))
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     auth::use_ensure_auth,
+    components::acme_provider::KEY_ALGORITHMS,
     pages::{
         cert_list::{CertsQuery, CertsTab},
         Route,
@@ -9,7 +10,7 @@ use crate::{
 use gloo_net::http::Request;
 use std::{collections::HashMap, str::FromStr};
 use taxy_api::{
-    cert::{CertInfo, CertKind, SelfSignedCertRequest},
+    cert::{CertInfo, CertKind, KeyAlgorithm, SelfSignedCertRequest},
     id::ShortId,
     subject_name::SubjectName,
 };
@@ -51,6 +52,20 @@ pub fn self_sign() -> Html {
         }
     });
 
+    let key_algorithm = use_state(KeyAlgorithm::default);
+    let key_algorithm_onchange = Callback::from({
+        let key_algorithm = key_algorithm.clone();
+        move |event: Event| {
+            let target: HtmlSelectElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            if let Some((alg, _)) = KEY_ALGORITHMS
+                .iter()
+                .find(|(alg, _)| alg.to_string() == target.value())
+            {
+                key_algorithm.set(*alg);
+            }
+        }
+    });
+
     let ca_cert_list = use_state(Vec::<CertInfo>::new);
     let ca_cert_list_cloned = ca_cert_list.clone();
     let ca_cert_cloned = ca_cert.clone();
@@ -74,7 +89,7 @@ pub fn self_sign() -> Html {
 
     let validation = use_state(|| false);
 
-    let entry = get_request(&san, *ca_cert);
+    let entry = get_request(&san, *ca_cert, *key_algorithm);
     let is_loading = use_state(|| false);
 
     let entry_cloned = entry;
@@ -120,6 +135,15 @@ pub fn self_sign() -> Html {
                     <option selected={ca_cert.to_string() == "generate"} value={"generate"}>{"Generate"}</option>
                 </select>
 
+                <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Key Algorithm"}</label>
+                <select onchange={key_algorithm_onchange} class="bg-gray-50 border border-gray-300 text-gray-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5">
+                    { KEY_ALGORITHMS.iter().map(|(alg, label)| {
+                        html! {
+                            <option selected={*key_algorithm == *alg} value={alg.to_string()}>{label}</option>
+                        }
+                    }).collect::<Html>() }
+                </select>
+
                 <div class="flex mt-4 items-center justify-end">
                     <button type="button" onclick={cancel_onclick} class="mr-2 inline-flex items-center text-neutral-500 bg-neutral-50 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
                         {"Cancel"}
@@ -136,6 +160,7 @@ pub fn self_sign() -> Html {
 fn get_request(
     san: &str,
     ca_cert: ShortId,
+    key_algorithm: KeyAlgorithm,
 ) -> Result<SelfSignedCertRequest, HashMap<String, String>> {
     let mut errors = HashMap::new();
     let mut names = Vec::new();
@@ -160,6 +185,7 @@ fn get_request(
             } else {
                 Some(ca_cert)
             },
+            key_algorithm,
         })
     } else {
         Err(errors)
@@ -49,10 +49,30 @@ pub fn upload() -> Html {
         }
     });
 
+    let pkcs12 = use_state(|| Option::<web_sys::File>::None);
+    let pkcs12_onchange = Callback::from({
+        let pkcs12 = pkcs12.clone();
+        move |event: Event| {
+            let target: HtmlInputElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            pkcs12.set(target.files().and_then(|file| file.get(0)));
+        }
+    });
+
+    let password = use_state(String::new);
+    let password_oninput = Callback::from({
+        let password = password.clone();
+        move |event: InputEvent| {
+            let target: HtmlInputElement = event.target().unwrap_throw().dyn_into().unwrap_throw();
+            password.set(target.value());
+        }
+    });
+
     let is_loading = use_state(|| false);
 
     let chain_cloned = (*chain).clone();
     let key_cloned = (*key).clone();
+    let pkcs12_cloned = (*pkcs12).clone();
+    let password_cloned = (*password).clone();
     let is_loading_cloned = is_loading;
     let onsubmit = Callback::from(move |event: SubmitEvent| {
         event.prevent_default();
@@ -62,14 +82,20 @@ pub fn upload() -> Html {
         let navigator = navigator.clone();
         let chain_cloned = chain_cloned.clone();
         let key_cloned = key_cloned.clone();
+        let pkcs12_cloned = pkcs12_cloned.clone();
+        let password_cloned = password_cloned.clone();
         let is_loading_cloned = is_loading_cloned.clone();
-        if let Some(chain) = chain_cloned {
+        if pkcs12_cloned.is_some() || chain_cloned.is_some() {
             is_loading_cloned.set(true);
             wasm_bindgen_futures::spawn_local(async move {
-                if upload_cert(&chain, key_cloned.as_ref(), query.kind)
-                    .await
-                    .is_ok()
-                {
+                let result = if let Some(pkcs12) = pkcs12_cloned {
+                    upload_pkcs12(&pkcs12, &password_cloned, query.kind).await
+                } else if let Some(chain) = chain_cloned {
+                    upload_cert(&chain, key_cloned.as_ref(), query.kind).await
+                } else {
+                    Ok(())
+                };
+                if result.is_ok() {
                     let _ = navigator.push_with_query(
                         &Route::Certs,
                         &CertsQuery {
@@ -86,7 +112,8 @@ pub fn upload() -> Html {
         }
     });
 
-    let uploadable = chain.is_some() && (query.kind == CertKind::Root || key.is_some());
+    let uploadable =
+        pkcs12.is_some() || (chain.is_some() && (query.kind == CertKind::Root || key.is_some()));
 
     html! {
         <>
@@ -97,6 +124,12 @@ pub fn upload() -> Html {
                 <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Private Key"}</label>
                 <input onchange={key_onchange} class="block w-full text-sm text-neutral-900 border border-neutral-300 rounded-lg cursor-pointer bg-neutral-50 focus:outline-none file:bg-transparent file:border-0 file:bg-neutral-100 file:mr-4 file:py-3 file:px-4" type="file" />
 
+                <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"Or PKCS#12 File (.pfx, .p12)"}</label>
+                <input onchange={pkcs12_onchange} class="block w-full text-sm text-neutral-900 border border-neutral-300 rounded-lg cursor-pointer bg-neutral-50 focus:outline-none file:bg-transparent file:border-0 file:bg-neutral-100 file:mr-4 file:py-3 file:px-4" type="file" accept=".pfx,.p12" />
+
+                <label class="block mt-4 mb-2 text-sm font-medium text-neutral-900">{"PKCS#12 Password"}</label>
+                <input type="password" oninput={password_oninput} class="bg-neutral-50 border border-neutral-300 text-neutral-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block w-full p-2.5" />
+
                 <div class="flex mt-4 items-center justify-end">
                     <button type="button" onclick={cancel_onclick} class="mr-2 inline-flex items-center text-neutral-500 bg-neutral-50 focus:outline-none hover:bg-neutral-100 focus:ring-4 focus:ring-neutral-200 font-medium rounded-lg text-sm px-4 py-2">
                         {"Cancel"}
@@ -129,3 +162,21 @@ async fn upload_cert(
 
     Ok(())
 }
+
+async fn upload_pkcs12(
+    pkcs12: &web_sys::File,
+    password: &str,
+    kind: CertKind,
+) -> Result<(), gloo_net::Error> {
+    let form_data = web_sys::FormData::new().unwrap();
+    form_data.append_with_blob("pkcs12", pkcs12).unwrap();
+    form_data.append_with_str("password", password).unwrap();
+
+    Request::post(&format!("{API_ENDPOINT}/certs/upload"))
+        .query([("kind", kind.to_string())])
+        .body(form_data)?
+        .send()
+        .await?;
+
+    Ok(())
+}
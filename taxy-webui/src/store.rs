@@ -1,7 +1,7 @@
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use taxy_api::{
-    acme::AcmeInfo,
+    acme::{AcmeInfo, AcmeStatus},
     cert::CertInfo,
     id::ShortId,
     port::{PortEntry, PortStatus},
@@ -15,6 +15,11 @@ pub struct SessionStore {
     pub token: Option<String>,
 }
 
+#[derive(Default, Clone, PartialEq, Store)]
+pub struct AppInfoStore {
+    pub config_file: Option<String>,
+}
+
 #[derive(Default, Clone, PartialEq, Store)]
 pub struct PortStore {
     pub entries: Vec<PortEntry>,
@@ -35,4 +40,5 @@ pub struct CertStore {
 #[derive(Default, Clone, PartialEq, Store)]
 pub struct AcmeStore {
     pub entries: Vec<AcmeInfo>,
+    pub statuses: HashMap<ShortId, AcmeStatus>,
 }
@@ -1,7 +1,7 @@
 use super::{with_state, AppState};
 use crate::server::rpc::acme::*;
 use taxy_api::{
-    acme::{AcmeConfig, AcmeRequest},
+    acme::{AcmeConfig, AcmeRequest, AcmeRevokeRequest},
     id::ShortId,
 };
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
@@ -18,6 +18,31 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
             .and_then(get),
     );
 
+    let api_status = warp::get().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("status"))
+            .and(warp::path::end())
+            .and_then(status),
+    );
+
+    let api_renew = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("renew"))
+            .and(warp::path::end())
+            .and_then(renew),
+    );
+
+    let api_revoke = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("revoke"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(revoke),
+    );
+
     let api_add = warp::post().and(
         with_state(app_state.clone())
             .and(warp::body::json())
@@ -41,7 +66,16 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
     );
 
     warp::path("acme")
-        .and(api_delete.or(api_get).or(api_add).or(api_put).or(api_list))
+        .and(
+            api_delete
+                .or(api_get)
+                .or(api_status)
+                .or(api_renew)
+                .or(api_revoke)
+                .or(api_add)
+                .or(api_put)
+                .or(api_list),
+        )
         .boxed()
 }
 
@@ -81,6 +115,74 @@ pub async fn get(state: AppState, id: ShortId) -> Result<impl Reply, Rejection>
     Ok(warp::reply::json(&state.call(GetAcme { id }).await?))
 }
 
+/// Get the status of an ACME configuration.
+#[utoipa::path(
+    get,
+    path = "/api/acme/{id}/status",
+    params(
+        ("id" = String, Path, description = "ACME ID")
+    ),
+    responses(
+        (status = 200, body = AcmeStatus),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn status(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetAcmeStatus { id }).await?))
+}
+
+/// Request a new certificate immediately, regardless of the renewal schedule.
+#[utoipa::path(
+    post,
+    path = "/api/acme/{id}/renew",
+    params(
+        ("id" = String, Path, description = "ACME ID")
+    ),
+    responses(
+        (status = 200),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn renew(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(RenewAcme { id }).await?))
+}
+
+/// Revoke a certificate issued by an ACME configuration and remove it.
+#[utoipa::path(
+    post,
+    path = "/api/acme/{id}/revoke",
+    params(
+        ("id" = String, Path, description = "ACME ID")
+    ),
+    request_body = AcmeRevokeRequest,
+    responses(
+        (status = 200),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn revoke(
+    state: AppState,
+    id: ShortId,
+    request: AcmeRevokeRequest,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(RevokeAcmeCert { id, request }).await?,
+    ))
+}
+
 /// Register an ACME configuration.
 #[utoipa::path(
     post,
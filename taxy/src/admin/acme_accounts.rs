@@ -0,0 +1,251 @@
+use super::{with_state, AppState};
+use crate::server::rpc::acme_accounts::*;
+use taxy_api::{
+    acme::{AcmeAccountImport, AcmeAccountRequest, AcmeAccountUpdate},
+    id::ShortId,
+};
+use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
+
+pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
+    let api_list = warp::get()
+        .and(warp::path::end())
+        .and(with_state(app_state.clone()).and_then(list));
+
+    let api_get = warp::get().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(get),
+    );
+
+    let api_add = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(add),
+    );
+
+    let api_import = warp::post().and(warp::path("import")).and(
+        with_state(app_state.clone())
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(import),
+    );
+
+    let api_put = warp::put().and(
+        with_state(app_state.clone())
+            .and(warp::body::json())
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(put),
+    );
+
+    let api_key_rollover = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("key_rollover"))
+            .and(warp::path::end())
+            .and_then(key_rollover),
+    );
+
+    let api_deactivate = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("deactivate"))
+            .and(warp::path::end())
+            .and_then(deactivate),
+    );
+
+    let api_delete = warp::delete().and(
+        with_state(app_state)
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(delete),
+    );
+
+    warp::path("acme_accounts")
+        .and(
+            api_delete
+                .or(api_get)
+                .or(api_import)
+                .or(api_key_rollover)
+                .or(api_deactivate)
+                .or(api_add)
+                .or(api_put)
+                .or(api_list),
+        )
+        .boxed()
+}
+
+/// List ACME accounts.
+#[utoipa::path(
+    get,
+    path = "/api/acme_accounts",
+    responses(
+        (status = 200, body = [AcmeAccountInfo]),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn list(state: AppState) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetAcmeAccountList).await?))
+}
+
+/// Get an ACME account.
+#[utoipa::path(
+    get,
+    path = "/api/acme_accounts/{id}",
+    params(
+        ("id" = String, Path, description = "ACME account ID")
+    ),
+    responses(
+        (status = 200, body = AcmeAccountInfo),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn get(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetAcmeAccount { id }).await?))
+}
+
+/// Register a new ACME account.
+#[utoipa::path(
+    post,
+    path = "/api/acme_accounts",
+    request_body = AcmeAccountRequest,
+    responses(
+        (status = 200, body = AcmeAccountInfo),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn add(state: AppState, request: AcmeAccountRequest) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(AddAcmeAccount { request }).await?,
+    ))
+}
+
+/// Import an existing ACME account from its credentials.
+#[utoipa::path(
+    post,
+    path = "/api/acme_accounts/import",
+    request_body = AcmeAccountImport,
+    responses(
+        (status = 200, body = AcmeAccountInfo),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn import(state: AppState, request: AcmeAccountImport) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(ImportAcmeAccount { request }).await?,
+    ))
+}
+
+/// Update the contacts of an ACME account.
+#[utoipa::path(
+    put,
+    path = "/api/acme_accounts/{id}",
+    params(
+        ("id" = String, Path, description = "ACME account ID")
+    ),
+    request_body = AcmeAccountUpdate,
+    responses(
+        (status = 200, body = AcmeAccountInfo),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn put(
+    state: AppState,
+    request: AcmeAccountUpdate,
+    id: ShortId,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(UpdateAcmeAccount { id, request }).await?,
+    ))
+}
+
+/// Replace the key of an ACME account.
+#[utoipa::path(
+    post,
+    path = "/api/acme_accounts/{id}/key_rollover",
+    params(
+        ("id" = String, Path, description = "ACME account ID")
+    ),
+    responses(
+        (status = 200, body = AcmeAccountInfo),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn key_rollover(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(RolloverAcmeAccountKey { id }).await?,
+    ))
+}
+
+/// Deactivate an ACME account. This cannot be undone.
+#[utoipa::path(
+    post,
+    path = "/api/acme_accounts/{id}/deactivate",
+    params(
+        ("id" = String, Path, description = "ACME account ID")
+    ),
+    responses(
+        (status = 200, body = AcmeAccountInfo),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn deactivate(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(DeactivateAcmeAccount { id }).await?,
+    ))
+}
+
+/// Delete an ACME account that is not used by any ACME configuration.
+#[utoipa::path(
+    delete,
+    path = "/api/acme_accounts/{id}",
+    params(
+        ("id" = String, Path, description = "ACME account ID")
+    ),
+    responses(
+        (status = 200),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn delete(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(DeleteAcmeAccount { id }).await?,
+    ))
+}
@@ -0,0 +1,286 @@
+use super::{with_state, AppState};
+use crate::server::rpc::ca::*;
+use hyper::Response;
+use taxy_api::{
+    ca::{CaImport, CaRequest, CaRevokeRequest, IssueCertRequest},
+    id::ShortId,
+};
+use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
+
+pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
+    let api_list = warp::get()
+        .and(warp::path::end())
+        .and(with_state(app_state.clone()).and_then(list));
+
+    let api_get = warp::get().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(get),
+    );
+
+    let api_create = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(create),
+    );
+
+    let api_import = warp::post().and(warp::path("import")).and(
+        with_state(app_state.clone())
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(import),
+    );
+
+    let api_delete = warp::delete().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(delete),
+    );
+
+    let api_issue = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("issue"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(issue),
+    );
+
+    let api_issued = warp::get().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("issued"))
+            .and(warp::path::end())
+            .and_then(issued),
+    );
+
+    let api_revoke = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("revoke"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(revoke),
+    );
+
+    // The CRL is published without authentication so that relying parties can fetch it.
+    let api_crl = warp::get().and(
+        warp::any()
+            .map(move || app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("crl"))
+            .and(warp::path::end())
+            .and_then(crl),
+    );
+
+    warp::path("ca")
+        .and(
+            api_crl
+                .or(api_delete)
+                .or(api_issued)
+                .or(api_get)
+                .or(api_import)
+                .or(api_issue)
+                .or(api_revoke)
+                .or(api_create)
+                .or(api_list),
+        )
+        .boxed()
+}
+
+/// List private CAs.
+#[utoipa::path(
+    get,
+    path = "/api/ca",
+    responses(
+        (status = 200, body = [CaInfo]),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn list(state: AppState) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetCaList).await?))
+}
+
+/// Get a private CA.
+#[utoipa::path(
+    get,
+    path = "/api/ca/{id}",
+    params(
+        ("id" = String, Path, description = "CA certificate ID")
+    ),
+    responses(
+        (status = 200, body = CaInfo),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn get(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetCa { id }).await?))
+}
+
+/// Create a new private CA.
+#[utoipa::path(
+    post,
+    path = "/api/ca",
+    request_body = CaRequest,
+    responses(
+        (status = 200, body = CaInfo),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn create(state: AppState, request: CaRequest) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(CreateCa { request }).await?))
+}
+
+/// Import an existing CA certificate with its private key.
+#[utoipa::path(
+    post,
+    path = "/api/ca/import",
+    request_body = CaImport,
+    responses(
+        (status = 200, body = CaInfo),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn import(state: AppState, request: CaImport) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(ImportCa { request }).await?))
+}
+
+/// Stop managing a private CA. The CA certificate itself is kept.
+#[utoipa::path(
+    delete,
+    path = "/api/ca/{id}",
+    params(
+        ("id" = String, Path, description = "CA certificate ID")
+    ),
+    responses(
+        (status = 200),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn delete(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(DeleteCa { id }).await?))
+}
+
+/// Issue a server or client certificate.
+#[utoipa::path(
+    post,
+    path = "/api/ca/{id}/issue",
+    params(
+        ("id" = String, Path, description = "CA certificate ID")
+    ),
+    request_body = IssueCertRequest,
+    responses(
+        (status = 200, body = CertInfo),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn issue(
+    state: AppState,
+    id: ShortId,
+    request: IssueCertRequest,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(IssueCert { id, request }).await?,
+    ))
+}
+
+/// List the certificates issued by a private CA.
+#[utoipa::path(
+    get,
+    path = "/api/ca/{id}/issued",
+    params(
+        ("id" = String, Path, description = "CA certificate ID")
+    ),
+    responses(
+        (status = 200, body = [IssuedCert]),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn issued(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetIssuedCerts { id }).await?))
+}
+
+/// Revoke a certificate issued by a private CA.
+#[utoipa::path(
+    post,
+    path = "/api/ca/{id}/revoke",
+    params(
+        ("id" = String, Path, description = "CA certificate ID")
+    ),
+    request_body = CaRevokeRequest,
+    responses(
+        (status = 200, body = IssuedCert),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn revoke(
+    state: AppState,
+    id: ShortId,
+    request: CaRevokeRequest,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state
+            .call(RevokeIssuedCert {
+                id,
+                serial: request.serial,
+                reason: request.reason,
+            })
+            .await?,
+    ))
+}
+
+/// Download the DER encoded CRL of a private CA.
+#[utoipa::path(
+    get,
+    path = "/api/ca/{id}/crl",
+    params(
+        ("id" = String, Path, description = "CA certificate ID")
+    ),
+    responses(
+        (status = 200, body = Vec<u8>, content_type = "application/pkix-crl"),
+        (status = 404),
+    )
+)]
+pub async fn crl(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    let crl = state.call(GetCrl { id }).await?;
+    Ok(Response::builder()
+        .header("Content-Type", "application/pkix-crl")
+        .body(*crl)
+        .unwrap())
+}
@@ -3,7 +3,10 @@ use crate::{certs::Cert, server::rpc::certs::*};
 use hyper::Response;
 use std::{io::Read, ops::Deref, sync::Arc};
 use taxy_api::{
-    cert::{SelfSignedCertRequest, UploadQuery},
+    cert::{
+        CertKind, CsrCompleteRequest, CsrRequest, Pkcs12ExportRequest, SelfSignedCertRequest,
+        UploadQuery,
+    },
     error::Error,
     id::ShortId,
 };
@@ -15,6 +18,12 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
         .and(warp::path::end())
         .and(with_state(app_state.clone()).and_then(list));
 
+    let api_usage = warp::get().and(warp::path("usage")).and(
+        with_state(app_state.clone())
+            .and(warp::path::end())
+            .and_then(usage),
+    );
+
     let api_get = warp::get().and(
         with_state(app_state.clone())
             .and(warp::path::param())
@@ -44,19 +53,73 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
             .and_then(delete),
     );
 
+    let api_csr_list = warp::get().and(warp::path("csr")).and(
+        with_state(app_state.clone())
+            .and(warp::path::end())
+            .and_then(csr_list),
+    );
+
+    let api_csr_get = warp::get().and(warp::path("csr")).and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(csr_get),
+    );
+
+    let api_csr_create = warp::post().and(warp::path("csr")).and(
+        with_state(app_state.clone())
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(csr_create),
+    );
+
+    let api_csr_complete = warp::post().and(warp::path("csr")).and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("complete"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(csr_complete),
+    );
+
+    let api_csr_delete = warp::delete().and(warp::path("csr")).and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(csr_delete),
+    );
+
     let api_download = warp::get().and(
-        with_state(app_state)
+        with_state(app_state.clone())
             .and(warp::path::param())
             .and(warp::path("download"))
             .and(warp::path::end())
             .and_then(download),
     );
 
+    let api_pkcs12 = warp::post().and(
+        with_state(app_state)
+            .and(warp::path::param())
+            .and(warp::path("pkcs12"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(pkcs12),
+    );
+
+    let api_csr = api_csr_list
+        .or(api_csr_get)
+        .or(api_csr_create)
+        .or(api_csr_complete)
+        .or(api_csr_delete);
+
     warp::path("certs")
         .and(
-            api_delete
+            api_csr
+                .or(api_usage)
+                .or(api_delete)
                 .or(api_get)
                 .or(api_download)
+                .or(api_pkcs12)
                 .or(api_self_sign)
                 .or(api_upload)
                 .or(api_list),
@@ -80,6 +143,23 @@ pub async fn list(state: AppState) -> Result<impl Reply, Rejection> {
     Ok(warp::reply::json(&state.call(GetCertList).await?))
 }
 
+/// Get the ports and proxy vhosts served by each server certificate,
+/// and the server names without a valid certificate.
+#[utoipa::path(
+    get,
+    path = "/api/certs/usage",
+    responses(
+        (status = 200, body = CertUsageGraph),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn usage(state: AppState) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetCertUsage).await?))
+}
+
 /// Delete a certificate.
 #[utoipa::path(
     get,
@@ -120,24 +200,30 @@ pub async fn self_sign(
 ) -> Result<impl Reply, Rejection> {
     let cert = if let Some(ca_cert) = request.ca_cert {
         let ca = state.call(GetCert { id: ca_cert }).await?;
-        Cert::new_self_signed(&request.san, &ca)?
+        Cert::new_self_signed(&request.san, &ca, request.key_algorithm)?
     } else {
-        let ca = Arc::new(Cert::new_ca()?);
+        let ca = Arc::new(Cert::new_ca(request.key_algorithm)?);
         state.call(AddCert { cert: ca.clone() }).await?;
-        Cert::new_self_signed(&request.san, &ca)?
+        Cert::new_self_signed(&request.san, &ca, request.key_algorithm)?
     };
     let cert = Arc::new(cert);
     Ok(warp::reply::json(&state.call(AddCert { cert }).await?))
 }
 
 /// Upload a certificate and key pair.
+///
+/// The chain and the key can be either PEM or DER encoded. The key can be in PKCS#8, PKCS#1 or SEC1 format.
+/// Alternatively, a PKCS#12 file can be uploaded as `pkcs12` along with its `password`.
+///
+/// Missing intermediate certificates are completed from the stored certificates or the AIA extension.
+/// Validation problems are returned as `warnings`, or rejected if `strict` is set.
 #[utoipa::path(
     post,
     path = "/api/certs/upload",
     request_body(content = CertPostBody, content_type = "multipart/form-data"),
     params(UploadQuery),
     responses(
-        (status = 200),
+        (status = 200, body = CertUploadResult),
         (status = 400, body = Error),
         (status = 401),
     ),
@@ -152,9 +238,23 @@ pub async fn upload(
 ) -> Result<impl Reply, Rejection> {
     let mut chain = Vec::new();
     let mut key = Vec::new();
+    let mut pkcs12 = Vec::new();
+    let mut password = Vec::new();
     while let Some(part) = form.next().await {
         if let Ok(mut part) = part {
-            if part.name() == "chain" {
+            if part.name() == "pkcs12" {
+                if let Some(Ok(buf)) = part.data().await {
+                    buf.reader()
+                        .read_to_end(&mut pkcs12)
+                        .map_err(|_| Error::FailedToReadPkcs12)?;
+                }
+            } else if part.name() == "password" {
+                if let Some(Ok(buf)) = part.data().await {
+                    buf.reader()
+                        .read_to_end(&mut password)
+                        .map_err(|_| Error::FailedToReadPkcs12)?;
+                }
+            } else if part.name() == "chain" {
                 if let Some(Ok(buf)) = part.data().await {
                     buf.reader()
                         .read_to_end(&mut chain)
@@ -170,12 +270,27 @@ pub async fn upload(
         }
     }
 
-    let key = if key.is_empty() { None } else { Some(key) };
-    let cert = Arc::new(Cert::new(query.kind, chain, key)?);
-    Ok(warp::reply::json(&state.call(AddCert { cert }).await?))
+    let cert = if pkcs12.is_empty() {
+        let key = if key.is_empty() { None } else { Some(key) };
+        Cert::new(query.kind, chain, key)?
+    } else {
+        let password = String::from_utf8(password).map_err(|_| Error::FailedToReadPkcs12)?;
+        Cert::from_pkcs12(query.kind, &pkcs12, &password)?
+    };
+    let cert = state.call(GetChainResolver).await?.complete(cert).await?;
+    Ok(warp::reply::json(
+        &state
+            .call(UploadCert {
+                cert,
+                strict: query.strict,
+            })
+            .await?,
+    ))
 }
 
 /// Delete a certificate.
+///
+/// Certificates loaded from watched directories are read-only and cannot be deleted.
 #[utoipa::path(
     delete,
     path = "/api/certs/{id}",
@@ -184,6 +299,7 @@ pub async fn upload(
     ),
     responses(
         (status = 200),
+        (status = 400),
         (status = 404),
         (status = 401),
     ),
@@ -195,6 +311,115 @@ pub async fn delete(state: AppState, id: ShortId) -> Result<impl Reply, Rejectio
     Ok(warp::reply::json(&state.call(DeleteCert { id }).await?))
 }
 
+/// List pending certificate signing requests.
+#[utoipa::path(
+    get,
+    path = "/api/certs/csr",
+    responses(
+        (status = 200, body = [CsrInfo]),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn csr_list(state: AppState) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetCsrList).await?))
+}
+
+/// Get a pending certificate signing request.
+#[utoipa::path(
+    get,
+    path = "/api/certs/csr/{id}",
+    params(
+        ("id" = String, Path, description = "CSR ID")
+    ),
+    responses(
+        (status = 200, body = CsrInfo),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn csr_get(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetCsr { id }).await?))
+}
+
+/// Generate a private key and a certificate signing request.
+#[utoipa::path(
+    post,
+    path = "/api/certs/csr",
+    request_body = CsrRequest,
+    responses(
+        (status = 200, body = CsrInfo),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn csr_create(state: AppState, request: CsrRequest) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(CreateCsr { request }).await?))
+}
+
+/// Complete a certificate signing request with the signed certificate.
+#[utoipa::path(
+    post,
+    path = "/api/certs/csr/{id}/complete",
+    params(
+        ("id" = String, Path, description = "CSR ID")
+    ),
+    request_body = CsrCompleteRequest,
+    responses(
+        (status = 200, body = CertInfo),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn csr_complete(
+    state: AppState,
+    id: ShortId,
+    request: CsrCompleteRequest,
+) -> Result<impl Reply, Rejection> {
+    // Fetch missing issuers before the key is attached. An unreadable chain is
+    // passed on as it is, and rejected by `CompleteCsr`.
+    let mut chain = request.chain.into_bytes();
+    if let Ok(cert) = Cert::new(CertKind::Server, chain.clone(), None) {
+        let cert = state.call(GetChainResolver).await?.complete(cert).await?;
+        chain = cert.pem_chain.clone();
+    }
+    Ok(warp::reply::json(
+        &state.call(CompleteCsr { id, chain }).await?,
+    ))
+}
+
+/// Delete a pending certificate signing request and its private key.
+#[utoipa::path(
+    delete,
+    path = "/api/certs/csr/{id}",
+    params(
+        ("id" = String, Path, description = "CSR ID")
+    ),
+    responses(
+        (status = 200),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn csr_delete(state: AppState, id: ShortId) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(DeleteCsr { id }).await?))
+}
+
 /// Download a certificate.
 #[utoipa::path(
     get,
@@ -222,3 +447,43 @@ pub async fn download(state: AppState, id: ShortId) -> Result<impl Reply, Reject
         .body(file.deref().clone())
         .unwrap())
 }
+
+/// Export a certificate and its private key as a PKCS#12 file.
+#[utoipa::path(
+    post,
+    path = "/api/certs/{id}/pkcs12",
+    params(
+        ("id" = String, Path, description = "Certification ID")
+    ),
+    request_body = Pkcs12ExportRequest,
+    responses(
+        (status = 200, body = Vec<u8>, content_type = "application/x-pkcs12"),
+        (status = 404),
+        (status = 400, body = Error),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn pkcs12(
+    state: AppState,
+    id: ShortId,
+    request: Pkcs12ExportRequest,
+) -> Result<impl Reply, Rejection> {
+    let file = state
+        .call(ExportPkcs12 {
+            id,
+            password: request.password,
+            legacy: request.legacy,
+        })
+        .await?;
+    Ok(Response::builder()
+        .header("Content-Type", "application/x-pkcs12")
+        .header(
+            "Content-Disposition",
+            &format!("attachment; filename=\"{}.p12\"", id),
+        )
+        .body(file.deref().clone())
+        .unwrap())
+}
@@ -1,17 +1,44 @@
 use super::{with_state, AppState};
+use crate::config::document;
 use crate::server::rpc::config::*;
+use hyper::body::Bytes;
 use taxy_api::app::AppConfig;
+use taxy_api::history::{ConfigExportQuery, ConfigImportQuery};
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
 
+const MAX_DOCUMENT_SIZE: u64 = 1024 * 1024 * 4;
+
 pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
     let api_get = warp::get()
         .and(warp::path::end())
         .and(with_state(app_state.clone()).and_then(get));
 
-    let api_put = warp::put()
-        .and(warp::path::end())
-        .and(with_state(app_state).and(warp::body::json()).and_then(put));
-    warp::path("config").and(api_get.or(api_put)).boxed()
+    let api_put = warp::put().and(warp::path::end()).and(
+        with_state(app_state.clone())
+            .and(warp::body::json())
+            .and_then(put),
+    );
+
+    let api_export = warp::get().and(warp::path("export")).and(
+        with_state(app_state.clone())
+            .and(warp::query())
+            .and(warp::path::end())
+            .and_then(export),
+    );
+
+    let api_import = warp::post().and(warp::path("import")).and(
+        with_state(app_state)
+            .and(warp::query())
+            .and(warp::header::optional::<String>("Content-Type"))
+            .and(warp::body::content_length_limit(MAX_DOCUMENT_SIZE))
+            .and(warp::body::bytes())
+            .and(warp::path::end())
+            .and_then(import),
+    );
+
+    warp::path("config")
+        .and(api_get.or(api_put).or(api_export).or(api_import))
+        .boxed()
 }
 
 /// Get the application configuration.
@@ -47,3 +74,70 @@ pub async fn get(state: AppState) -> Result<impl Reply, Rejection> {
 pub async fn put(state: AppState, config: AppConfig) -> Result<impl Reply, Rejection> {
     Ok(warp::reply::json(&state.call(SetConfig { config }).await?))
 }
+
+/// Export the whole configuration as a single document.
+#[utoipa::path(
+    get,
+    path = "/api/config/export",
+    params(ConfigExportQuery),
+    responses(
+        (status = 200, body = ConfigSnapshot),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn export(state: AppState, query: ConfigExportQuery) -> Result<impl Reply, Rejection> {
+    let format = query.format.unwrap_or_default();
+    let snapshot = state.call(ExportConfig).await?;
+    Ok(warp::reply::with_header(
+        document::to_string(&snapshot, format)?,
+        "Content-Type",
+        document::content_type(format),
+    ))
+}
+
+/// Replace the configuration with a document exported by `/api/config/export`.
+///
+/// Ports, proxies and ACME entries missing from the document are deleted.
+/// Certificates are only referenced and must already exist.
+#[utoipa::path(
+    post,
+    path = "/api/config/import",
+    params(ConfigImportQuery),
+    request_body(content = ConfigSnapshot, content_type = "application/toml"),
+    responses(
+        (status = 200, body = ConfigImportPlan),
+        (status = 400, body = Error),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn import(
+    state: AppState,
+    query: ConfigImportQuery,
+    content_type: Option<String>,
+    body: Bytes,
+) -> Result<impl Reply, Rejection> {
+    let format = query
+        .format
+        .or_else(|| {
+            content_type
+                .as_deref()
+                .and_then(document::format_from_content_type)
+        })
+        .unwrap_or_default();
+    let snapshot = document::from_slice(&body, format)?;
+    Ok(warp::reply::json(
+        &state
+            .call(ImportConfig {
+                snapshot,
+                dry_run: query.dry_run,
+            })
+            .await?,
+    ))
+}
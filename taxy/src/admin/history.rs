@@ -0,0 +1,131 @@
+use super::{with_state, AppState};
+use crate::server::rpc::history::*;
+use taxy_api::history::ConfigDiffQuery;
+use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
+
+pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
+    let api_list = warp::get()
+        .and(warp::path::end())
+        .and(with_state(app_state.clone()).and_then(list));
+
+    let api_get = warp::get().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path::end())
+            .and_then(get),
+    );
+
+    let api_diff = warp::get().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("diff"))
+            .and(warp::query())
+            .and(warp::path::end())
+            .and_then(diff),
+    );
+
+    let api_rollback = warp::post().and(
+        with_state(app_state)
+            .and(warp::path::param())
+            .and(warp::path("rollback"))
+            .and(warp::path::end())
+            .and_then(rollback),
+    );
+
+    warp::path("history")
+        .and(api_list.or(api_get).or(api_diff).or(api_rollback))
+        .boxed()
+}
+
+/// List the recorded configuration versions, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/history",
+    responses(
+        (status = 200, body = [ConfigVersionInfo]),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn list(state: AppState) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&state.call(GetConfigHistory).await?))
+}
+
+/// Get a configuration version.
+#[utoipa::path(
+    get,
+    path = "/api/history/{version}",
+    params(
+        ("version" = u64, Path, description = "Config version"),
+    ),
+    responses(
+        (status = 200, body = ConfigVersion),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn get(state: AppState, version: u64) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(GetConfigVersion { version }).await?,
+    ))
+}
+
+/// Get the changes made by a configuration version.
+#[utoipa::path(
+    get,
+    path = "/api/history/{version}/diff",
+    params(
+        ("version" = u64, Path, description = "Config version"),
+        ConfigDiffQuery
+    ),
+    responses(
+        (status = 200, body = [ConfigChange]),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn diff(
+    state: AppState,
+    version: u64,
+    query: ConfigDiffQuery,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state
+            .call(GetConfigDiff {
+                version,
+                from: query.from,
+            })
+            .await?,
+    ))
+}
+
+/// Restore the ports, proxies, ACME entries and app config of a configuration version.
+#[utoipa::path(
+    post,
+    path = "/api/history/{version}/rollback",
+    params(
+        ("version" = u64, Path, description = "Config version"),
+    ),
+    responses(
+        (status = 200),
+        (status = 400, body = Error),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn rollback(state: AppState, version: u64) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(RollbackConfig { version }).await?,
+    ))
+}
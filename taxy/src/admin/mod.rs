@@ -27,10 +27,13 @@ use warp::filters::body::BodyDeserializeError;
 use warp::{sse::Event, Filter, Rejection, Reply};
 
 mod acme;
+mod acme_accounts;
 mod app_info;
 mod auth;
+mod ca;
 mod certs;
 mod config;
+mod history;
 mod log;
 mod ports;
 mod proxies;
@@ -50,6 +53,7 @@ pub async fn start_admin(
         sender: command,
         event_listener_counter: Arc::new(AtomicUsize::new(0)),
         data: data.clone(),
+        user: None,
     };
 
     let data_clone = data.clone();
@@ -118,10 +122,13 @@ pub async fn start_admin(
         options
             .or(app_info::api(app_state.clone()))
             .or(config::api(app_state.clone()))
+            .or(history::api(app_state.clone()))
             .or(ports::api(app_state.clone()))
             .or(proxies::api(app_state.clone()))
             .or(certs::api(app_state.clone()))
             .or(acme::api(app_state.clone()))
+            .or(acme_accounts::api(app_state.clone()))
+            .or(ca::api(app_state.clone()))
             .or(auth::api(app_state.clone()))
             .or(log::api(app_state))
             .or(api_events)
@@ -164,6 +171,7 @@ pub struct AppState {
     sender: mpsc::Sender<ServerCommand>,
     event_listener_counter: Arc<AtomicUsize>,
     data: Arc<Mutex<Data>>,
+    user: Option<String>,
 }
 
 struct StreamWrapper {
@@ -244,7 +252,11 @@ impl AppState {
         let arg = Box::new(RpcWrapper::new(method)) as Box<dyn ErasedRpcMethod>;
         let _ = self
             .sender
-            .send(ServerCommand::CallMethod { id, arg })
+            .send(ServerCommand::CallMethod {
+                id,
+                author: self.user.clone(),
+                arg,
+            })
             .await;
 
         match rx.await {
@@ -282,19 +294,20 @@ fn with_state(state: AppState) -> impl Filter<Extract = (AppState,), Error = Rej
                     if let Some(token) = token {
                         let mut data = data.lock().await;
                         let expiry = data.config.admin.session_expiry;
-                        if data
-                            .sessions
-                            .verify(SessionKind::Admin, &token, expiry)
-                            .is_some()
+                        if let Some(session) =
+                            data.sessions.verify(SessionKind::Admin, &token, expiry)
                         {
-                            return Ok(());
+                            return Ok(session.username.clone());
                         }
                     }
                     Err(warp::reject::custom(Error::Unauthorized))
                 }
             }),
         )
-        .map(move |_| state.clone())
+        .map(move |user| AppState {
+            user: Some(user),
+            ..state.clone()
+        })
 }
 
 struct EventStream {
@@ -1,6 +1,8 @@
 use super::{with_state, AppState};
 use crate::server::rpc::ports::*;
-use taxy_api::{id::ShortId, port::Port};
+use taxy_api::{
+    id::ShortId, port::Port, route_test::RouteTestRequest, validation::PortValidationRequest,
+};
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
 
 pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
@@ -43,6 +45,23 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
             .and_then(post),
     );
 
+    let api_validate = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path("validate"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(validate),
+    );
+
+    let api_route_test = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path::param())
+            .and(warp::path("route-test"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(route_test),
+    );
+
     let api_reset = warp::get()
         .and(with_state(app_state.clone()))
         .and(warp::path::param())
@@ -65,6 +84,8 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
                 .or(api_interfaces)
                 .or(api_reset)
                 .or(api_list)
+                .or(api_validate)
+                .or(api_route_test)
                 .or(api_post),
         )
         .boxed()
@@ -189,6 +210,56 @@ pub async fn put(state: AppState, entry: Port, id: ShortId) -> Result<impl Reply
     Ok(warp::reply::json(&state.call(UpdatePort { entry }).await?))
 }
 
+/// Check a port configuration without applying it.
+#[utoipa::path(
+    post,
+    path = "/api/ports/validate",
+    request_body = PortValidationRequest,
+    responses(
+        (status = 200, body = ValidationResult),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn validate(
+    state: AppState,
+    request: PortValidationRequest,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(ValidatePort { request }).await?,
+    ))
+}
+
+/// Match a request against the routes of a port without sending it.
+#[utoipa::path(
+    post,
+    path = "/api/ports/{id}/route-test",
+    params(
+        ("id" = String, Path, description = "Port configuration id")
+    ),
+    request_body = RouteTestRequest,
+    responses(
+        (status = 200, body = RouteTestResult),
+        (status = 400, body = Error),
+        (status = 404),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn route_test(
+    state: AppState,
+    id: ShortId,
+    request: RouteTestRequest,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(TestRoute { id, request }).await?,
+    ))
+}
+
 /// Close all existing connections.
 #[utoipa::path(
     get,
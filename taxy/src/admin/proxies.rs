@@ -1,6 +1,6 @@
 use super::{with_state, AppState};
 use crate::server::rpc::proxies::*;
-use taxy_api::{id::ShortId, proxy::Proxy};
+use taxy_api::{id::ShortId, proxy::Proxy, validation::ProxyValidationRequest};
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
 
 pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
@@ -38,6 +38,14 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
             .and_then(put),
     );
 
+    let api_validate = warp::post().and(
+        with_state(app_state.clone())
+            .and(warp::path("validate"))
+            .and(warp::body::json())
+            .and(warp::path::end())
+            .and_then(validate),
+    );
+
     let api_post = warp::post().and(
         with_state(app_state)
             .and(warp::body::json())
@@ -52,6 +60,7 @@ pub fn api(app_state: AppState) -> BoxedFilter<(impl Reply,)> {
                 .or(api_status)
                 .or(api_put)
                 .or(api_list)
+                .or(api_validate)
                 .or(api_post),
         )
         .boxed()
@@ -175,3 +184,25 @@ pub async fn put(state: AppState, entry: Proxy, id: ShortId) -> Result<impl Repl
     let entry = (id, entry).into();
     Ok(warp::reply::json(&state.call(UpdateProxy { entry }).await?))
 }
+
+/// Check a proxy configuration without applying it.
+#[utoipa::path(
+    post,
+    path = "/api/proxies/validate",
+    request_body = ProxyValidationRequest,
+    responses(
+        (status = 200, body = ValidationResult),
+        (status = 401),
+    ),
+    security(
+        ("cookie"=[])
+    )
+)]
+pub async fn validate(
+    state: AppState,
+    request: ProxyValidationRequest,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(
+        &state.call(ValidateProxy { request }).await?,
+    ))
+}
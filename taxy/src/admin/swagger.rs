@@ -1,18 +1,46 @@
-use super::{acme, app_info, auth, certs, config, log, ports, proxies};
-use taxy_api::acme::{AcmeConfig, AcmeInfo, AcmeRequest, ExternalAccountBinding};
-use taxy_api::app::{AdminConfig, AppConfig, AppInfo, LogConfig};
+use super::{acme, acme_accounts, app_info, auth, ca, certs, config, history, log, ports, proxies};
+use taxy_api::acme::{
+    AcmeAccountImport, AcmeAccountInfo, AcmeAccountRequest, AcmeAccountStatus, AcmeAccountUpdate,
+    AcmeConfig, AcmeInfo, AcmeOrderStatus, AcmeRequest, AcmeRevokeRequest, AcmeStatus,
+    DnsChallengeConfig, ExternalAccountBinding, RevocationReason,
+};
+use taxy_api::app::{
+    AdminConfig, AppConfig, AppInfo, CertExpiryAlertConfig, CertWatchConfig, LogConfig,
+    OnDemandTlsConfig,
+};
 use taxy_api::auth::{LoginMethod, LoginRequest, LoginResponse};
-use taxy_api::cert::{CertInfo, CertKind, CertMetadata, CertPostBody, SelfSignedCertRequest};
+use taxy_api::ca::{
+    CaImport, CaInfo, CaRequest, CaRevokeRequest, ExtendedKeyUsage, IssueCertRequest, IssuedCert,
+    KeyUsage, Revocation,
+};
+use taxy_api::cert::{
+    CertExpiryAlert, CertExpiryStatus, CertInfo, CertKind, CertMetadata, CertPostBody,
+    CertUploadResult, CertUsage, CertUsageEntry, CertUsageGraph, CsrCompleteRequest, CsrInfo,
+    CsrRequest, KeyAlgorithm, Pkcs12ExportRequest, SelfSignedCertRequest,
+};
 use taxy_api::error::{Error, ErrorMessage};
 use taxy_api::event::ServerEvent;
+use taxy_api::history::{
+    AcmeSnapshot, ChangeAction, ConfigChange, ConfigFormat, ConfigImportPlan, ConfigResource,
+    ConfigSnapshot, ConfigVersion, ConfigVersionInfo,
+};
 use taxy_api::log::{LogLevel, SystemLogRow};
 use taxy_api::port::{NetworkAddr, NetworkInterface, PortEntry, PortOptions, UpstreamServer};
 use taxy_api::port::{PortState, PortStatus, SocketState};
 use taxy_api::proxy::{
-    HttpProxy, Proxy, ProxyEntry, ProxyKind, ProxyState, ProxyStatus, Route, Server, TcpProxy,
+    HttpProxy, Proxy, ProxyEntry, ProxyKind, ProxyState, ProxyStatus, Route, RouteConflict,
+    RouteConflictKind, Server, TcpProxy,
+};
+use taxy_api::route_test::{
+    HeaderChange, HttpHeader, RouteTestRejection, RouteTestRejectionReason, RouteTestRequest,
+    RouteTestResult,
 };
 use taxy_api::tls::TlsState;
 use taxy_api::tls::TlsTermination;
+use taxy_api::validation::{
+    PortValidationRequest, ProxyValidationRequest, ValidationIssue, ValidationIssueKind,
+    ValidationResult,
+};
 use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
 use utoipa::{Modify, OpenApi};
 use warp::filters::BoxedFilter;
@@ -31,32 +59,91 @@ use warp::{Rejection, Reply};
         ports::post,
         ports::put,
         ports::reset,
+        ports::validate,
+        ports::route_test,
         config::get,
         config::put,
+        config::export,
+        config::import,
+        history::list,
+        history::get,
+        history::diff,
+        history::rollback,
         app_info::get,
         acme::list,
         acme::get,
+        acme::status,
+        acme::renew,
+        acme::revoke,
         acme::delete,
         acme::add,
+        acme_accounts::list,
+        acme_accounts::get,
+        acme_accounts::add,
+        acme_accounts::import,
+        acme_accounts::put,
+        acme_accounts::key_rollover,
+        acme_accounts::deactivate,
+        acme_accounts::delete,
+        ca::list,
+        ca::get,
+        ca::create,
+        ca::import,
+        ca::delete,
+        ca::issue,
+        ca::issued,
+        ca::revoke,
+        ca::crl,
         proxies::list,
         proxies::get,
         proxies::delete,
         proxies::post,
         proxies::put,
+        proxies::validate,
         log::get,
         certs::list,
+        certs::usage,
         certs::get,
         certs::delete,
         certs::self_sign,
         certs::upload,
+        certs::csr_list,
+        certs::csr_get,
+        certs::csr_create,
+        certs::csr_complete,
+        certs::csr_delete,
+        certs::pkcs12,
     ),
     components(schemas(
         AppInfo,
         AppConfig,
         AdminConfig,
         LogConfig,
+        OnDemandTlsConfig,
+        CertExpiryAlertConfig,
+        CertWatchConfig,
+        ConfigSnapshot,
+        AcmeSnapshot,
+        ConfigVersion,
+        ConfigVersionInfo,
+        ConfigChange,
+        ConfigResource,
+        ChangeAction,
+        ConfigFormat,
+        ConfigImportPlan,
         PortEntry,
         PortOptions,
+        PortValidationRequest,
+        ProxyValidationRequest,
+        ValidationResult,
+        ValidationIssue,
+        ValidationIssueKind,
+        RouteTestRequest,
+        HttpHeader,
+        RouteTestResult,
+        HeaderChange,
+        RouteTestRejection,
+        RouteTestRejectionReason,
         UpstreamServer,
         TlsTermination,
         PortStatus,
@@ -64,20 +151,52 @@ use warp::{Rejection, Reply};
         SocketState,
         TlsState,
         CertKind,
+        KeyAlgorithm,
         CertInfo,
         CertMetadata,
         AcmeInfo,
         AcmeConfig,
+        AcmeStatus,
+        AcmeOrderStatus,
         SelfSignedCertRequest,
+        CsrRequest,
+        CsrInfo,
+        CsrCompleteRequest,
+        Pkcs12ExportRequest,
         AcmeRequest,
+        AcmeRevokeRequest,
+        RevocationReason,
+        AcmeAccountInfo,
+        AcmeAccountStatus,
+        AcmeAccountRequest,
+        AcmeAccountUpdate,
+        AcmeAccountImport,
         ExternalAccountBinding,
+        CaInfo,
+        CaRequest,
+        CaImport,
+        CaRevokeRequest,
+        IssueCertRequest,
+        IssuedCert,
+        Revocation,
+        KeyUsage,
+        ExtendedKeyUsage,
+        DnsChallengeConfig,
         CertPostBody,
+        CertUploadResult,
+        CertUsage,
+        CertUsageEntry,
+        CertUsageGraph,
+        CertExpiryAlert,
+        CertExpiryStatus,
         Error,
         ErrorMessage,
         ServerEvent,
         ProxyEntry,
         ProxyState,
         ProxyStatus,
+        RouteConflict,
+        RouteConflictKind,
         Proxy,
         ProxyKind,
         HttpProxy,
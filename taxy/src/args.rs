@@ -1,5 +1,5 @@
 use crate::log::LogFormat;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::{net::SocketAddr, path::PathBuf};
 use tracing_subscriber::filter::LevelFilter;
 
@@ -15,6 +15,42 @@ pub enum Command {
     Start(StartArgs),
     /// Add user
     AddUser(AddUserArgs),
+    /// Re-encrypt secrets in the config directory with a new master key
+    RotateMasterKey(RotateMasterKeyArgs),
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+pub enum StorageKind {
+    /// TOML files in the config directory
+    File,
+    /// A SQLite database in the config directory
+    Sqlite,
+}
+
+#[derive(Args)]
+pub struct MasterKeyArgs {
+    /// Base64 encoded 256-bit key to encrypt secrets in the config directory
+    #[clap(
+        long,
+        value_name = "KEY",
+        env = "TAXY_MASTER_KEY",
+        hide_env_values = true
+    )]
+    pub master_key: Option<String>,
+
+    /// File containing a base64 encoded 256-bit master key
+    #[clap(
+        long,
+        value_name = "FILE",
+        env = "TAXY_MASTER_KEY_FILE",
+        conflicts_with = "master_key"
+    )]
+    pub master_key_file: Option<PathBuf>,
+
+    /// Prompt for a passphrase to derive the master key from
+    #[clap(long, conflicts_with_all = ["master_key", "master_key_file"])]
+    pub master_key_passphrase: bool,
 }
 
 #[derive(Args)]
@@ -69,6 +105,22 @@ pub struct StartArgs {
 
     #[clap(long, short = 'd', value_name = "DIR", env = "TAXY_LOG_DIR")]
     pub log_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "STORAGE",
+        default_value = "file",
+        env = "TAXY_STORAGE"
+    )]
+    pub storage: StorageKind,
+
+    /// Load ports, proxies and ACME entries from a read-only TOML, YAML or JSON file
+    #[clap(long, value_name = "FILE", env = "TAXY_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub master_key: MasterKeyArgs,
 }
 
 #[derive(Args)]
@@ -83,4 +135,52 @@ pub struct AddUserArgs {
 
     #[clap(long)]
     pub totp: bool,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "STORAGE",
+        default_value = "file",
+        env = "TAXY_STORAGE"
+    )]
+    pub storage: StorageKind,
+
+    #[command(flatten)]
+    pub master_key: MasterKeyArgs,
+}
+
+#[derive(Args)]
+#[clap(group(
+    clap::ArgGroup::new("new_key")
+        .required(true)
+        .args(["new_master_key_file", "new_master_key_passphrase", "decrypt"]),
+))]
+pub struct RotateMasterKeyArgs {
+    #[clap(long, short, value_name = "DIR", env = "TAXY_CONFIG_DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "STORAGE",
+        default_value = "file",
+        env = "TAXY_STORAGE"
+    )]
+    pub storage: StorageKind,
+
+    /// The current master key
+    #[command(flatten)]
+    pub master_key: MasterKeyArgs,
+
+    /// File containing the new base64 encoded 256-bit master key
+    #[clap(long, value_name = "FILE")]
+    pub new_master_key_file: Option<PathBuf>,
+
+    /// Prompt for a passphrase to derive the new master key from
+    #[clap(long)]
+    pub new_master_key_passphrase: bool,
+
+    /// Decrypt all secrets and disable encryption
+    #[clap(long)]
+    pub decrypt: bool,
 }
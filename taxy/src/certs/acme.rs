@@ -1,11 +1,15 @@
-use crate::certs::Cert;
+use self::account::{AccountClient, AccountObject};
+use self::dns::DnsWebhook;
+use crate::certs::{set_key_pair, Cert};
+use crate::proxy::http::hyper_tls::client::HttpsConnector;
 use anyhow::bail;
 use backoff::{backoff::Backoff, ExponentialBackoffBuilder};
+use hyper::client::HttpConnector;
 use instant_acme::{
     Account, AccountCredentials, AuthorizationStatus, ChallengeType, ExternalAccountKey,
-    Identifier, NewAccount, NewOrder, Order, OrderStatus,
+    HttpClient, Identifier, KeyAuthorization, NewAccount, NewOrder, Order, OrderStatus,
 };
-use rcgen::{Certificate, CertificateParams, DistinguishedName};
+use rcgen::{Certificate, CertificateParams, CustomExtension, DistinguishedName};
 use serde_derive::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
@@ -13,14 +17,28 @@ use std::{
     sync::Arc,
     time::{Duration, SystemTime},
 };
+use taxy_api::{acme::AcmeInfo, cert::KeyAlgorithm, subject_name::SubjectName};
 use taxy_api::{
-    acme::Acme,
+    acme::{Acme, AcmeConfig, WebhookCredentials},
     cert::{CertKind, CertMetadata},
     id::ShortId,
 };
-use taxy_api::{acme::AcmeInfo, subject_name::SubjectName};
-use taxy_api::{acme::AcmeRequest, error::Error};
+use taxy_api::{
+    acme::{AcmeAccountInfo, AcmeAccountRequest, AcmeAccountStatus},
+    error::Error,
+};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
 use tracing::{error, info};
+use x509_parser::time::ASN1Time;
+
+mod account;
+mod ari;
+mod directory;
+mod dns;
+
+pub use self::ari::{RenewalInfo, DEFAULT_RETRY_AFTER};
+pub use self::directory::Directory;
+pub use self::dns::move_url_credentials;
 
 const HTTP_CHALLENGE_TIMEOUT: Duration = Duration::from_secs(180);
 
@@ -29,7 +47,7 @@ pub struct AcmeEntry {
     pub id: ShortId,
     #[serde(flatten)]
     pub acme: Acme,
-    pub account: Arc<AccountCredentials>,
+    pub account_id: ShortId,
 }
 
 impl fmt::Debug for AcmeEntry {
@@ -37,17 +55,160 @@ impl fmt::Debug for AcmeEntry {
         f.debug_struct("AcmeEntry")
             .field("config", &self.acme.config)
             .field("identifiers", &self.acme.identifiers)
+            .field("account_id", &self.account_id)
             .finish()
     }
 }
 
 impl AcmeEntry {
-    pub async fn new(id: ShortId, req: AcmeRequest) -> Result<Self, Error> {
+    pub fn new(id: ShortId, mut acme: Acme, account_id: ShortId) -> Result<Self, Error> {
+        acme.validate()?;
+        if let Some(dns) = &mut acme.dns_challenge {
+            move_url_credentials(dns);
+        }
+        Ok(Self {
+            id,
+            acme,
+            account_id,
+        })
+    }
+
+    pub async fn request(
+        &self,
+        account: &AcmeAccountEntry,
+        root_certs: &RootCertStore,
+    ) -> anyhow::Result<AcmeOrder> {
+        AcmeOrder::new(self, account, root_certs).await
+    }
+
+    pub fn id(&self) -> ShortId {
+        self.id
+    }
+
+    pub fn needs_renewal(&self, cert: &Cert) -> bool {
+        renewal_due(
+            &self.acme.config,
+            cert.not_before.timestamp(),
+            cert.not_after.timestamp(),
+            ASN1Time::now().timestamp(),
+        )
+    }
+
+    pub fn renewal_time(&self, cert: &Cert) -> SystemTime {
+        let not_before = cert.not_before.timestamp();
+        let not_after = cert.not_after.timestamp();
+        let renewal_at = not_after - renewal_threshold(&self.acme.config, not_before, not_after);
+        SystemTime::UNIX_EPOCH + Duration::from_secs(renewal_at.max(0) as u64)
+    }
+
+    pub async fn renewal_info(
+        &self,
+        directory: &Directory,
+        cert: &Cert,
+        root_certs: &RootCertStore,
+    ) -> anyhow::Result<Option<RenewalInfo>> {
+        ari::renewal_info(&https_client(root_certs), directory, cert).await
+    }
+
+    pub fn info(&self) -> AcmeInfo {
+        AcmeInfo {
+            id: self.id,
+            account_id: self.account_id,
+            config: self.acme.config.clone(),
+            identifiers: self
+                .acme
+                .identifiers
+                .iter()
+                .map(|id| id.to_string())
+                .collect(),
+            challenge_type: self.acme.challenge_type.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AcmeRecord {
+    #[serde(flatten)]
+    pub acme: Acme,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<ShortId>,
+    /// Credentials embedded by older versions, which created one account per entry.
+    #[serde(default, skip_serializing)]
+    pub account: Option<Arc<AccountCredentials>>,
+    /// Account contacts embedded by older versions.
+    #[serde(default, skip_serializing)]
+    pub contacts: Vec<String>,
+    /// The DNS webhook credentials, kept out of `acme` so that they can be encrypted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_credentials: Option<WebhookCredentials>,
+}
+
+impl AcmeRecord {
+    /// Returns the config with the DNS webhook credentials put back in place.
+    ///
+    /// The second value is `true` if credentials embedded in the webhook URL
+    /// have been moved out of it, so the record should be saved again.
+    pub fn into_acme(self) -> (Acme, bool) {
+        let mut acme = self.acme;
+        let mut moved = false;
+        if let Some(dns) = &mut acme.dns_challenge {
+            dns.webhook_credentials = self.dns_credentials.or(dns.webhook_credentials.take());
+            moved = move_url_credentials(dns);
+        }
+        (acme, moved)
+    }
+}
+
+impl From<AcmeEntry> for (ShortId, AcmeRecord) {
+    fn from(mut entry: AcmeEntry) -> Self {
+        let dns_credentials = entry
+            .acme
+            .dns_challenge
+            .as_mut()
+            .and_then(|dns| dns.webhook_credentials.take());
+        (
+            entry.id,
+            AcmeRecord {
+                acme: entry.acme,
+                account_id: Some(entry.account_id),
+                account: None,
+                contacts: Vec::new(),
+                dns_credentials,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct AcmeAccountEntry {
+    pub id: ShortId,
+    pub server_url: String,
+    pub contacts: Vec<String>,
+    pub status: AcmeAccountStatus,
+    pub credentials: Arc<AccountCredentials>,
+}
+
+impl fmt::Debug for AcmeAccountEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AcmeAccountEntry")
+            .field("server_url", &self.server_url)
+            .field("contacts", &self.contacts)
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+impl AcmeAccountEntry {
+    pub async fn new(
+        id: ShortId,
+        req: AcmeAccountRequest,
+        root_certs: &RootCertStore,
+    ) -> Result<Self, Error> {
         let contact = req.contacts.iter().map(|c| c.as_str()).collect::<Vec<_>>();
         let external_account = req
             .eab
             .map(|eab| ExternalAccountKey::new(eab.key_id, &eab.hmac_key));
-        let account = Account::create(
+        let account = Account::create_with_http(
             &NewAccount {
                 contact: &contact,
                 terms_of_service_agreed: true,
@@ -55,10 +216,11 @@ impl AcmeEntry {
             },
             &req.server_url,
             external_account.as_ref(),
+            http_client(root_certs),
         )
         .await;
 
-        let (_, account) = match account {
+        let (_, credentials) = match account {
             Ok(account) => account,
             Err(e) => {
                 error!("failed to create account: {}", e);
@@ -68,88 +230,241 @@ impl AcmeEntry {
 
         Ok(Self {
             id,
-            acme: req.acme,
-            account: Arc::new(account),
+            server_url: req.server_url,
+            contacts: req.contacts,
+            status: AcmeAccountStatus::Valid,
+            credentials: Arc::new(credentials),
         })
     }
 
-    pub async fn request(&self) -> anyhow::Result<AcmeOrder> {
-        AcmeOrder::new(self).await
+    /// Imports existing credentials and fetches the account state from the server.
+    pub async fn import(
+        id: ShortId,
+        credentials: AccountCredentials,
+        root_certs: &RootCertStore,
+    ) -> Result<Self, Error> {
+        let server_url = serde_json::to_value(&credentials)
+            .ok()
+            .and_then(|value| value["directory"].as_str().map(|url| url.to_string()))
+            .ok_or(Error::InvalidAcmeAccountCredentials)?;
+        let mut entry = Self {
+            id,
+            server_url,
+            contacts: Vec::new(),
+            status: AcmeAccountStatus::Valid,
+            credentials: Arc::new(credentials),
+        };
+        let account = entry
+            .client(root_certs)
+            .await?
+            .account()
+            .await
+            .map_err(request_failed)?;
+        entry.update_from(account);
+        Ok(entry)
+    }
+
+    pub async fn update_contacts(
+        &mut self,
+        contacts: Vec<String>,
+        root_certs: &RootCertStore,
+    ) -> Result<(), Error> {
+        self.ensure_valid()?;
+        let account = self
+            .client(root_certs)
+            .await?
+            .update_contacts(&contacts)
+            .await
+            .map_err(request_failed)?;
+        self.update_from(account);
+        Ok(())
     }
 
-    pub fn id(&self) -> ShortId {
-        self.id
+    pub async fn rollover_key(&mut self, root_certs: &RootCertStore) -> Result<(), Error> {
+        self.ensure_valid()?;
+        let credentials = self
+            .client(root_certs)
+            .await?
+            .rollover_key(&self.credentials)
+            .await
+            .map_err(request_failed)?;
+        self.credentials = Arc::new(credentials);
+        Ok(())
     }
 
-    pub fn info(&self) -> AcmeInfo {
-        AcmeInfo {
+    pub async fn deactivate(&mut self, root_certs: &RootCertStore) -> Result<(), Error> {
+        self.ensure_valid()?;
+        let account = self
+            .client(root_certs)
+            .await?
+            .deactivate()
+            .await
+            .map_err(request_failed)?;
+        self.update_from(account);
+        Ok(())
+    }
+
+    /// Revokes `cert` with an RFC 5280 reason code.
+    pub async fn revoke(
+        &self,
+        cert: &Cert,
+        reason: u8,
+        root_certs: &RootCertStore,
+    ) -> anyhow::Result<()> {
+        let chain = cert.certificates()?;
+        let Some(der) = chain.first() else {
+            bail!("empty certificate");
+        };
+        let client = AccountClient::new(&self.credentials, https_client(root_certs)).await?;
+        client.revoke_certificate(&der.0, reason).await
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.status == AcmeAccountStatus::Valid
+    }
+
+    pub fn ensure_valid(&self) -> Result<(), Error> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(Error::AcmeAccountDeactivated { id: self.id })
+        }
+    }
+
+    pub fn info(&self) -> AcmeAccountInfo {
+        AcmeAccountInfo {
             id: self.id,
-            config: self.acme.config.clone(),
-            identifiers: self
-                .acme
-                .identifiers
-                .iter()
-                .map(|id| id.to_string())
-                .collect(),
-            challenge_type: self.acme.challenge_type.clone(),
+            server_url: self.server_url.clone(),
+            contacts: self.contacts.clone(),
+            status: self.status,
+        }
+    }
+
+    /// Fetches the directory of the ACME server.
+    ///
+    /// Returns `None` if the account has no server URL.
+    pub async fn directory(&self, root_certs: &RootCertStore) -> anyhow::Result<Option<Directory>> {
+        if self.server_url.is_empty() {
+            return Ok(None);
         }
+        Directory::fetch(&https_client(root_certs), &self.server_url)
+            .await
+            .map(Some)
+    }
+
+    async fn client(&self, root_certs: &RootCertStore) -> Result<AccountClient, Error> {
+        AccountClient::new(&self.credentials, https_client(root_certs))
+            .await
+            .map_err(request_failed)
+    }
+
+    fn update_from(&mut self, account: AccountObject) {
+        self.contacts = account.contact;
+        self.status = match account.status.as_str() {
+            "valid" => AcmeAccountStatus::Valid,
+            "revoked" => AcmeAccountStatus::Revoked,
+            _ => AcmeAccountStatus::Deactivated,
+        };
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct AcmeAccount {
-    #[serde(flatten)]
-    pub acme: Acme,
-    pub account: Arc<AccountCredentials>,
+pub struct AcmeAccountRecord {
+    #[serde(default)]
+    pub server_url: String,
+    #[serde(default)]
+    pub contacts: Vec<String>,
+    #[serde(default)]
+    pub status: AcmeAccountStatus,
+    pub credentials: Arc<AccountCredentials>,
 }
 
-impl From<AcmeEntry> for (ShortId, AcmeAccount) {
-    fn from(entry: AcmeEntry) -> Self {
+impl From<AcmeAccountEntry> for (ShortId, AcmeAccountRecord) {
+    fn from(entry: AcmeAccountEntry) -> Self {
         (
             entry.id,
-            AcmeAccount {
-                acme: entry.acme,
-                account: entry.account,
+            AcmeAccountRecord {
+                server_url: entry.server_url,
+                contacts: entry.contacts,
+                status: entry.status,
+                credentials: entry.credentials,
             },
         )
     }
 }
 
-impl From<(ShortId, AcmeAccount)> for AcmeEntry {
-    fn from((id, entry): (ShortId, AcmeAccount)) -> Self {
+impl From<(ShortId, AcmeAccountRecord)> for AcmeAccountEntry {
+    fn from((id, record): (ShortId, AcmeAccountRecord)) -> Self {
         Self {
             id,
-            acme: entry.acme,
-            account: entry.account,
+            server_url: record.server_url,
+            contacts: record.contacts,
+            status: record.status,
+            credentials: record.credentials,
         }
     }
 }
 
+fn request_failed(err: anyhow::Error) -> Error {
+    Error::AcmeAccountRequestFailed {
+        detail: err.to_string(),
+    }
+}
+
 pub struct AcmeOrder {
     pub id: ShortId,
     pub challenge_type: ChallengeType,
     pub identifiers: Vec<Identifier>,
     pub http_challenges: HashMap<String, String>,
+    pub tls_alpn_challenges: Vec<Arc<Cert>>,
+    pub dns_challenges: Vec<(String, String)>,
+    pub dns_webhook: Option<DnsWebhook>,
     pub challenges: Vec<(String, String)>,
+    pub key_algorithm: KeyAlgorithm,
     pub order: Order,
 }
 
 impl AcmeOrder {
-    pub async fn new(entry: &AcmeEntry) -> anyhow::Result<Self> {
+    pub async fn new(
+        entry: &AcmeEntry,
+        account: &AcmeAccountEntry,
+        root_certs: &RootCertStore,
+    ) -> anyhow::Result<Self> {
         info!("requesting certificate");
 
+        let challenge_type = match entry.acme.challenge_type.as_str() {
+            "http-01" => ChallengeType::Http01,
+            "tls-alpn-01" => ChallengeType::TlsAlpn01,
+            "dns-01" => ChallengeType::Dns01,
+            _ => bail!("challenge type is not supported"),
+        };
+
+        let dns_webhook = if challenge_type == ChallengeType::Dns01 {
+            let config = entry
+                .acme
+                .dns_challenge
+                .as_ref()
+                .ok_or(Error::DnsChallengeConfigMissing)?;
+            Some(DnsWebhook::new(config, https_client(root_certs))?)
+        } else {
+            None
+        };
+
         let identifiers = entry
             .acme
             .identifiers
             .iter()
             .filter_map(|id| match id {
                 SubjectName::DnsName(domain) => Some(Identifier::Dns(domain.to_string())),
+                SubjectName::WildcardDnsName(domain) => {
+                    Some(Identifier::Dns(format!("*.{domain}")))
+                }
                 _ => None,
             })
             .collect::<Vec<_>>();
         let account: AccountCredentials =
-            serde_json::from_str(&serde_json::to_string(&entry.account)?)?;
-        let account = Account::from_credentials(account).await?;
+            serde_json::from_str(&serde_json::to_string(&account.credentials)?)?;
+        let account = Account::from_credentials_and_http(account, http_client(root_certs)).await?;
         let mut order = account
             .new_order(&NewOrder {
                 identifiers: &identifiers,
@@ -158,6 +473,8 @@ impl AcmeOrder {
         let authorizations = order.authorizations().await?;
 
         let mut http_challenges = HashMap::new();
+        let mut tls_alpn_challenges = Vec::new();
+        let mut dns_challenges = Vec::new();
         let mut challenges = Vec::new();
 
         for authz in &authorizations {
@@ -170,32 +487,65 @@ impl AcmeOrder {
             let challenge = authz
                 .challenges
                 .iter()
-                .find(|c| c.r#type == ChallengeType::Http01)
-                .ok_or_else(|| anyhow::anyhow!("no http01 challenge found"))?;
+                .find(|c| c.r#type == challenge_type)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("no {} challenge found", entry.acme.challenge_type)
+                })?;
 
             let Identifier::Dns(identifier) = &authz.identifier;
+            let key_authorization = order.key_authorization(challenge);
 
-            http_challenges.insert(
-                challenge.token.to_string(),
-                order.key_authorization(challenge).as_str().to_string(),
-            );
+            match challenge_type {
+                ChallengeType::Http01 => {
+                    http_challenges.insert(
+                        challenge.token.to_string(),
+                        key_authorization.as_str().to_string(),
+                    );
+                }
+                ChallengeType::TlsAlpn01 => {
+                    let cert = tls_alpn_challenge_cert(identifier, &key_authorization)?;
+                    tls_alpn_challenges.push(Arc::new(cert));
+                }
+                ChallengeType::Dns01 => {
+                    dns_challenges.push((identifier.to_string(), key_authorization.dns_value()));
+                }
+            }
             challenges.push((identifier.to_string(), challenge.url.to_string()));
         }
-        let challenge_type = match entry.acme.challenge_type.as_str() {
-            "http-01" => ChallengeType::Http01,
-            _ => bail!("challenge type is not supported"),
-        };
         Ok(Self {
             id: entry.id,
             challenge_type,
             identifiers,
             http_challenges,
+            tls_alpn_challenges,
+            dns_challenges,
+            dns_webhook,
             challenges,
+            key_algorithm: entry.acme.config.key_algorithm,
             order,
         })
     }
 
     pub async fn start_challenge(&mut self) -> anyhow::Result<Cert> {
+        let result = self.complete_challenge().await;
+        if let Some(webhook) = &self.dns_webhook {
+            for (domain, value) in &self.dns_challenges {
+                if let Err(err) = webhook.cleanup(domain, value).await {
+                    error!(domain, "failed to clean up dns record: {}", err);
+                }
+            }
+        }
+        result
+    }
+
+    async fn complete_challenge(&mut self) -> anyhow::Result<Cert> {
+        if let Some(webhook) = &self.dns_webhook {
+            for (domain, value) in &self.dns_challenges {
+                webhook.present(domain, value).await?;
+            }
+            tokio::time::sleep(webhook.propagation_delay()).await;
+        }
+
         for (_, url) in &self.challenges {
             self.order.set_challenge_ready(url).await?;
         }
@@ -230,6 +580,7 @@ impl AcmeOrder {
 
         let mut params = CertificateParams::new(san);
         params.distinguished_name = DistinguishedName::new();
+        set_key_pair(&mut params, self.key_algorithm)?;
         let cert = Certificate::from_params(params)?;
         let csr = cert.serialize_request_der()?;
 
@@ -257,3 +608,82 @@ impl AcmeOrder {
         Ok(cert?)
     }
 }
+
+fn tls_alpn_challenge_cert(
+    domain: &str,
+    key_authorization: &KeyAuthorization,
+) -> anyhow::Result<Cert> {
+    let mut params = CertificateParams::new(vec![domain.to_string()]);
+    params.distinguished_name = DistinguishedName::new();
+    params.custom_extensions = vec![CustomExtension::new_acme_identifier(
+        key_authorization.digest().as_ref(),
+    )];
+    let cert = Certificate::from_params(params)?;
+    let cert = Cert::new(
+        CertKind::Server,
+        cert.serialize_pem()?.into_bytes(),
+        Some(cert.serialize_private_key_pem().into_bytes()),
+    );
+    Ok(cert?)
+}
+
+/// Returns true if less than `renew_before_percent` of the lifetime, or less
+/// than `renew_before_days` days, remain before `not_after`.
+///
+/// `renew_before_days` is capped at half of the lifetime so that short-lived
+/// certificates are not renewed over and over again.
+fn renewal_due(config: &AcmeConfig, not_before: i64, not_after: i64, now: i64) -> bool {
+    not_after - now < renewal_threshold(config, not_before, not_after)
+}
+
+fn renewal_threshold(config: &AcmeConfig, not_before: i64, not_after: i64) -> i64 {
+    let lifetime = (not_after - not_before).max(0);
+    let mut threshold = lifetime * i64::from(config.renew_before_percent) / 100;
+    if let Some(days) = config.renew_before_days {
+        let days = i64::try_from(days.saturating_mul(60 * 60 * 24)).unwrap_or(i64::MAX);
+        threshold = threshold.max(days.min(lifetime / 2));
+    }
+    threshold
+}
+
+pub(crate) fn https_client(
+    root_certs: &RootCertStore,
+) -> hyper::Client<HttpsConnector<HttpConnector>> {
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_certs.clone())
+        .with_no_client_auth();
+    hyper::Client::builder().build(HttpsConnector::new(Arc::new(config)))
+}
+
+fn http_client(root_certs: &RootCertStore) -> Box<dyn HttpClient> {
+    Box::new(https_client(root_certs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::renewal_due;
+    use taxy_api::acme::AcmeConfig;
+
+    const DAY: i64 = 60 * 60 * 24;
+
+    #[test]
+    fn test_renewal_due() {
+        let config = AcmeConfig::default();
+        assert!(!renewal_due(&config, 0, 90 * DAY, 0));
+        assert!(!renewal_due(&config, 0, 90 * DAY, 60 * DAY));
+        assert!(renewal_due(&config, 0, 90 * DAY, 61 * DAY));
+        assert!(renewal_due(&config, 0, 90 * DAY, 100 * DAY));
+
+        let config = AcmeConfig {
+            renew_before_days: Some(45),
+            ..Default::default()
+        };
+        assert!(!renewal_due(&config, 0, 90 * DAY, 44 * DAY));
+        assert!(renewal_due(&config, 0, 90 * DAY, 46 * DAY));
+
+        // Capped at half of the lifetime.
+        assert!(!renewal_due(&config, 0, 7 * DAY, 3 * DAY));
+        assert!(renewal_due(&config, 0, 7 * DAY, 4 * DAY));
+    }
+}
@@ -0,0 +1,234 @@
+use super::directory::Directory;
+use crate::proxy::http::hyper_tls::client::HttpsConnector;
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose, Engine as _};
+use hyper::{body, client::HttpConnector, Body, Client, Method, Request};
+use instant_acme::AccountCredentials;
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// A client for the account operations that instant-acme does not provide.
+pub struct AccountClient {
+    client: Client<HttpsConnector<HttpConnector>>,
+    directory: Directory,
+    key: EcdsaKeyPair,
+    kid: String,
+    rng: SystemRandom,
+}
+
+/// The account object returned by the ACME server.
+#[derive(Deserialize)]
+pub struct AccountObject {
+    pub status: String,
+    #[serde(default)]
+    pub contact: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Credentials {
+    id: String,
+    key_pkcs8: String,
+    directory: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Protected<'a> {
+    alg: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jwk: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<&'a str>,
+    url: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Problem {
+    detail: Option<String>,
+}
+
+impl AccountClient {
+    pub async fn new(
+        credentials: &AccountCredentials,
+        client: Client<HttpsConnector<HttpConnector>>,
+    ) -> anyhow::Result<Self> {
+        let credentials: Credentials = serde_json::from_value(serde_json::to_value(credentials)?)?;
+        let directory_url = credentials
+            .directory
+            .ok_or_else(|| anyhow!("account has no directory url"))?;
+        let key_pkcs8 = general_purpose::URL_SAFE_NO_PAD.decode(credentials.key_pkcs8)?;
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &key_pkcs8)
+            .map_err(|err| anyhow!("invalid account key: {}", err))?;
+        let directory = Directory::fetch(&client, &directory_url).await?;
+        Ok(Self {
+            client,
+            directory,
+            key,
+            kid: credentials.id,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Fetches the account object with a POST-as-GET request.
+    pub async fn account(&self) -> anyhow::Result<AccountObject> {
+        let body = self.post(&self.kid, None).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    pub async fn update_contacts(&self, contacts: &[String]) -> anyhow::Result<AccountObject> {
+        let body = self
+            .post(&self.kid, Some(&json!({ "contact": contacts })))
+            .await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    pub async fn deactivate(&self) -> anyhow::Result<AccountObject> {
+        let body = self
+            .post(&self.kid, Some(&json!({ "status": "deactivated" })))
+            .await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Replaces the account key with a newly generated one and returns the
+    /// updated credentials.
+    pub async fn rollover_key(
+        &self,
+        credentials: &AccountCredentials,
+    ) -> anyhow::Result<AccountCredentials> {
+        let url = self
+            .directory
+            .key_change
+            .as_deref()
+            .ok_or_else(|| anyhow!("server does not support key rollover"))?;
+
+        let new_key_pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &self.rng)
+                .map_err(|err| anyhow!("failed to generate key: {}", err))?;
+        let new_key =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, new_key_pkcs8.as_ref())
+                .map_err(|err| anyhow!("invalid account key: {}", err))?;
+
+        // The inner JWS is signed by the new key and carries no nonce.
+        let inner = self.sign(
+            &new_key,
+            Protected {
+                alg: "ES256",
+                kid: None,
+                jwk: Some(jwk(&new_key)),
+                nonce: None,
+                url,
+            },
+            Some(&json!({
+                "account": self.kid,
+                "oldKey": jwk(&self.key),
+            })),
+        )?;
+        self.post(url, Some(&inner)).await?;
+
+        let mut credentials = serde_json::to_value(credentials)?;
+        credentials["key_pkcs8"] = general_purpose::URL_SAFE_NO_PAD
+            .encode(new_key_pkcs8.as_ref())
+            .into();
+        Ok(serde_json::from_value(credentials)?)
+    }
+
+    /// Revokes a DER-encoded certificate with an RFC 5280 reason code.
+    pub async fn revoke_certificate(&self, der: &[u8], reason: u8) -> anyhow::Result<()> {
+        let url = self
+            .directory
+            .revoke_cert
+            .as_deref()
+            .ok_or_else(|| anyhow!("server does not support revocation"))?;
+        let payload = json!({
+            "certificate": general_purpose::URL_SAFE_NO_PAD.encode(der),
+            "reason": reason,
+        });
+        self.post(url, Some(&payload)).await?;
+        Ok(())
+    }
+
+    /// Sends a request signed by the account key. A `None` payload makes it
+    /// a POST-as-GET request.
+    async fn post(&self, url: &str, payload: Option<&Value>) -> anyhow::Result<Vec<u8>> {
+        let nonce = self.nonce().await?;
+        let body = self.sign(
+            &self.key,
+            Protected {
+                alg: "ES256",
+                kid: Some(&self.kid),
+                jwk: None,
+                nonce: Some(&nonce),
+                url,
+            },
+            payload,
+        )?;
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(url)
+            .header("Content-Type", "application/jose+json")
+            .body(Body::from(serde_json::to_vec(&body)?))?;
+        let res = self.client.request(req).await?;
+        let status = res.status();
+        let body = body::to_bytes(res.into_body()).await?;
+        if !status.is_success() {
+            let detail = serde_json::from_slice::<Problem>(&body)
+                .ok()
+                .and_then(|problem| problem.detail)
+                .unwrap_or_else(|| status.to_string());
+            bail!(detail);
+        }
+        Ok(body.to_vec())
+    }
+
+    fn sign(
+        &self,
+        key: &EcdsaKeyPair,
+        protected: Protected,
+        payload: Option<&Value>,
+    ) -> anyhow::Result<Value> {
+        let protected = general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(&protected)?);
+        let payload = match payload {
+            Some(payload) => general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(payload)?),
+            None => String::new(),
+        };
+        let signature = key
+            .sign(&self.rng, format!("{protected}.{payload}").as_bytes())
+            .map_err(|err| anyhow!("failed to sign request: {}", err))?;
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": general_purpose::URL_SAFE_NO_PAD.encode(signature),
+        }))
+    }
+
+    async fn nonce(&self) -> anyhow::Result<String> {
+        let req = Request::builder()
+            .method(Method::HEAD)
+            .uri(&self.directory.new_nonce)
+            .body(Body::empty())?;
+        let res = self.client.request(req).await?;
+        res.headers()
+            .get("Replay-Nonce")
+            .and_then(|nonce| nonce.to_str().ok())
+            .map(|nonce| nonce.to_string())
+            .ok_or_else(|| anyhow!("missing replay nonce"))
+    }
+}
+
+/// Builds the JSON Web Key of a P-256 public key.
+fn jwk(key: &EcdsaKeyPair) -> Value {
+    // An uncompressed point: 0x04 || x || y
+    let point = key.public_key().as_ref();
+    json!({
+        "crv": "P-256",
+        "kty": "EC",
+        "x": general_purpose::URL_SAFE_NO_PAD.encode(&point[1..33]),
+        "y": general_purpose::URL_SAFE_NO_PAD.encode(&point[33..]),
+    })
+}
@@ -0,0 +1,115 @@
+use super::directory::Directory;
+use crate::certs::Cert;
+use crate::proxy::http::hyper_tls::client::HttpsConnector;
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose, Engine as _};
+use hyper::{body, client::HttpConnector, header::RETRY_AFTER, Client, StatusCode};
+use serde_derive::Deserialize;
+use std::time::{Duration, SystemTime};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use x509_parser::{extensions::ParsedExtension, parse_x509_certificate};
+
+/// How long to wait before polling again if the server sends no `Retry-After`.
+pub const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60 * 60 * 6);
+
+const MIN_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// A renewal window suggested by the ACME server (ACME Renewal Information).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenewalWindow {
+    pub start: SystemTime,
+    pub end: SystemTime,
+}
+
+impl RenewalWindow {
+    /// Picks a point in the window derived from `cert`, so that renewals of
+    /// different certificates are spread out but stay stable across polls.
+    pub fn select(&self, cert: &Cert) -> SystemTime {
+        let length = self.end.duration_since(self.start).unwrap_or_default();
+        let seed = u64::from_str_radix(cert.fingerprint.get(..16).unwrap_or_default(), 16)
+            .unwrap_or_default();
+        self.start + Duration::from_secs(seed % (length.as_secs() + 1))
+    }
+}
+
+/// A suggested renewal window and how long it stays valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenewalInfo {
+    pub window: RenewalWindow,
+    pub retry_after: Duration,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenewalInfoObject {
+    suggested_window: SuggestedWindow,
+}
+
+#[derive(Deserialize)]
+struct SuggestedWindow {
+    start: String,
+    end: String,
+}
+
+/// Fetches the suggested renewal window of `cert`.
+///
+/// Returns `None` if the server does not offer the `renewalInfo` resource.
+pub async fn renewal_info(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    directory: &Directory,
+    cert: &Cert,
+) -> anyhow::Result<Option<RenewalInfo>> {
+    let Some(renewal_info) = &directory.renewal_info else {
+        return Ok(None);
+    };
+
+    let url = format!("{}/{}", renewal_info.trim_end_matches('/'), cert_id(cert)?);
+    let res = client.get(url.parse()?).await?;
+    if res.status() != StatusCode::OK {
+        bail!("unexpected status: {}", res.status());
+    }
+    let retry_after = res
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RETRY_AFTER)
+        .max(MIN_RETRY_AFTER);
+    let body = body::to_bytes(res.into_body()).await?;
+    let info: RenewalInfoObject = serde_json::from_slice(&body)?;
+
+    let start = OffsetDateTime::parse(&info.suggested_window.start, &Rfc3339)?;
+    let end = OffsetDateTime::parse(&info.suggested_window.end, &Rfc3339)?;
+    if end < start {
+        bail!("invalid renewal window");
+    }
+    Ok(Some(RenewalInfo {
+        window: RenewalWindow {
+            start: start.into(),
+            end: end.into(),
+        },
+        retry_after,
+    }))
+}
+
+/// Builds the ARI certificate identifier: the base64url-encoded authority key
+/// identifier and serial number, joined by a dot.
+fn cert_id(cert: &Cert) -> anyhow::Result<String> {
+    let chain = cert.certificates()?;
+    let der = chain.first().ok_or_else(|| anyhow!("empty certificate"))?;
+    let (_, x509) = parse_x509_certificate(&der.0)?;
+    let key_id = x509
+        .extensions()
+        .iter()
+        .find_map(|ext| match ext.parsed_extension() {
+            ParsedExtension::AuthorityKeyIdentifier(aki) => aki.key_identifier.as_ref(),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("authority key identifier not found"))?;
+    Ok(format!(
+        "{}.{}",
+        general_purpose::URL_SAFE_NO_PAD.encode(key_id.0),
+        general_purpose::URL_SAFE_NO_PAD.encode(x509.raw_serial())
+    ))
+}
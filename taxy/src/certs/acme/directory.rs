@@ -0,0 +1,35 @@
+use crate::proxy::http::hyper_tls::client::HttpsConnector;
+use anyhow::bail;
+use hyper::{body, client::HttpConnector, Client, StatusCode, Uri};
+use serde_derive::Deserialize;
+
+/// ACME directory resources used outside of instant-acme.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Directory {
+    pub new_nonce: String,
+    pub revoke_cert: Option<String>,
+    pub key_change: Option<String>,
+    pub renewal_info: Option<String>,
+}
+
+impl Directory {
+    pub async fn fetch(
+        client: &Client<HttpsConnector<HttpConnector>>,
+        directory_url: &str,
+    ) -> anyhow::Result<Self> {
+        get_json(client, directory_url.parse()?).await
+    }
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    uri: Uri,
+) -> anyhow::Result<T> {
+    let res = client.get(uri).await?;
+    if res.status() != StatusCode::OK {
+        bail!("unexpected status: {}", res.status());
+    }
+    let body = body::to_bytes(res.into_body()).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
@@ -0,0 +1,98 @@
+use crate::proxy::http::hyper_tls::client::HttpsConnector;
+use anyhow::bail;
+use base64::{engine::general_purpose, Engine as _};
+use hyper::{client::HttpConnector, header, Body, Client, Method, Request};
+use serde_derive::Serialize;
+use std::time::Duration;
+use taxy_api::acme::{DnsChallengeConfig, WebhookCredentials};
+use url::Url;
+
+/// Manages `_acme-challenge` TXT records through an HTTP webhook.
+///
+/// The webhook receives `POST {webhook_url}/present` and `POST {webhook_url}/cleanup`
+/// requests with a JSON body of `{"fqdn": "...", "value": "..."}`, which is compatible
+/// with the `httpreq` provider of lego.
+pub struct DnsWebhook {
+    url: Url,
+    credentials: Option<WebhookCredentials>,
+    propagation_delay: Duration,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+#[derive(Serialize)]
+struct DnsRecord<'a> {
+    fqdn: &'a str,
+    value: &'a str,
+}
+
+impl DnsWebhook {
+    pub fn new(
+        config: &DnsChallengeConfig,
+        client: Client<HttpsConnector<HttpConnector>>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: config.webhook_url.parse()?,
+            credentials: config.webhook_credentials.clone(),
+            propagation_delay: config.propagation_delay,
+            client,
+        })
+    }
+
+    pub fn propagation_delay(&self) -> Duration {
+        self.propagation_delay
+    }
+
+    pub async fn present(&self, domain: &str, value: &str) -> anyhow::Result<()> {
+        self.post("present", domain, value).await
+    }
+
+    pub async fn cleanup(&self, domain: &str, value: &str) -> anyhow::Result<()> {
+        self.post("cleanup", domain, value).await
+    }
+
+    async fn post(&self, action: &str, domain: &str, value: &str) -> anyhow::Result<()> {
+        let mut url = self.url.clone();
+        url.set_path(&format!("{}/{}", url.path().trim_end_matches('/'), action));
+        let fqdn = format!("_acme-challenge.{domain}.");
+        let body = serde_json::to_vec(&DnsRecord { fqdn: &fqdn, value })?;
+        let mut req = Request::builder()
+            .method(Method::POST)
+            .uri(url.as_str())
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(credentials) = &self.credentials {
+            let credentials = format!("{}:{}", credentials.username, credentials.password);
+            req = req.header(
+                header::AUTHORIZATION,
+                format!("Basic {}", general_purpose::STANDARD.encode(credentials)),
+            );
+        }
+
+        let res = self.client.request(req.body(Body::from(body))?).await?;
+        if !res.status().is_success() {
+            bail!("dns webhook {action} failed: {}", res.status());
+        }
+        Ok(())
+    }
+}
+
+/// Moves credentials embedded in the webhook URL to `webhook_credentials`,
+/// so that they are stored with the other secrets.
+///
+/// Returns `true` if the URL contained credentials.
+pub fn move_url_credentials(config: &mut DnsChallengeConfig) -> bool {
+    let Ok(mut url) = config.webhook_url.parse::<Url>() else {
+        return false;
+    };
+    if url.username().is_empty() && url.password().is_none() {
+        return false;
+    }
+    let credentials = WebhookCredentials {
+        username: url.username().to_string(),
+        password: url.password().unwrap_or_default().to_string(),
+    };
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    config.webhook_url = url.to_string();
+    config.webhook_credentials.get_or_insert(credentials);
+    true
+}
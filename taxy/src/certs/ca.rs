@@ -0,0 +1,375 @@
+use super::{set_key_pair, signer, Cert};
+use rand::RngCore;
+use rcgen::{
+    BasicConstraints, CertificateParams, CertificateRevocationList,
+    CertificateRevocationListParams, CrlDistributionPoint, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyIdMethod, KeyUsagePurpose, RevokedCertParams, SanType,
+    SerialNumber,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+use taxy_api::{
+    acme::RevocationReason,
+    ca::{
+        CaImport, CaInfo, CaRequest, ExtendedKeyUsage, IssueCertRequest, IssuedCert, KeyUsage,
+        Revocation,
+    },
+    cert::CertKind,
+    error::Error,
+    id::ShortId,
+    subject_name::SubjectName,
+};
+use time::OffsetDateTime;
+use tracing::error;
+use x509_parser::parse_x509_certificate;
+
+const CRL_VALIDITY: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+const CRL_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+const BACKDATE: Duration = Duration::from_secs(60 * 5);
+const MAX_VALIDITY_DAYS: u64 = 365 * 100;
+
+#[derive(Debug, Clone)]
+pub struct CaEntry {
+    pub id: ShortId,
+    pub crl_url: Option<String>,
+    pub crl_number: u64,
+    pub issued: Vec<IssuedCert>,
+    crl: Option<(SystemTime, Vec<u8>)>,
+}
+
+impl CaEntry {
+    pub fn new(id: ShortId, crl_url: Option<String>) -> Self {
+        Self {
+            id,
+            crl_url,
+            crl_number: 0,
+            issued: Vec::new(),
+            crl: None,
+        }
+    }
+
+    /// Generates a new self-signed CA certificate.
+    pub fn generate(request: &CaRequest) -> Result<(Self, Cert), Error> {
+        let mut distinguished_name = DistinguishedName::new();
+        distinguished_name.push(DnType::CommonName, request.common_name.as_str());
+        let mut params = CertificateParams::default();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.distinguished_name = distinguished_name;
+        params.key_usages = vec![
+            KeyUsagePurpose::DigitalSignature,
+            KeyUsagePurpose::KeyCertSign,
+            KeyUsagePurpose::CrlSign,
+        ];
+        set_validity(&mut params, request.validity_days)?;
+        params.serial_number = Some(random_serial());
+        set_key_pair(&mut params, request.key_algorithm)?;
+
+        let cert = rcgen::Certificate::from_params(params).map_err(generation_failed)?;
+        let pem_chain = cert.serialize_pem().map_err(generation_failed)?;
+        let pem_key = cert.serialize_private_key_pem();
+        let cert = Cert::new(
+            CertKind::Root,
+            pem_chain.into_bytes(),
+            Some(pem_key.into_bytes()),
+        )?;
+        Ok((Self::new(cert.id(), request.crl_url.clone()), cert))
+    }
+
+    /// Imports an existing CA certificate with its private key.
+    pub fn import(request: &CaImport) -> Result<(Self, Cert), Error> {
+        let cert = Cert::new(
+            CertKind::Root,
+            request.chain.as_bytes().to_vec(),
+            Some(request.key.as_bytes().to_vec()),
+        )?;
+        if !cert.is_ca {
+            return Err(Error::InvalidCaCert { id: cert.id() });
+        }
+        signer(&cert).map_err(|_| Error::InvalidCaCert { id: cert.id() })?;
+        Ok((Self::new(cert.id(), request.crl_url.clone()), cert))
+    }
+
+    pub fn info(&self, ca: &Cert) -> CaInfo {
+        let subject = ca
+            .certificates()
+            .ok()
+            .and_then(|chain| {
+                let der = &chain.first()?.0;
+                let (_, x509) = parse_x509_certificate(der).ok()?;
+                Some(x509.subject().to_string())
+            })
+            .unwrap_or_default();
+        CaInfo {
+            id: self.id,
+            subject,
+            crl_url: self.crl_url.clone(),
+            crl_number: self.crl_number,
+            issued: self.issued.len(),
+        }
+    }
+
+    /// Issues a leaf certificate signed by `ca` and records it.
+    pub fn issue(&mut self, ca: &Cert, request: &IssueCertRequest) -> Result<Cert, Error> {
+        if request.kind == CertKind::Root {
+            return Err(Error::UnsupportedIssueCertKind {
+                kind: request.kind.to_string(),
+            });
+        }
+        let signer = signer(ca)?;
+
+        let mut params = CertificateParams::default();
+        params.subject_alt_names = request
+            .san
+            .iter()
+            .map(|name| {
+                if let SubjectName::IPAddress(ip) = name {
+                    SanType::IpAddress(*ip)
+                } else {
+                    SanType::DnsName(name.to_string())
+                }
+            })
+            .collect();
+
+        let common_name = request
+            .common_name
+            .clone()
+            .or_else(|| request.san.first().map(|name| name.to_string()))
+            .unwrap_or_else(|| "Taxy Cert".into());
+        let mut distinguished_name = DistinguishedName::new();
+        distinguished_name.push(DnType::CommonName, common_name.as_str());
+        params.distinguished_name = distinguished_name;
+
+        params.key_usages = if request.key_usages.is_empty() {
+            default_key_usages(request.kind)
+        } else {
+            request.key_usages.iter().copied().map(key_usage).collect()
+        };
+        params.extended_key_usages = if request.extended_key_usages.is_empty() {
+            default_extended_key_usages(request.kind)
+        } else {
+            request
+                .extended_key_usages
+                .iter()
+                .copied()
+                .map(extended_key_usage)
+                .collect()
+        };
+        if let Some(url) = &self.crl_url {
+            params.crl_distribution_points = vec![CrlDistributionPoint {
+                uris: vec![url.clone()],
+            }];
+        }
+        params.use_authority_key_identifier_extension = true;
+        set_validity(&mut params, request.validity_days)?;
+        let serial = random_serial();
+        params.serial_number = Some(serial.clone());
+        set_key_pair(&mut params, request.key_algorithm)?;
+
+        let cert = rcgen::Certificate::from_params(params).map_err(generation_failed)?;
+        let cert_pem = cert
+            .serialize_pem_with_signer(&signer)
+            .map_err(generation_failed)?;
+        let ca_pem = String::from_utf8_lossy(&ca.pem_chain);
+        let pem_chain = format!("{}\r\n{}", cert_pem, ca_pem).into_bytes();
+        let pem_key = cert.serialize_private_key_pem().into_bytes();
+        let cert = Cert::new(request.kind, pem_chain, Some(pem_key))?;
+
+        self.issued.push(IssuedCert {
+            serial: hex::encode(serial.as_ref()),
+            cert_id: cert.id(),
+            kind: request.kind,
+            common_name,
+            san: request.san.clone(),
+            not_before: cert.not_before.timestamp(),
+            not_after: cert.not_after.timestamp(),
+            revoked: None,
+        });
+        Ok(cert)
+    }
+
+    /// Marks an issued certificate as revoked and returns its record.
+    pub fn revoke(&mut self, serial: &str, reason: RevocationReason) -> Result<&IssuedCert, Error> {
+        let issued = self
+            .issued
+            .iter_mut()
+            .find(|issued| issued.serial.eq_ignore_ascii_case(serial))
+            .ok_or_else(|| Error::IdNotFound {
+                id: serial.to_string(),
+            })?;
+        if issued.revoked.is_some() {
+            return Err(Error::CertAlreadyRevoked {
+                serial: issued.serial.clone(),
+            });
+        }
+        issued.revoked = Some(Revocation {
+            revoked_at: unix_time(SystemTime::now()),
+            reason,
+        });
+        self.crl = None;
+        Ok(issued)
+    }
+
+    /// Returns the DER encoded CRL and whether a new one has been generated.
+    ///
+    /// A cached CRL is reused until it is older than a day or a certificate is revoked.
+    pub fn crl(&mut self, ca: &Cert) -> Result<(Vec<u8>, bool), Error> {
+        let now = SystemTime::now();
+        if let Some((generated_at, crl)) = &self.crl {
+            let age = now.duration_since(*generated_at).unwrap_or_default();
+            if age < CRL_REFRESH_INTERVAL {
+                return Ok((crl.clone(), false));
+            }
+        }
+
+        let signer = signer(ca)?;
+        let this_update = OffsetDateTime::from(now);
+        let revoked_certs = self
+            .issued
+            .iter()
+            .filter_map(|issued| {
+                let revoked = issued.revoked.as_ref()?;
+                Some(RevokedCertParams {
+                    serial_number: SerialNumber::from(hex::decode(&issued.serial).ok()?),
+                    revocation_time: OffsetDateTime::from_unix_timestamp(revoked.revoked_at)
+                        .ok()?,
+                    reason_code: revocation_reason(revoked.reason),
+                    invalidity_date: None,
+                })
+            })
+            .collect();
+        let params = CertificateRevocationListParams {
+            this_update,
+            next_update: this_update + CRL_VALIDITY,
+            crl_number: SerialNumber::from(self.crl_number + 1),
+            issuing_distribution_point: None,
+            revoked_certs,
+            alg: signer.get_key_pair().algorithm(),
+            key_identifier_method: KeyIdMethod::Sha256,
+        };
+        let crl = CertificateRevocationList::from_params(params)
+            .and_then(|crl| crl.serialize_der_with_signer(&signer))
+            .map_err(generation_failed)?;
+
+        self.crl_number += 1;
+        self.crl = Some((now, crl.clone()));
+        Ok((crl, true))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CaRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crl_url: Option<String>,
+    #[serde(default)]
+    pub crl_number: u64,
+    #[serde(default)]
+    pub issued: Vec<IssuedCert>,
+}
+
+impl From<CaEntry> for (ShortId, CaRecord) {
+    fn from(entry: CaEntry) -> Self {
+        (
+            entry.id,
+            CaRecord {
+                crl_url: entry.crl_url,
+                crl_number: entry.crl_number,
+                issued: entry.issued,
+            },
+        )
+    }
+}
+
+impl From<(ShortId, CaRecord)> for CaEntry {
+    fn from((id, record): (ShortId, CaRecord)) -> Self {
+        Self {
+            id,
+            crl_url: record.crl_url,
+            crl_number: record.crl_number,
+            issued: record.issued,
+            crl: None,
+        }
+    }
+}
+
+fn default_key_usages(kind: CertKind) -> Vec<KeyUsagePurpose> {
+    match kind {
+        CertKind::Server => vec![
+            KeyUsagePurpose::DigitalSignature,
+            KeyUsagePurpose::KeyEncipherment,
+        ],
+        _ => vec![KeyUsagePurpose::DigitalSignature],
+    }
+}
+
+fn default_extended_key_usages(kind: CertKind) -> Vec<ExtendedKeyUsagePurpose> {
+    match kind {
+        CertKind::Server => vec![ExtendedKeyUsagePurpose::ServerAuth],
+        _ => vec![ExtendedKeyUsagePurpose::ClientAuth],
+    }
+}
+
+fn revocation_reason(reason: RevocationReason) -> Option<rcgen::RevocationReason> {
+    match reason {
+        // RFC 5280 recommends omitting the reason code instead of using unspecified.
+        RevocationReason::Unspecified => None,
+        RevocationReason::KeyCompromise => Some(rcgen::RevocationReason::KeyCompromise),
+        RevocationReason::AffiliationChanged => Some(rcgen::RevocationReason::AffiliationChanged),
+        RevocationReason::Superseded => Some(rcgen::RevocationReason::Superseded),
+        RevocationReason::CessationOfOperation => {
+            Some(rcgen::RevocationReason::CessationOfOperation)
+        }
+    }
+}
+
+fn set_validity(params: &mut CertificateParams, days: u64) -> Result<(), Error> {
+    let now = SystemTime::now();
+    let not_after = Some(days)
+        .filter(|&days| days <= MAX_VALIDITY_DAYS)
+        .and_then(|days| days.checked_mul(60 * 60 * 24))
+        .and_then(|secs| now.checked_add(Duration::from_secs(secs)))
+        .ok_or(Error::InvalidValidityDays { days })?;
+    params.not_before = OffsetDateTime::from(now - BACKDATE);
+    params.not_after = OffsetDateTime::from(not_after);
+    Ok(())
+}
+
+/// Returns a random positive 16-byte serial number.
+fn random_serial() -> SerialNumber {
+    let mut serial = [0; 16];
+    rand::thread_rng().fill_bytes(&mut serial);
+    serial[0] &= 0x7f;
+    serial[0] |= 0x01;
+    SerialNumber::from_slice(&serial)
+}
+
+fn unix_time(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|time| time.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn generation_failed(err: rcgen::RcgenError) -> Error {
+    error!(?err, "failed to generate certificate");
+    Error::FailedToGerateSelfSignedCertificate
+}
+
+fn key_usage(usage: KeyUsage) -> KeyUsagePurpose {
+    match usage {
+        KeyUsage::DigitalSignature => KeyUsagePurpose::DigitalSignature,
+        KeyUsage::ContentCommitment => KeyUsagePurpose::ContentCommitment,
+        KeyUsage::KeyEncipherment => KeyUsagePurpose::KeyEncipherment,
+        KeyUsage::DataEncipherment => KeyUsagePurpose::DataEncipherment,
+        KeyUsage::KeyAgreement => KeyUsagePurpose::KeyAgreement,
+    }
+}
+
+fn extended_key_usage(usage: ExtendedKeyUsage) -> ExtendedKeyUsagePurpose {
+    match usage {
+        ExtendedKeyUsage::ServerAuth => ExtendedKeyUsagePurpose::ServerAuth,
+        ExtendedKeyUsage::ClientAuth => ExtendedKeyUsagePurpose::ClientAuth,
+        ExtendedKeyUsage::CodeSigning => ExtendedKeyUsagePurpose::CodeSigning,
+        ExtendedKeyUsage::EmailProtection => ExtendedKeyUsagePurpose::EmailProtection,
+        ExtendedKeyUsage::TimeStamping => ExtendedKeyUsagePurpose::TimeStamping,
+        ExtendedKeyUsage::OcspSigning => ExtendedKeyUsagePurpose::OcspSigning,
+    }
+}
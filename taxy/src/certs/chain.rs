@@ -0,0 +1,269 @@
+use super::{acme::https_client, der_to_pem, Cert};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use taxy_api::{cert::CertKind, error::Error, subject_name::SubjectName};
+use tokio_rustls::rustls::{
+    client::{ServerCertVerifier, WebPkiVerifier},
+    server::{AllowAnyAuthenticatedClient, ClientCertVerifier},
+    Certificate, RootCertStore, ServerName,
+};
+use tracing::{info, warn};
+use x509_parser::{
+    extensions::{GeneralName, ParsedExtension},
+    oid_registry::OID_PKIX_ACCESS_DESCRIPTOR_CA_ISSUERS,
+    parse_x509_certificate,
+    prelude::X509Certificate,
+};
+
+const MAX_CHAIN_LENGTH: usize = 8;
+const AIA_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The certificates needed to complete chains outside of the server state.
+#[derive(Debug, Clone)]
+pub struct ChainResolver {
+    pub stored: Vec<Arc<Cert>>,
+    pub root_certs: RootCertStore,
+}
+
+impl ChainResolver {
+    /// Completes the chain of `cert`, fetching missing issuers over the network.
+    pub async fn complete(&self, cert: Cert) -> Result<Cert, Error> {
+        complete_chain(cert, &self.stored, &self.root_certs, true).await
+    }
+}
+
+/// Appends missing intermediate certificates to the chain of `cert`.
+///
+/// Issuers are looked up in the chains of the stored certificates first,
+/// then, if `fetch` is set, fetched from the CA Issuers URL in the Authority Information Access extension.
+/// The chain ends at the first self-signed certificate or trusted root, which is not appended.
+pub async fn complete_chain(
+    cert: Cert,
+    stored: &[Arc<Cert>],
+    root_certs: &RootCertStore,
+    fetch: bool,
+) -> Result<Cert, Error> {
+    if cert.kind == CertKind::Root {
+        return Ok(cert);
+    }
+
+    let mut chain = cert.certificates()?;
+    let mut completed = false;
+    while chain.len() < MAX_CHAIN_LENGTH {
+        let last = chain
+            .last()
+            .ok_or(Error::FailedToReadCertificate)?
+            .0
+            .clone();
+        let (_, last) =
+            parse_x509_certificate(&last).map_err(|_| Error::FailedToReadCertificate)?;
+        if is_self_issued(&last) || is_trusted(&last, root_certs) {
+            break;
+        }
+        let issuer = match find_stored_issuer(&last, stored) {
+            Some(issuer) => Some(issuer),
+            None if fetch => fetch_issuer(&last, root_certs).await,
+            None => None,
+        };
+        let Some(issuer) = issuer else {
+            break;
+        };
+        let (_, parsed) =
+            parse_x509_certificate(&issuer.0).map_err(|_| Error::FailedToReadCertificate)?;
+        if is_self_issued(&parsed) || chain.contains(&issuer) {
+            break;
+        }
+        info!(id = %cert.id, issuer = %parsed.subject(), "appended intermediate certificate");
+        chain.push(issuer);
+        completed = true;
+    }
+
+    if !completed {
+        return Ok(cert);
+    }
+    let mut pem_chain = String::new();
+    for cert in &chain {
+        pem_chain += &der_to_pem("CERTIFICATE", &cert.0)?;
+    }
+    Cert::new(cert.kind, pem_chain.into_bytes(), cert.pem_key.clone())
+}
+
+/// Validates the chain of `cert` against the root certificates and returns a list of warnings.
+pub fn validate_chain(cert: &Cert, root_certs: &RootCertStore) -> Result<Vec<String>, Error> {
+    let chain = cert.certificates()?;
+    let (end_entity, intermediates) = chain.split_first().ok_or(Error::FailedToReadCertificate)?;
+    let (_, x509) =
+        parse_x509_certificate(&end_entity.0).map_err(|_| Error::FailedToReadCertificate)?;
+
+    let mut warnings = Vec::new();
+    let now = SystemTime::now();
+    match cert.kind {
+        CertKind::Server => {
+            let name = server_name(cert);
+            let verifier = WebPkiVerifier::new(root_certs.clone(), None);
+            if let Err(err) = verifier.verify_server_cert(
+                end_entity,
+                intermediates,
+                &name,
+                &mut std::iter::empty(),
+                &[],
+                now,
+            ) {
+                warnings.push(format!("certificate chain does not verify: {err}"));
+            }
+            if !has_extended_key_usage(&x509, |eku| eku.server_auth) {
+                warnings.push("certificate lacks the serverAuth extended key usage".into());
+            }
+        }
+        CertKind::Client => {
+            let verifier = AllowAnyAuthenticatedClient::new(root_certs.clone());
+            if let Err(err) = verifier.verify_client_cert(end_entity, intermediates, now) {
+                warnings.push(format!("certificate chain does not verify: {err}"));
+            }
+            if !has_extended_key_usage(&x509, |eku| eku.client_auth) {
+                warnings.push("certificate lacks the clientAuth extended key usage".into());
+            }
+        }
+        CertKind::Root => (),
+    }
+    Ok(warnings)
+}
+
+fn server_name(cert: &Cert) -> ServerName {
+    cert.san
+        .iter()
+        .find_map(|name| match name {
+            SubjectName::DnsName(name) => ServerName::try_from(name.as_str()).ok(),
+            SubjectName::WildcardDnsName(name) => {
+                ServerName::try_from(format!("wildcard.{name}").as_str()).ok()
+            }
+            SubjectName::IPAddress(addr) => Some(ServerName::IpAddress(*addr)),
+        })
+        .unwrap_or_else(|| ServerName::try_from("localhost").unwrap())
+}
+
+fn has_extended_key_usage<F>(cert: &X509Certificate, purpose: F) -> bool
+where
+    F: Fn(&x509_parser::extensions::ExtendedKeyUsage) -> bool,
+{
+    match cert.extended_key_usage() {
+        Ok(Some(eku)) => eku.value.any || purpose(eku.value),
+        _ => false,
+    }
+}
+
+fn is_self_issued(cert: &X509Certificate) -> bool {
+    cert.subject().as_raw() == cert.issuer().as_raw()
+}
+
+fn is_trusted(cert: &X509Certificate, root_certs: &RootCertStore) -> bool {
+    root_certs
+        .roots
+        .iter()
+        .any(|root| root.subject().as_ref() == cert.issuer().as_raw())
+}
+
+fn is_issued_by(cert: &X509Certificate, issuer: &X509Certificate) -> bool {
+    if cert.issuer().as_raw() != issuer.subject().as_raw() || !issuer.is_ca() {
+        return false;
+    }
+    match (authority_key_id(cert), subject_key_id(issuer)) {
+        (Some(aki), Some(ski)) => aki == ski,
+        _ => true,
+    }
+}
+
+fn authority_key_id<'a>(cert: &X509Certificate<'a>) -> Option<&'a [u8]> {
+    cert.iter_extensions()
+        .find_map(|ext| match ext.parsed_extension() {
+            ParsedExtension::AuthorityKeyIdentifier(aki) => aki.key_identifier.as_ref(),
+            _ => None,
+        })
+        .map(|id| id.0)
+}
+
+fn subject_key_id<'a>(cert: &X509Certificate<'a>) -> Option<&'a [u8]> {
+    cert.iter_extensions()
+        .find_map(|ext| match ext.parsed_extension() {
+            ParsedExtension::SubjectKeyIdentifier(ski) => Some(ski.0),
+            _ => None,
+        })
+}
+
+fn find_stored_issuer(cert: &X509Certificate, stored: &[Arc<Cert>]) -> Option<Certificate> {
+    let mut candidates = stored
+        .iter()
+        .filter_map(|stored| stored.certificates().ok())
+        .flatten()
+        .filter_map(|candidate| {
+            let (_, parsed) = parse_x509_certificate(&candidate.0).ok()?;
+            is_issued_by(cert, &parsed).then(|| (parsed.validity().is_valid(), candidate.clone()))
+        })
+        .collect::<Vec<_>>();
+    // Prefer certificates that are currently valid.
+    candidates.sort_by_key(|(valid, _)| !valid);
+    candidates
+        .into_iter()
+        .next()
+        .map(|(_, candidate)| candidate)
+}
+
+async fn fetch_issuer(
+    cert: &X509Certificate<'_>,
+    root_certs: &RootCertStore,
+) -> Option<Certificate> {
+    let urls = cert
+        .iter_extensions()
+        .filter_map(|ext| match ext.parsed_extension() {
+            ParsedExtension::AuthorityInfoAccess(aia) => Some(aia),
+            _ => None,
+        })
+        .flat_map(|aia| &aia.accessdescs)
+        .filter(|desc| desc.access_method == OID_PKIX_ACCESS_DESCRIPTOR_CA_ISSUERS)
+        .filter_map(|desc| match desc.access_location {
+            GeneralName::URI(uri) => Some(uri.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for url in urls {
+        let body = match tokio::time::timeout(AIA_FETCH_TIMEOUT, fetch(&url, root_certs)).await {
+            Ok(Ok(body)) => body,
+            Ok(Err(err)) => {
+                warn!(?err, url, "failed to fetch issuer certificate");
+                continue;
+            }
+            Err(_) => {
+                warn!(url, "fetching issuer certificate timed out");
+                continue;
+            }
+        };
+        let issuer = if super::is_pem(&body) {
+            rustls_pemfile::certs(&mut body.as_slice())
+                .ok()
+                .and_then(|certs| certs.into_iter().next())
+        } else {
+            Some(body)
+        };
+        let Some(issuer) = issuer.map(Certificate) else {
+            continue;
+        };
+        if let Ok((_, parsed)) = parse_x509_certificate(&issuer.0) {
+            if is_issued_by(cert, &parsed) {
+                return Some(issuer);
+            }
+        }
+        warn!(url, "fetched certificate is not the issuer");
+    }
+    None
+}
+
+async fn fetch(url: &str, root_certs: &RootCertStore) -> anyhow::Result<Vec<u8>> {
+    let res = https_client(root_certs).get(url.parse()?).await?;
+    if !res.status().is_success() {
+        anyhow::bail!("unexpected status: {}", res.status());
+    }
+    Ok(hyper::body::to_bytes(res.into_body()).await?.to_vec())
+}
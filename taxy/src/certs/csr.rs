@@ -0,0 +1,108 @@
+use super::{set_key_pair, Cert};
+use rcgen::{CertificateParams, DistinguishedName, DnType, SanType};
+use serde_derive::{Deserialize, Serialize};
+use std::time::SystemTime;
+use taxy_api::{
+    cert::{CertKind, CsrInfo, CsrRequest, KeyAlgorithm},
+    error::Error,
+    id::ShortId,
+    subject_name::SubjectName,
+};
+use tracing::error;
+
+/// A private key and its certificate signing request waiting for the signed certificate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsrEntry {
+    #[serde(skip)]
+    pub id: ShortId,
+    pub kind: CertKind,
+    pub san: Vec<SubjectName>,
+    pub common_name: String,
+    pub key_algorithm: KeyAlgorithm,
+    pub created_at: i64,
+    pub pem_csr: String,
+    pub pem_key: String,
+}
+
+impl CsrEntry {
+    pub fn new(id: ShortId, request: CsrRequest) -> Result<Self, Error> {
+        let mut params = CertificateParams::default();
+        params.subject_alt_names = request
+            .san
+            .iter()
+            .map(|name| {
+                if let SubjectName::IPAddress(ip) = name {
+                    SanType::IpAddress(*ip)
+                } else {
+                    SanType::DnsName(name.to_string())
+                }
+            })
+            .collect();
+
+        let common_name = request
+            .common_name
+            .clone()
+            .or_else(|| request.san.first().map(|name| name.to_string()))
+            .unwrap_or_else(|| "Taxy Cert".into());
+        let mut distinguished_name = DistinguishedName::new();
+        distinguished_name.push(DnType::CommonName, common_name.as_str());
+        if let Some(organization) = &request.organization {
+            distinguished_name.push(DnType::OrganizationName, organization.as_str());
+        }
+        params.distinguished_name = distinguished_name;
+        set_key_pair(&mut params, request.key_algorithm)?;
+
+        let cert = rcgen::Certificate::from_params(params).and_then(|cert| {
+            let pem_csr = cert.serialize_request_pem()?;
+            Ok((pem_csr, cert.serialize_private_key_pem()))
+        });
+        let (pem_csr, pem_key) = match cert {
+            Ok(cert) => cert,
+            Err(err) => {
+                error!(?err, "failed to generate csr");
+                return Err(Error::FailedToGenerateKeyPair);
+            }
+        };
+
+        let created_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|time| time.as_secs() as i64)
+            .unwrap_or_default();
+
+        Ok(Self {
+            id,
+            kind: request.kind,
+            san: request.san,
+            common_name,
+            key_algorithm: request.key_algorithm,
+            created_at,
+            pem_csr,
+            pem_key,
+        })
+    }
+
+    pub fn info(&self) -> CsrInfo {
+        CsrInfo {
+            id: self.id,
+            kind: self.kind,
+            san: self.san.clone(),
+            common_name: self.common_name.clone(),
+            key_algorithm: self.key_algorithm,
+            created_at: self.created_at,
+            csr: self.pem_csr.clone(),
+        }
+    }
+
+    /// Pairs the signed certificate chain with the private key of this request.
+    pub fn complete(&self, chain: &[u8]) -> Result<Cert, Error> {
+        Cert::new(
+            self.kind,
+            chain.to_vec(),
+            Some(self.pem_key.as_bytes().to_vec()),
+        )
+        .map_err(|err| match err {
+            Error::CertKeyMismatch => Error::CsrKeyMismatch { id: self.id },
+            err => err,
+        })
+    }
+}
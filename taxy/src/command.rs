@@ -1,21 +1,49 @@
 use crate::{
-    certs::{acme::AcmeOrder, Cert},
+    certs::{
+        acme::{AcmeOrder, Directory},
+        Cert,
+    },
     server::rpc::ErasedRpcMethod,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::SystemTime};
+use taxy_api::{history::ConfigSnapshot, id::ShortId};
 
 pub enum ServerCommand {
     AddCert {
         cert: Arc<Cert>,
     },
+    SetExternalCerts {
+        certs: Vec<Arc<Cert>>,
+    },
+    ApplyConfigFile {
+        snapshot: Box<ConfigSnapshot>,
+    },
     SetBroadcastEvents {
         enabled: bool,
     },
-    SetHttpChallenges {
+    SetAcmeChallenges {
         orders: Vec<AcmeOrder>,
     },
+    StartAcmeChallenges,
+    RequestOnDemandCert {
+        name: String,
+    },
+    AddOnDemandAcme {
+        name: String,
+        allowed: bool,
+    },
+    UpdateAcmeStatus {
+        id: ShortId,
+        update: AcmeStatusUpdate,
+    },
+    SetAcmeDirectory {
+        account_id: ShortId,
+        directory: Arc<Directory>,
+    },
     CallMethod {
         id: usize,
+        /// The user who called the method, recorded in the config history.
+        author: Option<String>,
         arg: Box<dyn ErasedRpcMethod>,
     },
 }
@@ -24,15 +52,61 @@ impl std::fmt::Debug for ServerCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::AddCert { cert } => f.debug_struct("AddCert").field("id", &cert.id()).finish(),
+            Self::SetExternalCerts { certs } => f
+                .debug_struct("SetExternalCerts")
+                .field("certs", &certs.len())
+                .finish(),
+            Self::ApplyConfigFile { snapshot } => f
+                .debug_struct("ApplyConfigFile")
+                .field("ports", &snapshot.ports.len())
+                .field("proxies", &snapshot.proxies.len())
+                .field("acme", &snapshot.acme.len())
+                .finish(),
             Self::SetBroadcastEvents { enabled } => f
                 .debug_struct("SetBroadcastEvents")
                 .field("enabled", enabled)
                 .finish(),
-            Self::SetHttpChallenges { orders } => f
-                .debug_struct("SetHttpChallenges")
+            Self::SetAcmeChallenges { orders } => f
+                .debug_struct("SetAcmeChallenges")
                 .field("orders", &orders.len())
                 .finish(),
-            Self::CallMethod { id, .. } => f.debug_struct("CallMethod").field("id", id).finish(),
+            Self::StartAcmeChallenges => f.debug_struct("StartAcmeChallenges").finish(),
+            Self::RequestOnDemandCert { name } => f
+                .debug_struct("RequestOnDemandCert")
+                .field("name", name)
+                .finish(),
+            Self::AddOnDemandAcme { name, allowed } => f
+                .debug_struct("AddOnDemandAcme")
+                .field("name", name)
+                .field("allowed", allowed)
+                .finish(),
+            Self::UpdateAcmeStatus { id, update } => f
+                .debug_struct("UpdateAcmeStatus")
+                .field("id", id)
+                .field("update", update)
+                .finish(),
+            Self::SetAcmeDirectory { account_id, .. } => f
+                .debug_struct("SetAcmeDirectory")
+                .field("account_id", account_id)
+                .finish(),
+            Self::CallMethod { id, author, .. } => f
+                .debug_struct("CallMethod")
+                .field("id", id)
+                .field("author", author)
+                .finish(),
         }
     }
 }
+
+#[derive(Debug)]
+pub enum AcmeStatusUpdate {
+    Requesting,
+    RenewalSuggested {
+        cert: ShortId,
+        time: Option<SystemTime>,
+        next_check_at: SystemTime,
+    },
+    Failed {
+        error: String,
+    },
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+use taxy_api::error::Error;
+use taxy_api::history::{ConfigFormat, ConfigSnapshot};
+
+/// Serializes the whole config as a single document.
+pub fn to_string(snapshot: &ConfigSnapshot, format: ConfigFormat) -> Result<String, Error> {
+    let result = match format {
+        ConfigFormat::Toml => toml::to_string_pretty(snapshot).map_err(|err| err.to_string()),
+        ConfigFormat::Yaml => serde_yaml::to_string(snapshot).map_err(|err| err.to_string()),
+        ConfigFormat::Json => serde_json::to_string_pretty(snapshot).map_err(|err| err.to_string()),
+    };
+    result.map_err(|reason| Error::InvalidConfigDocument { reason })
+}
+
+pub fn from_slice(data: &[u8], format: ConfigFormat) -> Result<ConfigSnapshot, Error> {
+    let result = match format {
+        ConfigFormat::Toml => std::str::from_utf8(data)
+            .map_err(|err| err.to_string())
+            .and_then(|data| toml::from_str(data).map_err(|err| err.to_string())),
+        ConfigFormat::Yaml => serde_yaml::from_slice(data).map_err(|err| err.to_string()),
+        ConfigFormat::Json => serde_json::from_slice(data).map_err(|err| err.to_string()),
+    };
+    result.map_err(|reason| Error::InvalidConfigDocument { reason })
+}
+
+pub fn content_type(format: ConfigFormat) -> &'static str {
+    match format {
+        ConfigFormat::Toml => "application/toml",
+        ConfigFormat::Yaml => "application/yaml",
+        ConfigFormat::Json => "application/json",
+    }
+}
+
+/// Guesses the format from a file extension, defaulting to TOML.
+pub fn format_from_path(path: &Path) -> ConfigFormat {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml" | "yml") => ConfigFormat::Yaml,
+        Some("json") => ConfigFormat::Json,
+        _ => ConfigFormat::Toml,
+    }
+}
+
+/// Guesses the format from a `Content-Type` header value.
+pub fn format_from_content_type(content_type: &str) -> Option<ConfigFormat> {
+    let mime = content_type.split(';').next()?.trim();
+    match mime {
+        "application/toml" => Some(ConfigFormat::Toml),
+        "application/yaml" | "application/x-yaml" | "text/yaml" => Some(ConfigFormat::Yaml),
+        "application/json" => Some(ConfigFormat::Json),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use taxy_api::port::{Port, PortEntry, PortOptions};
+    use taxy_api::proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind, Route, Server};
+
+    #[test]
+    fn test_round_trip() {
+        let snapshot = ConfigSnapshot {
+            ports: vec![PortEntry {
+                id: "http".parse().unwrap(),
+                port: Port {
+                    active: true,
+                    name: "HTTP".into(),
+                    listen: "/ip4/0.0.0.0/tcp/8080/http".parse().unwrap(),
+                    opts: PortOptions::default(),
+                },
+            }],
+            proxies: vec![ProxyEntry {
+                id: "proxy".parse().unwrap(),
+                proxy: Proxy {
+                    ports: vec!["http".parse().unwrap()],
+                    kind: ProxyKind::Http(HttpProxy {
+                        vhosts: vec!["example.com".parse().unwrap()],
+                        routes: vec![Route {
+                            path: "/".into(),
+                            servers: vec![Server {
+                                url: "http://127.0.0.1:3000".parse().unwrap(),
+                            }],
+                        }],
+                    }),
+                    ..Default::default()
+                },
+            }],
+            certs: vec!["cert".parse().unwrap()],
+            ..Default::default()
+        };
+        for format in [ConfigFormat::Toml, ConfigFormat::Yaml, ConfigFormat::Json] {
+            let data = to_string(&snapshot, format).unwrap();
+            assert_eq!(from_slice(data.as_bytes(), format).unwrap(), snapshot);
+        }
+        assert!(matches!(
+            from_slice(b"ports = 1", ConfigFormat::Toml),
+            Err(Error::InvalidConfigDocument { .. })
+        ));
+        assert_eq!(
+            format_from_content_type("application/yaml; charset=utf-8"),
+            Some(ConfigFormat::Yaml)
+        );
+    }
+}
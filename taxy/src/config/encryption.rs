@@ -0,0 +1,297 @@
+use argon2::{password_hash::SaltString, Argon2};
+use base64::{engine::general_purpose, Engine as _};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, path::Path};
+
+const KEY_LEN: usize = 32;
+const SEALED_PREFIX: &str = "enc:v1:";
+
+/// Fields of the stored records that contain secrets.
+pub const ACME_SECRET_FIELDS: &[&str] = &["dns_credentials"];
+pub const ACME_ACCOUNT_SECRET_FIELDS: &[&str] = &["credentials"];
+pub const CSR_SECRET_FIELDS: &[&str] = &["pem_key"];
+pub const ACCOUNT_SECRET_FIELDS: &[&str] = &["totp"];
+
+/// The key that encrypts the data keys of the secrets in the config directory.
+#[derive(Clone)]
+pub struct MasterKey {
+    key: [u8; KEY_LEN],
+    salt: Option<String>,
+}
+
+impl fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterKey").field("id", &self.id()).finish()
+    }
+}
+
+impl MasterKey {
+    pub fn generate() -> anyhow::Result<Self> {
+        let mut key = [0; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| anyhow::anyhow!("failed to generate master key"))?;
+        Ok(Self { key, salt: None })
+    }
+
+    /// Parses a base64 encoded 256-bit key.
+    pub fn from_base64(encoded: &str) -> anyhow::Result<Self> {
+        let decoded = general_purpose::STANDARD.decode(encoded.trim())?;
+        let key = decoded
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("master key must be {KEY_LEN} bytes long"))?;
+        Ok(Self { key, salt: None })
+    }
+
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_base64(&content)
+    }
+
+    /// Derives a key from a passphrase. A new salt is generated if `salt` is `None`.
+    pub fn from_passphrase(passphrase: &str, salt: Option<&str>) -> anyhow::Result<Self> {
+        let salt = match salt {
+            Some(salt) => salt.to_string(),
+            None => SaltString::generate(rand::thread_rng()).to_string(),
+        };
+        let mut key = [0; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt.as_bytes(), &mut key)
+            .map_err(|err| anyhow::anyhow!("failed to derive master key: {err}"))?;
+        Ok(Self {
+            key,
+            salt: Some(salt),
+        })
+    }
+
+    /// A fingerprint of the key, used to detect a wrong key before decrypting anything.
+    pub fn id(&self) -> String {
+        hex::encode(&Sha256::digest(self.key)[..8])
+    }
+
+    /// Encrypts `data` with a new data key and wraps the data key with this key.
+    pub fn seal(&self, data: &[u8]) -> anyhow::Result<String> {
+        let rng = SystemRandom::new();
+        let mut data_key = [0; KEY_LEN];
+        rng.fill(&mut data_key)
+            .map_err(|_| anyhow::anyhow!("failed to generate data key"))?;
+        let ciphertext = encrypt(&data_key, &[], data)?;
+        self.wrap_data_key(&data_key, &ciphertext)
+    }
+
+    pub fn open(&self, sealed: &str) -> anyhow::Result<Vec<u8>> {
+        let (data_key, ciphertext) = self.unwrap_data_key(sealed)?;
+        decrypt(&data_key, &[], &ciphertext)
+    }
+
+    /// Re-wraps the data key of `sealed` with `new` without decrypting the data.
+    pub fn rewrap(&self, sealed: &str, new: &MasterKey) -> anyhow::Result<String> {
+        let (data_key, ciphertext) = self.unwrap_data_key(sealed)?;
+        new.wrap_data_key(&data_key, &ciphertext)
+    }
+
+    fn wrap_data_key(&self, data_key: &[u8], ciphertext: &[u8]) -> anyhow::Result<String> {
+        let wrapped_key = encrypt(&self.key, self.id().as_bytes(), data_key)?;
+        Ok(format!(
+            "{SEALED_PREFIX}{}:{}:{}",
+            self.id(),
+            general_purpose::URL_SAFE_NO_PAD.encode(wrapped_key),
+            general_purpose::URL_SAFE_NO_PAD.encode(ciphertext)
+        ))
+    }
+
+    fn unwrap_data_key(&self, sealed: &str) -> anyhow::Result<([u8; KEY_LEN], Vec<u8>)> {
+        let parts = sealed
+            .trim()
+            .strip_prefix(SEALED_PREFIX)
+            .map(|rest| rest.split(':').collect::<Vec<_>>())
+            .filter(|parts| parts.len() == 3)
+            .ok_or_else(|| anyhow::anyhow!("malformed encrypted value"))?;
+        if parts[0] != self.id() {
+            anyhow::bail!("value is encrypted with another master key: {}", parts[0]);
+        }
+        let wrapped_key = general_purpose::URL_SAFE_NO_PAD.decode(parts[1])?;
+        let ciphertext = general_purpose::URL_SAFE_NO_PAD.decode(parts[2])?;
+        let data_key = decrypt(&self.key, self.id().as_bytes(), &wrapped_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("malformed data key"))?;
+        Ok((data_key, ciphertext))
+    }
+}
+
+/// Returns `true` if `value` is a value sealed by [`MasterKey::seal`].
+pub fn is_sealed(value: &str) -> bool {
+    value.trim_start().starts_with(SEALED_PREFIX)
+}
+
+/// Returns the ID of the master key that sealed `value`.
+pub fn sealed_key_id(value: &str) -> Option<&str> {
+    value
+        .trim_start()
+        .strip_prefix(SEALED_PREFIX)
+        .and_then(|rest| rest.split(':').next())
+}
+
+/// Encrypts `data` if a master key is given.
+pub fn seal_secret(key: Option<&MasterKey>, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match key {
+        Some(key) => Ok(key.seal(data)?.into_bytes()),
+        None => Ok(data.to_vec()),
+    }
+}
+
+/// Decrypts `data` if it is sealed, or returns it as is.
+pub fn open_secret(key: Option<&MasterKey>, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    match std::str::from_utf8(&data) {
+        Ok(sealed) if is_sealed(sealed) => required(key)?.open(sealed),
+        _ => Ok(data),
+    }
+}
+
+/// Re-encrypts `data` with `new`, or decrypts it if `new` is `None`.
+///
+/// Returns `None` if `data` is already in the desired state.
+pub fn reseal_secret(
+    current: Option<&MasterKey>,
+    data: &[u8],
+    new: Option<&MasterKey>,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let sealed = std::str::from_utf8(data)
+        .ok()
+        .filter(|data| is_sealed(data));
+    match (sealed, new) {
+        (Some(sealed), Some(new)) if sealed_key_id(sealed) == Some(new.id().as_str()) => Ok(None),
+        (Some(sealed), Some(new)) => Ok(Some(required(current)?.rewrap(sealed, new)?.into_bytes())),
+        (Some(sealed), None) => Ok(Some(required(current)?.open(sealed)?)),
+        (None, Some(new)) => Ok(Some(new.seal(data)?.into_bytes())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Replaces `fields` of a JSON object with their encrypted JSON if a master key is given.
+pub fn seal_fields(
+    key: Option<&MasterKey>,
+    value: &mut serde_json::Value,
+    fields: &[&str],
+) -> anyhow::Result<()> {
+    if let (Some(key), Some(object)) = (key, value.as_object_mut()) {
+        for field in fields {
+            if let Some(value) = object.get_mut(*field) {
+                *value = serde_json::Value::String(key.seal(&serde_json::to_vec(value)?)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decrypts `fields` sealed by [`seal_fields`].
+pub fn open_fields(
+    key: Option<&MasterKey>,
+    value: &mut serde_json::Value,
+    fields: &[&str],
+) -> anyhow::Result<()> {
+    if let Some(object) = value.as_object_mut() {
+        for field in fields {
+            if let Some(serde_json::Value::String(sealed)) = object.get(*field) {
+                if is_sealed(sealed) {
+                    let data = required(key)?.open(sealed)?;
+                    object.insert(field.to_string(), serde_json::from_slice(&data)?);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Re-encrypts `fields` with `new`, or decrypts them if `new` is `None`.
+///
+/// Returns the number of fields that have changed.
+pub fn reseal_fields(
+    current: Option<&MasterKey>,
+    value: &mut serde_json::Value,
+    fields: &[&str],
+    new: Option<&MasterKey>,
+) -> anyhow::Result<usize> {
+    let Some(object) = value.as_object_mut() else {
+        return Ok(0);
+    };
+    let mut count = 0;
+    for field in fields {
+        let Some(value) = object.get_mut(*field) else {
+            continue;
+        };
+        let data = match value {
+            serde_json::Value::String(sealed) if is_sealed(sealed) => sealed.as_bytes().to_vec(),
+            _ => serde_json::to_vec(value)?,
+        };
+        if let Some(resealed) = reseal_secret(current, &data, new)? {
+            *value = match new {
+                Some(_) => serde_json::Value::String(String::from_utf8(resealed)?),
+                None => serde_json::from_slice(&resealed)?,
+            };
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn required(key: Option<&MasterKey>) -> anyhow::Result<&MasterKey> {
+    key.ok_or_else(|| anyhow::anyhow!("the secret is encrypted, but no master key is given"))
+}
+
+/// The contents of `encryption.toml`, which marks the config directory as encrypted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    pub key_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+}
+
+impl From<&MasterKey> for EncryptionConfig {
+    fn from(key: &MasterKey) -> Self {
+        Self {
+            key_id: key.id(),
+            salt: key.salt.clone(),
+        }
+    }
+}
+
+fn encrypt(key: &[u8], aad: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow::anyhow!("invalid key"))?,
+    );
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow::anyhow!("failed to generate nonce"))?;
+    let mut in_out = data.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(aad),
+        &mut in_out,
+    )
+    .map_err(|_| anyhow::anyhow!("failed to encrypt"))?;
+    Ok([&nonce[..], &in_out].concat())
+}
+
+fn decrypt(key: &[u8], aad: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        anyhow::bail!("malformed encrypted value");
+    }
+    let key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow::anyhow!("invalid key"))?,
+    );
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| anyhow::anyhow!("malformed encrypted value"))?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(aad), &mut in_out)
+        .map_err(|_| anyhow::anyhow!("failed to decrypt"))?;
+    Ok(plaintext.to_vec())
+}
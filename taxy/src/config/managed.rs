@@ -0,0 +1,188 @@
+use super::{document, storage::Storage};
+use crate::certs::{
+    acme::{move_url_credentials, AcmeAccountEntry, AcmeEntry},
+    ca::CaEntry,
+    csr::CsrEntry,
+    Cert,
+};
+use crate::server::ON_DEMAND_PROVIDER;
+use std::{path::Path, sync::Arc};
+use taxy_api::{
+    app::AppConfig,
+    auth::{Account, LoginRequest, LoginResponse},
+    error::Error,
+    history::{ConfigSnapshot, ConfigVersion},
+    id::ShortId,
+    port::PortEntry,
+    proxy::ProxyEntry,
+};
+use tracing::warn;
+
+/// Loads a user-authored config file. The format is taken from the file extension.
+pub async fn load(path: &Path) -> anyhow::Result<ConfigSnapshot> {
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to read {}: {err}", path.display()))?;
+    let snapshot = document::from_slice(&data, document::format_from_path(path))
+        .map_err(|err| anyhow::anyhow!("failed to load {}: {err}", path.display()))?;
+
+    // Exported documents can be used as they are, but only their ports,
+    // proxies and ACME entries are taken from the file.
+    if snapshot.config != AppConfig::default() {
+        warn!(?path, "ignoring the config section of the config file");
+    }
+    if !snapshot.certs.is_empty() {
+        warn!(?path, "ignoring the certs section of the config file");
+    }
+    Ok(snapshot)
+}
+
+/// A storage whose ports, proxies and ACME entries come from a read-only config file.
+///
+/// Changes to those resources are kept in memory only, except for the ACME
+/// entries added by on-demand TLS, which are never in the file. Everything
+/// else is delegated to the inner storage.
+pub struct ManagedStorage<S> {
+    inner: S,
+    snapshot: ConfigSnapshot,
+}
+
+impl<S: Storage> ManagedStorage<S> {
+    pub async fn new(inner: S, path: &Path) -> anyhow::Result<Self> {
+        let snapshot = load(path).await?;
+        Ok(Self { inner, snapshot })
+    }
+
+    fn is_runtime_acme(&self, acme: &AcmeEntry) -> bool {
+        acme.acme.config.provider == ON_DEMAND_PROVIDER
+            && !self.snapshot.acme.iter().any(|entry| entry.id == acme.id)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Storage> Storage for ManagedStorage<S> {
+    async fn save_app_config(&self, config: &AppConfig) {
+        self.inner.save_app_config(config).await
+    }
+
+    async fn load_app_config(&self) -> AppConfig {
+        self.inner.load_app_config().await
+    }
+
+    async fn save_ports(&self, _entries: &[PortEntry]) {}
+
+    async fn load_ports(&self) -> Vec<PortEntry> {
+        self.snapshot.ports.clone()
+    }
+
+    async fn load_proxies(&self) -> Vec<ProxyEntry> {
+        self.snapshot.proxies.clone()
+    }
+
+    async fn save_proxies(&self, _proxies: &[ProxyEntry]) {}
+
+    async fn save_cert(&self, cert: &Cert) {
+        self.inner.save_cert(cert).await
+    }
+
+    async fn save_acme(&self, acme: &AcmeEntry) {
+        if self.is_runtime_acme(acme) {
+            self.inner.save_acme(acme).await
+        }
+    }
+
+    async fn delete_acme(&self, id: ShortId) {
+        if !self.snapshot.acme.iter().any(|entry| entry.id == id) {
+            self.inner.delete_acme(id).await
+        }
+    }
+
+    async fn delete_cert(&self, id: ShortId) {
+        self.inner.delete_cert(id).await
+    }
+
+    async fn load_acmes(&self) -> Vec<AcmeEntry> {
+        self.snapshot
+            .acme
+            .iter()
+            .map(|entry| {
+                let mut acme = entry.acme.clone();
+                if let Some(dns) = &mut acme.dns_challenge {
+                    move_url_credentials(dns);
+                }
+                AcmeEntry {
+                    id: entry.id,
+                    acme,
+                    account_id: entry.account_id,
+                }
+            })
+            .chain(
+                self.inner
+                    .load_acmes()
+                    .await
+                    .into_iter()
+                    .filter(|acme| self.is_runtime_acme(acme)),
+            )
+            .collect()
+    }
+
+    async fn save_acme_account(&self, account: &AcmeAccountEntry) {
+        self.inner.save_acme_account(account).await
+    }
+
+    async fn delete_acme_account(&self, id: ShortId) {
+        self.inner.delete_acme_account(id).await
+    }
+
+    async fn load_acme_accounts(&self) -> Vec<AcmeAccountEntry> {
+        self.inner.load_acme_accounts().await
+    }
+
+    async fn save_ca(&self, ca: &CaEntry) {
+        self.inner.save_ca(ca).await
+    }
+
+    async fn delete_ca(&self, id: ShortId) {
+        self.inner.delete_ca(id).await
+    }
+
+    async fn load_cas(&self) -> Vec<CaEntry> {
+        self.inner.load_cas().await
+    }
+
+    async fn save_csr(&self, csr: &CsrEntry) {
+        self.inner.save_csr(csr).await
+    }
+
+    async fn delete_csr(&self, id: ShortId) {
+        self.inner.delete_csr(id).await
+    }
+
+    async fn load_csrs(&self) -> Vec<CsrEntry> {
+        self.inner.load_csrs().await
+    }
+
+    async fn load_certs(&self) -> Vec<Arc<Cert>> {
+        self.inner.load_certs().await
+    }
+
+    async fn add_account(&self, name: &str, password: &str, totp: bool) -> Result<Account, Error> {
+        self.inner.add_account(name, password, totp).await
+    }
+
+    async fn verify_account(&self, request: LoginRequest) -> Result<LoginResponse, Error> {
+        self.inner.verify_account(request).await
+    }
+
+    async fn save_config_version(&self, version: &ConfigVersion) {
+        self.inner.save_config_version(version).await
+    }
+
+    async fn delete_config_version(&self, version: u64) {
+        self.inner.delete_config_version(version).await
+    }
+
+    async fn load_config_history(&self) -> Vec<ConfigVersion> {
+        self.inner.load_config_history().await
+    }
+}
@@ -0,0 +1,177 @@
+use super::build_info;
+use toml_edit::{Document, Item, Table};
+use tracing::warn;
+
+/// The kind of config file a migration applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKind {
+    Ports,
+    Proxies,
+    Acme,
+}
+
+/// A change to the format of the entries in a config file.
+struct Migration {
+    kind: ConfigKind,
+    /// The first version that writes the new format.
+    /// Files written by older versions are migrated.
+    version: Version,
+    description: &'static str,
+    /// Rewrites a single entry. Returns true if the entry has been changed.
+    migrate: fn(&mut Table) -> bool,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    kind: ConfigKind::Acme,
+    version: Version(0, 3, 18),
+    description: "replaced renewal_days with renew_before_days",
+    migrate: migrate_renewal_days,
+}];
+
+/// Lifetime of the certificates that `renewal_days` was tuned for.
+const ACME_CERT_LIFETIME_DAYS: i64 = 90;
+
+/// Older versions renewed a certificate `renewal_days` after it had been issued.
+/// Expiry-based renewal counts the days remaining instead.
+fn migrate_renewal_days(entry: &mut Table) -> bool {
+    let Some(item) = entry.remove("renewal_days") else {
+        return false;
+    };
+    let days = item.as_integer().unwrap_or_default();
+    if !entry.contains_key("renew_before_days") && days > 0 && days < ACME_CERT_LIFETIME_DAYS {
+        entry["renew_before_days"] = toml_edit::value(ACME_CERT_LIFETIME_DAYS - days);
+    }
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Version(u64, u64, u64);
+
+impl Version {
+    fn parse(version: &str) -> Option<Self> {
+        let version = version.split(['-', '+']).next()?;
+        let mut parts = version.split('.').map(|part| part.parse().ok());
+        let version = Self(parts.next()??, parts.next()??, parts.next()??);
+        parts.next().is_none().then_some(version)
+    }
+}
+
+/// Returns the version a document was written by.
+///
+/// Files written before v0.3.15 do not record it.
+pub fn document_version(doc: &Document) -> String {
+    doc.get("version")
+        .and_then(|version| version.as_str())
+        .unwrap_or("0.0.0")
+        .to_string()
+}
+
+/// Upgrades the entries of a document written by an older version.
+///
+/// Returns a description of each change. The `version` field is left as it is.
+pub fn migrate(kind: ConfigKind, doc: &mut Document) -> Vec<String> {
+    let version = document_version(doc);
+    let Some(from) = Version::parse(&version) else {
+        warn!(version, "unknown config version; skipping migrations");
+        return Vec::new();
+    };
+
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.kind == kind && from < migration.version)
+    {
+        for (id, item) in doc.as_table_mut().iter_mut() {
+            if let Item::Table(entry) = item {
+                if (migration.migrate)(entry) {
+                    changes.push(format!("{id}: {}", migration.description));
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// Returns true if a document was written by a newer version than this one.
+pub fn is_newer(doc: &Document) -> bool {
+    match (
+        Version::parse(&document_version(doc)),
+        Version::parse(build_info::PKG_VERSION),
+    ) {
+        (Some(version), Some(current)) => version > current,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_migrate_renewal_days() {
+        let mut doc = r#"
+version = "0.3.17"
+
+[abc]
+identifiers = ["example.com"]
+challenge_type = "http-01"
+renewal_days = 70
+
+[def]
+identifiers = ["example.org"]
+challenge_type = "http-01"
+renewal_days = 60
+renew_before_days = 10
+
+[ghi]
+identifiers = ["example.net"]
+challenge_type = "http-01"
+"#
+        .parse::<Document>()
+        .unwrap();
+
+        let changes = migrate(ConfigKind::Acme, &mut doc);
+        assert_eq!(
+            changes,
+            vec![
+                "abc: replaced renewal_days with renew_before_days",
+                "def: replaced renewal_days with renew_before_days",
+            ]
+        );
+        assert_eq!(doc["abc"]["renew_before_days"].as_integer(), Some(20));
+        assert_eq!(doc["def"]["renew_before_days"].as_integer(), Some(10));
+        assert!(doc["abc"].get("renewal_days").is_none());
+        assert!(doc["ghi"].get("renew_before_days").is_none());
+
+        let mut doc = "version = \"0.3.18\"\n[abc]\nrenewal_days = 70\n"
+            .parse::<Document>()
+            .unwrap();
+        assert!(migrate(ConfigKind::Acme, &mut doc).is_empty());
+        assert!(migrate(ConfigKind::Ports, &mut doc).is_empty());
+    }
+
+    #[test]
+    fn test_current_version_is_not_migrated() {
+        let current = Version::parse(build_info::PKG_VERSION).unwrap();
+        for migration in MIGRATIONS {
+            assert!(migration.version <= current, "{}", migration.description);
+        }
+
+        let mut doc = format!(
+            "version = \"{}\"\n[abc]\nrenewal_days = 70\n",
+            build_info::PKG_VERSION
+        )
+        .parse::<Document>()
+        .unwrap();
+        assert!(migrate(ConfigKind::Acme, &mut doc).is_empty());
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(Version::parse("0.3.17"), Some(Version(0, 3, 17)));
+        assert_eq!(Version::parse("1.2.3-beta.1"), Some(Version(1, 2, 3)));
+        assert_eq!(Version::parse("1.2"), None);
+        assert_eq!(Version::parse("1.2.3.4"), None);
+        assert!(Version::parse("0.3.9") < Version::parse("0.3.10"));
+    }
+}
@@ -1,7 +1,12 @@
 use std::path::Path;
 use taxy_api::app::AppInfo;
 
+pub mod document;
+pub mod encryption;
 pub mod file;
+pub mod managed;
+mod migration;
+pub mod sqlite;
 pub mod storage;
 
 mod build_info {
@@ -17,5 +22,6 @@ pub fn new_appinfo(config_path: &Path, log_path: &Path) -> AppInfo {
         rustc: build_info::RUSTC_VERSION,
         config_path: config_path.to_owned(),
         log_path: log_path.to_owned(),
+        config_file: None,
     }
 }
@@ -0,0 +1,992 @@
+use super::{
+    encryption::{
+        self, EncryptionConfig, MasterKey, ACCOUNT_SECRET_FIELDS, ACME_ACCOUNT_SECRET_FIELDS,
+        ACME_SECRET_FIELDS, CSR_SECRET_FIELDS,
+    },
+    file::{new_account, verify_login, FileStorage},
+    storage::Storage,
+};
+use crate::certs::{
+    acme::{AcmeAccountEntry, AcmeAccountRecord, AcmeEntry, AcmeRecord},
+    ca::{CaEntry, CaRecord},
+    csr::CsrEntry,
+    Cert,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode},
+    ConnectOptions, Row, SqliteConnection, SqlitePool,
+};
+use std::{path::Path, sync::Arc};
+use taxy_api::{
+    app::AppConfig,
+    auth::{Account, LoginRequest, LoginResponse},
+    cert::CertKind,
+    error::Error,
+    history::ConfigVersion,
+    id::ShortId,
+    port::{Port, PortEntry},
+    proxy::{Proxy, ProxyEntry},
+};
+use tracing::{error, info, warn};
+
+/// Schema migrations. The index of the last applied migration is kept in `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE settings (
+        name  TEXT PRIMARY KEY NOT NULL,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE ports (
+        id       TEXT PRIMARY KEY NOT NULL,
+        position INTEGER NOT NULL,
+        data     TEXT NOT NULL
+    );
+    CREATE TABLE proxies (
+        id       TEXT PRIMARY KEY NOT NULL,
+        position INTEGER NOT NULL,
+        data     TEXT NOT NULL
+    );
+    CREATE TABLE certs (
+        id    TEXT PRIMARY KEY NOT NULL,
+        kind  TEXT NOT NULL,
+        chain BLOB NOT NULL,
+        key   BLOB
+    );
+    CREATE TABLE acmes (
+        id   TEXT PRIMARY KEY NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE acme_accounts (
+        id   TEXT PRIMARY KEY NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE cas (
+        id   TEXT PRIMARY KEY NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE csrs (
+        id   TEXT PRIMARY KEY NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE accounts (
+        name TEXT PRIMARY KEY NOT NULL,
+        data TEXT NOT NULL
+    );",
+    "CREATE TABLE history (
+        version INTEGER PRIMARY KEY NOT NULL,
+        data    TEXT NOT NULL
+    );",
+];
+
+/// Tables of JSON records keyed by ID, and the fields that contain secrets.
+const ACMES: (&str, &[&str]) = ("acmes", ACME_SECRET_FIELDS);
+const ACME_ACCOUNTS: (&str, &[&str]) = ("acme_accounts", ACME_ACCOUNT_SECRET_FIELDS);
+const CAS: (&str, &[&str]) = ("cas", &[]);
+const CSRS: (&str, &[&str]) = ("csrs", CSR_SECRET_FIELDS);
+
+/// Set when the database is created, and cleared by the import of the config files.
+const IMPORT_PENDING: &str = "import_pending";
+
+pub struct SqliteStorage {
+    pool: SqlitePool,
+    master_key: Option<MasterKey>,
+    created: bool,
+}
+
+impl SqliteStorage {
+    /// Opens the database at `path`, creating it and applying schema migrations as needed.
+    pub async fn new(path: &Path) -> anyhow::Result<Self> {
+        let opt = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .log_statements(log::LevelFilter::Trace);
+        let pool = SqlitePool::connect_with(opt).await?;
+        migrate(&pool).await?;
+        let mut conn = pool.acquire().await?;
+        let created = read_setting::<bool>(&mut conn, IMPORT_PENDING)
+            .await?
+            .unwrap_or_default();
+        drop(conn);
+        Ok(Self {
+            pool,
+            master_key: None,
+            created,
+        })
+    }
+
+    /// Encrypts private keys, ACME account credentials and TOTP secrets with `master_key`.
+    pub fn with_master_key(mut self, master_key: Option<MasterKey>) -> Self {
+        self.master_key = master_key;
+        self
+    }
+
+    /// Returns `true` if the database has been created by [`Self::new`]
+    /// and the config files have not been imported into it yet.
+    pub fn is_new(&self) -> bool {
+        self.created
+    }
+
+    /// Copies the configuration stored in TOML files into the database.
+    pub async fn import(&self, files: &FileStorage) -> anyhow::Result<()> {
+        info!("import config files");
+        let mut tx = self.pool.begin().await?;
+        write_setting(&mut tx, "app_config", &files.load_app_config().await).await?;
+        write_ports(&mut tx, &files.load_ports().await).await?;
+        write_proxies(&mut tx, &files.load_proxies().await).await?;
+        for cert in files.load_certs().await {
+            self.write_cert(&mut tx, &cert).await?;
+        }
+        for acme in files.load_acmes().await {
+            let (id, record): (ShortId, AcmeRecord) = acme.into();
+            self.write_entry(&mut tx, ACMES, id, &record).await?;
+        }
+        for account in files.load_acme_accounts().await {
+            let (id, record): (ShortId, AcmeAccountRecord) = account.into();
+            self.write_entry(&mut tx, ACME_ACCOUNTS, id, &record)
+                .await?;
+        }
+        for ca in files.load_cas().await {
+            let (id, record): (ShortId, CaRecord) = ca.into();
+            self.write_entry(&mut tx, CAS, id, &record).await?;
+        }
+        for csr in files.load_csrs().await {
+            self.write_entry(&mut tx, CSRS, csr.id, &csr).await?;
+        }
+        match files.load_accounts().await {
+            Ok(accounts) => {
+                for (name, account) in accounts {
+                    self.write_account(&mut tx, &name, &account).await?;
+                }
+            }
+            Err(err) => warn!("failed to load accounts: {err}"),
+        }
+        for version in files.load_config_history().await {
+            sqlx::query("INSERT INTO history (version, data) VALUES (?, ?)")
+                .bind(version.version as i64)
+                .bind(serde_json::to_string(&version)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM settings WHERE name = ?")
+            .bind(IMPORT_PENDING)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn load_encryption_config(&self) -> anyhow::Result<Option<EncryptionConfig>> {
+        let mut conn = self.pool.acquire().await?;
+        read_setting(&mut conn, "encryption").await
+    }
+
+    /// Checks the master key against the stored key ID.
+    ///
+    /// If the database is not encrypted yet and a master key is given,
+    /// the existing secrets are encrypted with it.
+    pub async fn init_encryption(&self) -> anyhow::Result<()> {
+        match (self.load_encryption_config().await?, &self.master_key) {
+            (None, None) => Ok(()),
+            (Some(_), None) => {
+                anyhow::bail!("the database is encrypted, but no master key is given")
+            }
+            (Some(config), Some(key)) => {
+                if config.key_id != key.id() {
+                    anyhow::bail!("the master key does not match: expected {}", config.key_id);
+                }
+                Ok(())
+            }
+            (None, Some(key)) => {
+                let count = self.reseal_secrets(Some(key)).await?;
+                info!(count, "encrypted the existing secrets");
+                Ok(())
+            }
+        }
+    }
+
+    /// Re-encrypts all secrets with `new`, or decrypts them if `new` is `None`.
+    ///
+    /// Only the data keys are re-encrypted, in a single transaction.
+    pub async fn rotate_master_key(&self, new: Option<&MasterKey>) -> anyhow::Result<usize> {
+        if let Some(config) = self.load_encryption_config().await? {
+            if self.master_key.as_ref().map(|key| key.id()) != Some(config.key_id.clone()) {
+                anyhow::bail!("the master key does not match: expected {}", config.key_id);
+            }
+        }
+        self.reseal_secrets(new).await
+    }
+
+    async fn reseal_secrets(&self, new: Option<&MasterKey>) -> anyhow::Result<usize> {
+        let current = self.master_key.as_ref();
+        let mut count = 0;
+        let mut tx = self.pool.begin().await?;
+
+        let rows = sqlx::query("SELECT id, key FROM certs WHERE key IS NOT NULL")
+            .fetch_all(&mut *tx)
+            .await?;
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            let key: Vec<u8> = row.try_get("key")?;
+            if let Some(key) = encryption::reseal_secret(current, &key, new)? {
+                sqlx::query("UPDATE certs SET key = ? WHERE id = ?")
+                    .bind(key)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                count += 1;
+            }
+        }
+
+        for (table, fields) in [
+            ACMES,
+            ACME_ACCOUNTS,
+            CSRS,
+            ("accounts", ACCOUNT_SECRET_FIELDS),
+        ] {
+            let rows = sqlx::query(&format!("SELECT rowid, data FROM {table}"))
+                .fetch_all(&mut *tx)
+                .await?;
+            for row in rows {
+                let rowid: i64 = row.try_get("rowid")?;
+                let data: String = row.try_get("data")?;
+                let mut value = serde_json::from_str(&data)?;
+                let resealed = encryption::reseal_fields(current, &mut value, fields, new)?;
+                if resealed > 0 {
+                    sqlx::query(&format!("UPDATE {table} SET data = ? WHERE rowid = ?"))
+                        .bind(serde_json::to_string(&value)?)
+                        .bind(rowid)
+                        .execute(&mut *tx)
+                        .await?;
+                    count += resealed;
+                }
+            }
+        }
+
+        match new {
+            Some(key) => {
+                write_setting(&mut tx, "encryption", &EncryptionConfig::from(key)).await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM settings WHERE name = 'encryption'")
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+        self.purge_stale_pages().await?;
+        Ok(count)
+    }
+
+    /// Rebuilds the database file and empties the WAL, so that no copy of
+    /// the replaced secrets is left in free pages or unchecked WAL frames.
+    async fn purge_stale_pages(&self) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        let (busy,): (i64,) = sqlx::query_as("PRAGMA wal_checkpoint(TRUNCATE)")
+            .fetch_one(&mut *conn)
+            .await?;
+        if busy != 0 {
+            anyhow::bail!("failed to checkpoint the database: it is in use");
+        }
+        Ok(())
+    }
+
+    async fn write_cert(&self, conn: &mut SqliteConnection, cert: &Cert) -> anyhow::Result<()> {
+        let key = cert
+            .pem_key
+            .as_ref()
+            .map(|key| encryption::seal_secret(self.master_key.as_ref(), key))
+            .transpose()?;
+        sqlx::query(
+            "INSERT INTO certs (id, kind, chain, key) VALUES (?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET kind = excluded.kind, chain = excluded.chain, key = excluded.key",
+        )
+        .bind(cert.id().to_string())
+        .bind(cert.kind.to_string())
+        .bind(&cert.pem_chain)
+        .bind(key)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn read_certs(&self, conn: &mut SqliteConnection) -> anyhow::Result<Vec<Arc<Cert>>> {
+        let rows = sqlx::query("SELECT id, kind, chain, key FROM certs ORDER BY rowid")
+            .fetch_all(conn)
+            .await?;
+        let mut certs = Vec::new();
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            let kind: CertKind =
+                serde_json::from_value(serde_json::Value::String(row.try_get("kind")?))?;
+            let key = match row.try_get::<Option<Vec<u8>>, _>("key")? {
+                Some(key) => match encryption::open_secret(self.master_key.as_ref(), key) {
+                    Ok(key) => Some(key),
+                    Err(err) => {
+                        error!(id, "failed to decrypt: {err}");
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            match Cert::new(kind, row.try_get("chain")?, key) {
+                Ok(cert) => certs.push(Arc::new(cert)),
+                Err(err) => error!(id, "failed to load: {err}"),
+            }
+        }
+        Ok(certs)
+    }
+
+    async fn write_entry<T: Serialize>(
+        &self,
+        conn: &mut SqliteConnection,
+        (table, fields): (&str, &[&str]),
+        id: ShortId,
+        entry: &T,
+    ) -> anyhow::Result<()> {
+        let mut value = serde_json::to_value(entry)?;
+        encryption::seal_fields(self.master_key.as_ref(), &mut value, fields)?;
+        sqlx::query(&format!(
+            "INSERT INTO {table} (id, data) VALUES (?, ?)
+            ON CONFLICT (id) DO UPDATE SET data = excluded.data"
+        ))
+        .bind(id.to_string())
+        .bind(serde_json::to_string(&value)?)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn read_entries<T: DeserializeOwned>(
+        &self,
+        (table, fields): (&str, &[&str]),
+    ) -> anyhow::Result<Vec<(ShortId, T)>> {
+        let rows = sqlx::query(&format!("SELECT id, data FROM {table} ORDER BY rowid"))
+            .fetch_all(&self.pool)
+            .await?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            let data: String = row.try_get("data")?;
+            let mut value = serde_json::from_str(&data)?;
+            encryption::open_fields(self.master_key.as_ref(), &mut value, fields)?;
+            entries.push((id.parse()?, serde_json::from_value(value)?));
+        }
+        Ok(entries)
+    }
+
+    async fn delete_entry(&self, table: &str, id: ShortId) -> anyhow::Result<()> {
+        sqlx::query(&format!("DELETE FROM {table} WHERE id = ?"))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn write_account(
+        &self,
+        conn: &mut SqliteConnection,
+        name: &str,
+        account: &Account,
+    ) -> anyhow::Result<()> {
+        let mut value = serde_json::to_value(account)?;
+        encryption::seal_fields(self.master_key.as_ref(), &mut value, ACCOUNT_SECRET_FIELDS)?;
+        sqlx::query(
+            "INSERT INTO accounts (name, data) VALUES (?, ?)
+            ON CONFLICT (name) DO UPDATE SET data = excluded.data",
+        )
+        .bind(name)
+        .bind(serde_json::to_string(&value)?)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn read_account(&self, name: &str) -> anyhow::Result<Option<Account>> {
+        let data: Option<String> = sqlx::query_scalar("SELECT data FROM accounts WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(data) = data else {
+            return Ok(None);
+        };
+        let mut value = serde_json::from_str(&data)?;
+        encryption::open_fields(self.master_key.as_ref(), &mut value, ACCOUNT_SECRET_FIELDS)?;
+        Ok(Some(serde_json::from_value(value)?))
+    }
+
+    async fn add_account_impl(
+        &self,
+        name: &str,
+        password: &str,
+        totp: bool,
+    ) -> anyhow::Result<Account> {
+        let account = new_account(password, totp)?;
+        let mut conn = self.pool.acquire().await?;
+        self.write_account(&mut conn, name, &account).await?;
+        Ok(account)
+    }
+}
+
+async fn migrate(pool: &SqlitePool) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+        .fetch_one(&mut *tx)
+        .await?;
+    let version = version as usize;
+    if version > MIGRATIONS.len() {
+        anyhow::bail!("the database schema version {version} is not supported");
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        info!(version = index + 1, "migrate database");
+        sqlx::query(migration).execute(&mut *tx).await?;
+    }
+    // The import of the config files runs in a later transaction,
+    // and is retried on the next start until it succeeds.
+    if version == 0 {
+        write_setting(&mut tx, IMPORT_PENDING, &true).await?;
+    }
+    sqlx::query(&format!("PRAGMA user_version = {}", MIGRATIONS.len()))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn read_setting<T: DeserializeOwned>(
+    conn: &mut SqliteConnection,
+    name: &str,
+) -> anyhow::Result<Option<T>> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM settings WHERE name = ?")
+        .bind(name)
+        .fetch_optional(conn)
+        .await?;
+    Ok(value
+        .map(|value| serde_json::from_str(&value))
+        .transpose()?)
+}
+
+async fn write_setting<T: Serialize>(
+    conn: &mut SqliteConnection,
+    name: &str,
+    value: &T,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO settings (name, value) VALUES (?, ?)
+        ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+    )
+    .bind(name)
+    .bind(serde_json::to_string(value)?)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn write_ports(conn: &mut SqliteConnection, entries: &[PortEntry]) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM ports").execute(&mut *conn).await?;
+    for (position, entry) in entries.iter().enumerate() {
+        let (id, port): (ShortId, Port) = entry.clone().into();
+        sqlx::query("INSERT INTO ports (id, position, data) VALUES (?, ?, ?)")
+            .bind(id.to_string())
+            .bind(position as i64)
+            .bind(serde_json::to_string(&port)?)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn write_proxies(conn: &mut SqliteConnection, entries: &[ProxyEntry]) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM proxies")
+        .execute(&mut *conn)
+        .await?;
+    for (position, entry) in entries.iter().enumerate() {
+        let (id, proxy): (ShortId, Proxy) = entry.clone().into();
+        sqlx::query("INSERT INTO proxies (id, position, data) VALUES (?, ?, ?)")
+            .bind(id.to_string())
+            .bind(position as i64)
+            .bind(serde_json::to_string(&proxy)?)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn read_positioned<T: DeserializeOwned>(
+    pool: &SqlitePool,
+    table: &str,
+) -> anyhow::Result<Vec<(ShortId, T)>> {
+    let rows = sqlx::query(&format!("SELECT id, data FROM {table} ORDER BY position"))
+        .fetch_all(pool)
+        .await?;
+    let mut entries = Vec::new();
+    for row in rows {
+        let id: String = row.try_get("id")?;
+        let data: String = row.try_get("data")?;
+        entries.push((id.parse()?, serde_json::from_str(&data)?));
+    }
+    Ok(entries)
+}
+
+#[async_trait::async_trait]
+impl Storage for SqliteStorage {
+    async fn save_app_config(&self, config: &AppConfig) {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            write_setting(&mut tx, "app_config", config).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "settings", "failed to save: {err}");
+        }
+    }
+
+    async fn load_app_config(&self) -> AppConfig {
+        let result = match self.pool.acquire().await {
+            Ok(mut conn) => read_setting(&mut conn, "app_config").await,
+            Err(err) => Err(err.into()),
+        };
+        match result {
+            Ok(config) => config.unwrap_or_default(),
+            Err(err) => {
+                warn!(table = "settings", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+
+    async fn save_ports(&self, entries: &[PortEntry]) {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            write_ports(&mut tx, entries).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "ports", "failed to save: {err}");
+        }
+    }
+
+    async fn load_ports(&self) -> Vec<PortEntry> {
+        match read_positioned::<Port>(&self.pool, "ports").await {
+            Ok(entries) => entries.into_iter().map(|entry| entry.into()).collect(),
+            Err(err) => {
+                warn!(table = "ports", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+
+    async fn save_proxies(&self, proxies: &[ProxyEntry]) {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            write_proxies(&mut tx, proxies).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "proxies", "failed to save: {err}");
+        }
+    }
+
+    async fn load_proxies(&self) -> Vec<ProxyEntry> {
+        match read_positioned::<Proxy>(&self.pool, "proxies").await {
+            Ok(entries) => entries.into_iter().map(|entry| entry.into()).collect(),
+            Err(err) => {
+                warn!(table = "proxies", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+
+    async fn save_cert(&self, cert: &Cert) {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            self.write_cert(&mut tx, cert).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "certs", "failed to save: {err}");
+        }
+    }
+
+    async fn save_acme(&self, acme: &AcmeEntry) {
+        let (id, record): (ShortId, AcmeRecord) = acme.clone().into();
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            self.write_entry(&mut tx, ACMES, id, &record).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "acmes", "failed to save: {err}");
+        }
+    }
+
+    async fn delete_acme(&self, id: ShortId) {
+        if let Err(err) = self.delete_entry("acmes", id).await {
+            error!(table = "acmes", "failed to delete: {err}");
+        }
+    }
+
+    async fn delete_cert(&self, id: ShortId) {
+        if let Err(err) = self.delete_entry("certs", id).await {
+            error!(table = "certs", "failed to delete: {err}");
+        }
+    }
+
+    async fn load_acmes(&self) -> Vec<AcmeEntry> {
+        let records = match self.read_entries::<AcmeRecord>(ACMES).await {
+            Ok(records) => records,
+            Err(err) => {
+                warn!(table = "acmes", "failed to load: {err}");
+                return Default::default();
+            }
+        };
+        let mut entries = Vec::new();
+        for (id, record) in records {
+            let Some(account_id) = record.account_id else {
+                continue;
+            };
+            let (acme, moved) = record.into_acme();
+            let entry = AcmeEntry {
+                id,
+                acme,
+                account_id,
+            };
+            if moved {
+                self.save_acme(&entry).await;
+            }
+            entries.push(entry);
+        }
+        entries
+    }
+
+    async fn save_acme_account(&self, account: &AcmeAccountEntry) {
+        let (id, record): (ShortId, AcmeAccountRecord) = account.clone().into();
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            self.write_entry(&mut tx, ACME_ACCOUNTS, id, &record)
+                .await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "acme_accounts", "failed to save: {err}");
+        }
+    }
+
+    async fn delete_acme_account(&self, id: ShortId) {
+        if let Err(err) = self.delete_entry("acme_accounts", id).await {
+            error!(table = "acme_accounts", "failed to delete: {err}");
+        }
+    }
+
+    async fn load_acme_accounts(&self) -> Vec<AcmeAccountEntry> {
+        match self.read_entries::<AcmeAccountRecord>(ACME_ACCOUNTS).await {
+            Ok(entries) => entries.into_iter().map(|entry| entry.into()).collect(),
+            Err(err) => {
+                warn!(table = "acme_accounts", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+
+    async fn save_ca(&self, ca: &CaEntry) {
+        let (id, record): (ShortId, CaRecord) = ca.clone().into();
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            self.write_entry(&mut tx, CAS, id, &record).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "cas", "failed to save: {err}");
+        }
+    }
+
+    async fn delete_ca(&self, id: ShortId) {
+        if let Err(err) = self.delete_entry("cas", id).await {
+            error!(table = "cas", "failed to delete: {err}");
+        }
+    }
+
+    async fn load_cas(&self) -> Vec<CaEntry> {
+        match self.read_entries::<CaRecord>(CAS).await {
+            Ok(entries) => entries.into_iter().map(|entry| entry.into()).collect(),
+            Err(err) => {
+                warn!(table = "cas", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+
+    async fn save_csr(&self, csr: &CsrEntry) {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            self.write_entry(&mut tx, CSRS, csr.id, csr).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "csrs", "failed to save: {err}");
+        }
+    }
+
+    async fn delete_csr(&self, id: ShortId) {
+        if let Err(err) = self.delete_entry("csrs", id).await {
+            error!(table = "csrs", "failed to delete: {err}");
+        }
+    }
+
+    async fn load_csrs(&self) -> Vec<CsrEntry> {
+        match self.read_entries::<CsrEntry>(CSRS).await {
+            Ok(entries) => entries
+                .into_iter()
+                .map(|(id, entry)| CsrEntry { id, ..entry })
+                .collect(),
+            Err(err) => {
+                warn!(table = "csrs", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+
+    async fn load_certs(&self) -> Vec<Arc<Cert>> {
+        let result = match self.pool.acquire().await {
+            Ok(mut conn) => self.read_certs(&mut conn).await,
+            Err(err) => Err(err.into()),
+        };
+        match result {
+            Ok(certs) => certs,
+            Err(err) => {
+                warn!(table = "certs", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+
+    async fn add_account(&self, name: &str, password: &str, totp: bool) -> Result<Account, Error> {
+        self.add_account_impl(name, password, totp)
+            .await
+            .map_err(|_| Error::FailedToCreateAccount)
+    }
+
+    async fn verify_account(&self, request: LoginRequest) -> Result<LoginResponse, Error> {
+        let account = match self.read_account(&request.username).await {
+            Ok(account) => account,
+            Err(err) => {
+                error!(?err, "failed to load accounts: {err}");
+                return Err(Error::InvalidLoginCredentials);
+            }
+        };
+        verify_login(&request.username, account.as_ref(), request.method)
+    }
+
+    async fn save_config_version(&self, version: &ConfigVersion) {
+        let result = async {
+            sqlx::query("INSERT OR REPLACE INTO history (version, data) VALUES (?, ?)")
+                .bind(version.version as i64)
+                .bind(serde_json::to_string(version)?)
+                .execute(&self.pool)
+                .await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!(table = "history", "failed to save: {err}");
+        }
+    }
+
+    async fn delete_config_version(&self, version: u64) {
+        let result = sqlx::query("DELETE FROM history WHERE version = ?")
+            .bind(version as i64)
+            .execute(&self.pool)
+            .await;
+        if let Err(err) = result {
+            error!(table = "history", "failed to delete: {err}");
+        }
+    }
+
+    async fn load_config_history(&self) -> Vec<ConfigVersion> {
+        let result = async {
+            let rows: Vec<String> = sqlx::query_scalar("SELECT data FROM history ORDER BY version")
+                .fetch_all(&self.pool)
+                .await?;
+            rows.iter()
+                .map(|data| Ok(serde_json::from_str(data)?))
+                .collect::<anyhow::Result<Vec<_>>>()
+        }
+        .await;
+        match result {
+            Ok(versions) => versions,
+            Err(err) => {
+                warn!(table = "history", "failed to load: {err}");
+                Default::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use taxy_api::auth::LoginMethod;
+
+    fn temp_dir() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("taxy-test-{}", rand::random::<u64>()))
+    }
+
+    fn port(id: &str, addr: &str) -> PortEntry {
+        PortEntry {
+            id: id.parse().unwrap(),
+            port: Port {
+                active: true,
+                name: id.into(),
+                listen: addr.parse().unwrap(),
+                opts: Default::default(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_storage() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.db");
+
+        let storage = SqliteStorage::new(&path).await.unwrap();
+        assert!(storage.is_new());
+        assert_eq!(storage.load_app_config().await, AppConfig::default());
+
+        // The import is retried until it has completed.
+        drop(storage);
+        let storage = SqliteStorage::new(&path).await.unwrap();
+        assert!(storage.is_new());
+        storage
+            .import(&FileStorage::new(&dir.join("files")))
+            .await
+            .unwrap();
+
+        let ports = vec![
+            port("zzz", "/ip4/127.0.0.1/tcp/8080"),
+            port("aaa", "/ip4/127.0.0.1/tcp/8081"),
+        ];
+        storage.save_ports(&ports).await;
+        let proxies = vec![ProxyEntry {
+            id: "proxy".parse().unwrap(),
+            proxy: Proxy {
+                name: "proxy".into(),
+                ports: vec!["zzz".parse().unwrap()],
+                ..Default::default()
+            },
+        }];
+        storage.save_proxies(&proxies).await;
+        let cert = Arc::new(Cert::new_ca(Default::default()).unwrap());
+        storage.save_cert(&cert).await;
+        storage
+            .add_account("admin", "password", false)
+            .await
+            .unwrap();
+
+        // The entries are kept across reopening, in the saved order.
+        drop(storage);
+        let storage = SqliteStorage::new(&path).await.unwrap();
+        assert!(!storage.is_new());
+        assert_eq!(storage.load_ports().await, ports);
+        assert_eq!(storage.load_proxies().await, proxies);
+        let certs = storage.load_certs().await;
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].id(), cert.id());
+        assert_eq!(certs[0].pem_key, cert.pem_key);
+
+        let login = |password: &str| LoginRequest {
+            username: "admin".into(),
+            method: LoginMethod::Password {
+                password: password.into(),
+            },
+        };
+        assert!(storage.verify_account(login("password")).await.is_ok());
+        assert!(storage.verify_account(login("wrong")).await.is_err());
+
+        storage.save_ports(&ports[1..]).await;
+        assert_eq!(storage.load_ports().await, &ports[1..]);
+        storage.delete_cert(cert.id()).await;
+        assert!(storage.load_certs().await.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_import_and_encryption() {
+        let dir = temp_dir();
+        let key = MasterKey::generate().unwrap();
+        let files = FileStorage::new(&dir).with_master_key(Some(key.clone()));
+        files.init_encryption().await.unwrap();
+        let ports = vec![port("https", "/ip4/127.0.0.1/tcp/8443")];
+        files.save_ports(&ports).await;
+        let cert = Arc::new(Cert::new_ca(Default::default()).unwrap());
+        files.save_cert(&cert).await;
+        let totp = files.add_account("admin", "password", true).await.unwrap();
+
+        let path = dir.join("config.db");
+        let storage = SqliteStorage::new(&path)
+            .await
+            .unwrap()
+            .with_master_key(Some(key.clone()));
+        storage.import(&files).await.unwrap();
+        storage.init_encryption().await.unwrap();
+        assert_eq!(storage.load_ports().await, ports);
+        assert_eq!(storage.load_certs().await[0].pem_key, cert.pem_key);
+        assert_eq!(
+            storage.read_account("admin").await.unwrap().unwrap().totp,
+            totp.totp
+        );
+
+        // The secrets are not stored in plaintext, neither in the database nor in the WAL.
+        let on_disk = |needle: &[u8]| {
+            ["config.db", "config.db-wal", "config.db-shm"]
+                .iter()
+                .filter_map(|name| std::fs::read(dir.join(name)).ok())
+                .any(|content| content.windows(needle.len()).any(|w| w == needle))
+        };
+        assert!(!on_disk(b"PRIVATE KEY"));
+        assert!(!on_disk(totp.totp.as_deref().unwrap().as_bytes()));
+
+        // A wrong or missing master key is rejected.
+        let reopen = || SqliteStorage::new(&path);
+        assert!(reopen().await.unwrap().init_encryption().await.is_err());
+        let wrong = reopen()
+            .await
+            .unwrap()
+            .with_master_key(Some(MasterKey::generate().unwrap()));
+        assert!(wrong.init_encryption().await.is_err());
+
+        // No data key wrapped with the old master key is left after a rotation.
+        let new_key = MasterKey::generate().unwrap();
+        assert_eq!(storage.rotate_master_key(Some(&new_key)).await.unwrap(), 2);
+        assert!(!on_disk(format!("enc:v1:{}:", key.id()).as_bytes()));
+        assert!(!on_disk(b"PRIVATE KEY"));
+        let storage = reopen().await.unwrap().with_master_key(Some(new_key));
+        storage.init_encryption().await.unwrap();
+
+        // Decryption restores the plaintext secrets.
+        assert_eq!(storage.rotate_master_key(None).await.unwrap(), 2);
+        let plain = reopen().await.unwrap();
+        plain.init_encryption().await.unwrap();
+        assert_eq!(plain.load_certs().await[0].pem_key, cert.pem_key);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,15 @@
-use crate::certs::{acme::AcmeEntry, Cert};
+use crate::certs::{
+    acme::{AcmeAccountEntry, AcmeEntry},
+    ca::CaEntry,
+    csr::CsrEntry,
+    Cert,
+};
 use std::sync::Arc;
 use taxy_api::{
     app::AppConfig,
     auth::{Account, LoginRequest, LoginResponse},
     error::Error,
+    history::ConfigVersion,
     id::ShortId,
     port::PortEntry,
     proxy::ProxyEntry,
@@ -22,7 +28,19 @@ pub trait Storage: Send + Sync + 'static {
     async fn delete_acme(&self, id: ShortId);
     async fn delete_cert(&self, id: ShortId);
     async fn load_acmes(&self) -> Vec<AcmeEntry>;
+    async fn save_acme_account(&self, account: &AcmeAccountEntry);
+    async fn delete_acme_account(&self, id: ShortId);
+    async fn load_acme_accounts(&self) -> Vec<AcmeAccountEntry>;
+    async fn save_ca(&self, ca: &CaEntry);
+    async fn delete_ca(&self, id: ShortId);
+    async fn load_cas(&self) -> Vec<CaEntry>;
+    async fn save_csr(&self, csr: &CsrEntry);
+    async fn delete_csr(&self, id: ShortId);
+    async fn load_csrs(&self) -> Vec<CsrEntry>;
     async fn load_certs(&self) -> Vec<Arc<Cert>>;
     async fn add_account(&self, name: &str, password: &str, totp: bool) -> Result<Account, Error>;
     async fn verify_account(&self, request: LoginRequest) -> Result<LoginResponse, Error>;
+    async fn save_config_version(&self, version: &ConfigVersion);
+    async fn delete_config_version(&self, version: u64);
+    async fn load_config_history(&self) -> Vec<ConfigVersion>;
 }
@@ -3,14 +3,21 @@
 use clap::Parser;
 use directories::ProjectDirs;
 use std::fs;
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use taxy::args::Command;
+use taxy::args::MasterKeyArgs;
 use taxy::args::StartArgs;
+use taxy::args::StorageKind;
+use taxy::config::encryption::MasterKey;
 use taxy::config::file::FileStorage;
+use taxy::config::managed::ManagedStorage;
 use taxy::config::new_appinfo;
+use taxy::config::sqlite::SqliteStorage;
 use taxy::config::storage::Storage;
 use taxy::log::DatabaseLayer;
 use taxy::server::Server;
+use taxy_api::app::AppInfo;
 use tracing::{error, info};
 use tracing_subscriber::filter::{self, FilterExt};
 use tracing_subscriber::prelude::*;
@@ -22,6 +29,7 @@ async fn main() -> anyhow::Result<()> {
     match args.command {
         Command::Start(args) => start(args).await?,
         Command::AddUser(args) => add_user(args).await?,
+        Command::RotateMasterKey(args) => rotate_master_key(args).await?,
     }
 
     Ok(())
@@ -59,16 +67,47 @@ async fn start(args: StartArgs) -> anyhow::Result<()> {
     let config_dir = get_config_dir(args.config_dir)?;
     fs::create_dir_all(&config_dir)?;
 
-    let config = FileStorage::new(&config_dir);
     let app_info = new_appinfo(&config_dir, &log_dir);
+    let webui = (args.webui, !args.no_webui);
+    match args.storage {
+        StorageKind::File => {
+            let config = open_file_storage(&config_dir, &args.master_key).await?;
+            serve_with_config_file(app_info, webui, config, args.config).await
+        }
+        StorageKind::Sqlite => {
+            let config = open_sqlite_storage(&config_dir, &args.master_key).await?;
+            serve_with_config_file(app_info, webui, config, args.config).await
+        }
+    }
+}
 
+/// Serves ports, proxies and ACME entries from `config_file` instead of the storage, if given.
+async fn serve_with_config_file<S: Storage>(
+    mut app_info: AppInfo,
+    webui: (SocketAddr, bool),
+    config: S,
+    config_file: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let Some(path) = config_file else {
+        return serve(app_info, webui, config).await;
+    };
+    let config = ManagedStorage::new(config, &path).await?;
+    info!(?path, "loaded config file");
+    app_info.config_file = Some(path);
+    serve(app_info, webui, config).await
+}
+
+async fn serve<S: Storage>(
+    app_info: AppInfo,
+    (webui, webui_enabled): (SocketAddr, bool),
+    config: S,
+) -> anyhow::Result<()> {
     let (server, channels) = Server::new(app_info.clone(), config).await;
     let server_task = tokio::spawn(server.start());
     let event_send = channels.event.clone();
 
-    let webui_enabled = !args.no_webui;
     tokio::select! {
-        r = taxy::admin::start_admin(app_info, args.webui, channels.command, channels.callback, channels.event), if webui_enabled => {
+        r = taxy::admin::start_admin(app_info, webui, channels.command, channels.callback, channels.event), if webui_enabled => {
             if let Err(err) = r {
                 error!("admin error: {}", err);
             }
@@ -85,9 +124,21 @@ async fn start(args: StartArgs) -> anyhow::Result<()> {
 }
 
 async fn add_user(args: taxy::args::AddUserArgs) -> anyhow::Result<()> {
-    let config_dir = get_config_dir(args.config_dir)?;
-    let config = FileStorage::new(&config_dir);
-    let password = if let Some(password) = args.password {
+    let config_dir = get_config_dir(args.config_dir.clone())?;
+    match args.storage {
+        StorageKind::File => {
+            let config = open_file_storage(&config_dir, &args.master_key).await?;
+            add_account(&args, config).await
+        }
+        StorageKind::Sqlite => {
+            let config = open_sqlite_storage(&config_dir, &args.master_key).await?;
+            add_account(&args, config).await
+        }
+    }
+}
+
+async fn add_account<S: Storage>(args: &taxy::args::AddUserArgs, config: S) -> anyhow::Result<()> {
+    let password = if let Some(password) = args.password.clone() {
         password
     } else {
         rpassword::prompt_password("password?: ")?
@@ -99,6 +150,97 @@ async fn add_user(args: taxy::args::AddUserArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn rotate_master_key(args: taxy::args::RotateMasterKeyArgs) -> anyhow::Result<()> {
+    let config_dir = get_config_dir(args.config_dir)?;
+    let new_key = if let Some(path) = args.new_master_key_file {
+        Some(MasterKey::from_file(&path)?)
+    } else if args.new_master_key_passphrase {
+        let passphrase = rpassword::prompt_password("new master key passphrase?: ")?;
+        if passphrase != rpassword::prompt_password("retype new master key passphrase?: ")? {
+            anyhow::bail!("passphrases do not match");
+        }
+        Some(MasterKey::from_passphrase(&passphrase, None)?)
+    } else {
+        None
+    };
+
+    let count = match args.storage {
+        StorageKind::File => {
+            let config = FileStorage::new(&config_dir);
+            let salt = config.load_encryption_config().await?.and_then(|c| c.salt);
+            let master_key = load_master_key(&args.master_key, salt).await?;
+            let config = config.with_master_key(master_key);
+            config.rotate_master_key(new_key.as_ref()).await?
+        }
+        StorageKind::Sqlite => {
+            let config = SqliteStorage::new(&config_dir.join(SQLITE_FILE)).await?;
+            let salt = config.load_encryption_config().await?.and_then(|c| c.salt);
+            let master_key = load_master_key(&args.master_key, salt).await?;
+            let config = config.with_master_key(master_key);
+            config.rotate_master_key(new_key.as_ref()).await?
+        }
+    };
+    match new_key {
+        Some(key) => println!(
+            "Re-encrypted {count} secrets with the master key {}",
+            key.id()
+        ),
+        None => println!("Decrypted {count} secrets"),
+    }
+    Ok(())
+}
+
+async fn open_file_storage(config_dir: &Path, args: &MasterKeyArgs) -> anyhow::Result<FileStorage> {
+    let config = FileStorage::new(config_dir);
+    let salt = config.load_encryption_config().await?.and_then(|c| c.salt);
+    let master_key = load_master_key(args, salt).await?;
+    let config = config.with_master_key(master_key);
+    config.init_encryption().await?;
+    config.check().await?;
+    Ok(config)
+}
+
+/// Opens the SQLite database, importing the config files into it when it is first created.
+async fn open_sqlite_storage(
+    config_dir: &Path,
+    args: &MasterKeyArgs,
+) -> anyhow::Result<SqliteStorage> {
+    let config = SqliteStorage::new(&config_dir.join(SQLITE_FILE)).await?;
+    if config.is_new() {
+        let files = open_file_storage(config_dir, args).await?;
+        let config = config.with_master_key(files.master_key().cloned());
+        config.import(&files).await?;
+        config.init_encryption().await?;
+        return Ok(config);
+    }
+    let salt = config.load_encryption_config().await?.and_then(|c| c.salt);
+    let master_key = load_master_key(args, salt).await?;
+    let config = config.with_master_key(master_key);
+    config.init_encryption().await?;
+    Ok(config)
+}
+
+async fn load_master_key(
+    args: &MasterKeyArgs,
+    salt: Option<String>,
+) -> anyhow::Result<Option<MasterKey>> {
+    if let Some(key) = &args.master_key {
+        Ok(Some(MasterKey::from_base64(key)?))
+    } else if let Some(path) = &args.master_key_file {
+        Ok(Some(MasterKey::from_file(path)?))
+    } else if args.master_key_passphrase {
+        let passphrase = rpassword::prompt_password("master key passphrase?: ")?;
+        Ok(Some(MasterKey::from_passphrase(
+            &passphrase,
+            salt.as_deref(),
+        )?))
+    } else {
+        Ok(None)
+    }
+}
+
+const SQLITE_FILE: &str = "config.db";
+
 fn get_config_dir(dir: Option<PathBuf>) -> anyhow::Result<PathBuf> {
     if let Some(dir) = dir {
         Ok(dir)
@@ -16,7 +16,7 @@ pub enum ProxyError {
 }
 
 impl ProxyError {
-    fn code(&self) -> StatusCode {
+    pub fn code(&self) -> StatusCode {
         match self {
             Self::DomainFrontingDetected => StatusCode::MISDIRECTED_REQUEST,
             Self::NoRouteFound => StatusCode::BAD_GATEWAY,
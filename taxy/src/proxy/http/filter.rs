@@ -23,12 +23,7 @@ impl RequestFilter {
     }
 
     pub fn test<T>(&self, req: &Request<T>) -> Option<FilterResult> {
-        let host = req.headers().get("host").and_then(|v| v.to_str().ok());
-        let host_matched = match host {
-            Some(host) => self.vhosts.iter().any(|vhost| vhost.test(host)),
-            None => false,
-        };
-        if !host_matched && !self.vhosts.is_empty() {
+        if !self.test_host(req) {
             return None;
         }
         let path = req.uri().path().split('/').filter(|seg| !seg.is_empty());
@@ -44,6 +39,15 @@ impl RequestFilter {
             None
         }
     }
+
+    pub fn test_host<T>(&self, req: &Request<T>) -> bool {
+        let host = req.headers().get("host").and_then(|v| v.to_str().ok());
+        let host_matched = match host {
+            Some(host) => self.vhosts.iter().any(|vhost| vhost.test(host)),
+            None => false,
+        };
+        host_matched || self.vhosts.is_empty()
+    }
 }
 
 #[derive(Debug)]
@@ -1,9 +1,12 @@
 use self::{
     error::{map_response, ProxyError},
     pool::ConnectionPool,
-    route::Router,
+    route::{ParsedRoute, Router},
+};
+use super::{
+    tls::{TlsPortAcceptor, TlsTermination, ACME_TLS_ALPN_NAME},
+    PortContextEvent,
 };
-use super::{tls::TlsTermination, PortContextEvent};
 use crate::server::cert_list::CertList;
 use arc_swap::{ArcSwap, Cache};
 use header::HeaderRewriter;
@@ -15,10 +18,15 @@ use hyper::{
     },
     server::conn::Http,
     service::service_fn,
-    Response, StatusCode, Uri,
+    Request, Response, StatusCode, Uri,
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::SystemTime,
 };
-use std::{net::SocketAddr, sync::Arc, time::SystemTime};
 use taxy_api::error::Error;
+use taxy_api::id::ShortId;
 use taxy_api::port::{PortStatus, SocketState};
 use taxy_api::{port::PortEntry, proxy::ProxyEntry};
 use tokio::{
@@ -29,19 +37,17 @@ use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
 };
-use tokio_rustls::{
-    rustls::{client::ServerName, ClientConfig, RootCertStore},
-    TlsAcceptor,
-};
+use tokio_rustls::rustls::{client::ServerName, ClientConfig, RootCertStore};
 use tracing::{debug, error, info, span, Instrument, Level, Span};
 
 mod compression;
 mod error;
 mod filter;
 mod header;
-mod hyper_tls;
+pub(crate) mod hyper_tls;
 mod pool;
-mod route;
+pub(crate) mod route;
+mod route_test;
 mod upgrade;
 
 const MAX_BUFFER_SIZE: usize = 4096;
@@ -183,7 +189,7 @@ impl HttpPortContext {
 async fn start(
     mut stream: BufStream<TcpStream>,
     tls_client_config: Arc<ClientConfig>,
-    tls_acceptor: Option<TlsAcceptor>,
+    tls_acceptor: Option<TlsPortAcceptor>,
     shared_cache: Cache<Arc<ArcSwap<SharedContext>>, Arc<SharedContext>>,
     stop_notifier: Arc<Notify>,
     span: Span,
@@ -234,6 +240,10 @@ async fn start(
         debug!(%remote, "server: tls handshake");
         let accepted = acceptor.accept(stream).await?;
         let tls_conn = &accepted.get_ref().1;
+        if tls_conn.alpn_protocol() == Some(ACME_TLS_ALPN_NAME) {
+            debug!(%remote, "acme tls-alpn-01 challenge completed");
+            return Ok(());
+        }
         server_http2 = tls_conn.alpn_protocol() == Some(b"h2");
         sni = tls_conn.server_name().map(|sni| sni.to_string());
         stream = Box::new(accepted);
@@ -247,59 +257,16 @@ async fn start(
         let enter = span.clone();
         let _enter = enter.enter();
 
-        let header_host = req
-            .headers()
-            .get(HOST)
-            .and_then(|h| h.to_str().ok().and_then(|host| host.split(':').next()));
-
-        let domain_fronting = match (&sni, header_host) {
-            (Some(sni), Some(header)) => !sni.eq_ignore_ascii_case(header),
-            _ => false,
-        };
-
         let action = format!("{} {}", req.method().as_str(), req.uri());
         let pool = pool.clone();
         let shared = shared_cache.load();
 
-        let req = if domain_fronting {
-            Err(ProxyError::DomainFrontingDetected)
-        } else if let Some((route, res, _)) = shared.router.get_route(&req) {
-            let mut parts = Parts::default();
-
-            parts.path_and_query = if let Some(query) = req.uri().query() {
-                format!("{}?{}", res.uri.path(), query).parse().ok()
-            } else {
-                res.uri.path_and_query().cloned()
-            };
-
-            if !route.servers.is_empty() {
-                let server = &route.servers[0];
-
-                parts.scheme = Some(if server.url.scheme() == "http" {
-                    Scheme::HTTP
-                } else {
-                    Scheme::HTTPS
-                });
-
-                let authority = server.authority.clone();
-                req.headers_mut()
-                    .insert(HOST, HeaderValue::from_str(authority.as_str()).unwrap());
-                parts.authority = Some(authority);
+        let req = match rewrite_request(shared, &mut req, sni.as_deref(), remote.ip()) {
+            Ok(_) => {
+                info!(target: "taxy::access_log", remote = %remote, %local, action, target = %req.uri());
+                Ok(req)
             }
-
-            if let Ok(uri) = Uri::from_parts(parts) {
-                *req.uri_mut() = uri;
-            }
-
-            info!(target: "taxy::access_log", remote = %remote, %local, action, target = %req.uri());
-
-            shared
-                .header_rewriter
-                .pre_process(req.headers_mut(), remote.ip());
-            shared.header_rewriter.post_process(req.headers_mut());
-            Ok(req)
-        } else {
-            Err(ProxyError::NoRouteFound)
+            Err(err) => Err(err),
         };
 
         async move {
@@ -327,6 +294,64 @@ async fn start(
     Ok(())
 }
 
+/// Finds the route of a request and rewrites the request to be sent to its upstream server.
+fn rewrite_request<'a, T>(
+    shared: &'a SharedContext,
+    req: &mut Request<T>,
+    sni: Option<&str>,
+    remote: IpAddr,
+) -> Result<(&'a ParsedRoute, ShortId), ProxyError> {
+    let header_host = req
+        .headers()
+        .get(HOST)
+        .and_then(|h| h.to_str().ok().and_then(|host| host.split(':').next()));
+
+    let domain_fronting = match (sni, header_host) {
+        (Some(sni), Some(header)) => !sni.eq_ignore_ascii_case(header),
+        _ => false,
+    };
+    if domain_fronting {
+        return Err(ProxyError::DomainFrontingDetected);
+    }
+
+    let Some((route, res, id)) = shared.router.get_route(req) else {
+        return Err(ProxyError::NoRouteFound);
+    };
+
+    let mut parts = Parts::default();
+
+    parts.path_and_query = if let Some(query) = req.uri().query() {
+        format!("{}?{}", res.uri.path(), query).parse().ok()
+    } else {
+        res.uri.path_and_query().cloned()
+    };
+
+    if !route.servers.is_empty() {
+        let server = &route.servers[0];
+
+        parts.scheme = Some(if server.url.scheme() == "http" {
+            Scheme::HTTP
+        } else {
+            Scheme::HTTPS
+        });
+
+        let authority = server.authority.clone();
+        req.headers_mut()
+            .insert(HOST, HeaderValue::from_str(authority.as_str()).unwrap());
+        parts.authority = Some(authority);
+    }
+
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *req.uri_mut() = uri;
+    }
+
+    shared
+        .header_rewriter
+        .pre_process(req.headers_mut(), remote);
+    shared.header_rewriter.post_process(req.headers_mut());
+    Ok((route, id))
+}
+
 #[derive(Debug)]
 struct SharedContext {
     pub router: Router,
@@ -3,7 +3,8 @@ use hyper::Request;
 use taxy_api::{
     error::Error,
     id::ShortId,
-    proxy::{ProxyEntry, ProxyKind, Route, Server},
+    proxy::{ProxyEntry, ProxyKind, Route, RouteConflict, RouteConflictKind, Server},
+    subject_name::SubjectName,
 };
 use tokio_rustls::rustls::ServerName;
 use url::Url;
@@ -41,6 +42,105 @@ impl Router {
                 .map(|res| (&route.route, res, route.resource_id))
         })
     }
+
+    /// Explains why `get_route` finds no route for a request.
+    pub fn find_mismatch<T>(&self, req: &Request<T>) -> RouteMismatch {
+        if self.routes.is_empty() {
+            RouteMismatch::NoRoutes
+        } else if self.routes.iter().any(|route| route.filter.test_host(req)) {
+            RouteMismatch::Path
+        } else {
+            RouteMismatch::Host
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteMismatch {
+    NoRoutes,
+    Host,
+    Path,
+}
+
+/// Finds routes that never or only partly match because an earlier route takes precedence.
+///
+/// `entries` are the proxies bound to `port`, in the order passed to `Router::new`.
+/// Returns the ID of the proxy each conflicting route belongs to.
+pub fn find_conflicts(port: ShortId, entries: &[ProxyEntry]) -> Vec<(ShortId, RouteConflict)> {
+    let routes = entries
+        .iter()
+        .filter_map(|entry| match &entry.proxy.kind {
+            ProxyKind::Http(http) => Some((entry.id, http)),
+            _ => None,
+        })
+        .flat_map(|(id, http)| {
+            http.routes
+                .iter()
+                .map(move |route| (id, route, RequestFilter::new(&http.vhosts, route)))
+        })
+        .collect::<Vec<_>>();
+
+    let mut conflicts = Vec::new();
+    for (index, (id, route, filter)) in routes.iter().enumerate() {
+        for (by_id, by_route, by_filter) in &routes[..index] {
+            if !filter.path.starts_with(&by_filter.path) {
+                continue;
+            }
+            let (kind, vhosts) = if covers(&by_filter.vhosts, &filter.vhosts) {
+                if filter.path == by_filter.path && covers(&filter.vhosts, &by_filter.vhosts) {
+                    (RouteConflictKind::Duplicate, Vec::new())
+                } else {
+                    (RouteConflictKind::Shadowed, Vec::new())
+                }
+            } else {
+                let vhosts = filter
+                    .vhosts
+                    .iter()
+                    .filter(|vhost| covers(&by_filter.vhosts, std::slice::from_ref(vhost)))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if vhosts.is_empty() {
+                    continue;
+                }
+                (RouteConflictKind::Overlapping, vhosts)
+            };
+            conflicts.push((
+                *id,
+                RouteConflict {
+                    kind,
+                    port,
+                    path: route.path.clone(),
+                    proxy: *by_id,
+                    by_path: by_route.path.clone(),
+                    vhosts,
+                },
+            ));
+            if kind != RouteConflictKind::Overlapping {
+                break;
+            }
+        }
+    }
+    conflicts
+}
+
+/// Returns true if every request for `vhosts` is also accepted by `by`.
+/// An empty list accepts any host.
+fn covers(by: &[SubjectName], vhosts: &[SubjectName]) -> bool {
+    by.is_empty()
+        || (!vhosts.is_empty()
+            && vhosts
+                .iter()
+                .all(|vhost| by.iter().any(|by| vhost_covers(by, vhost))))
+}
+
+fn vhost_covers(by: &SubjectName, vhost: &SubjectName) -> bool {
+    match (by, vhost) {
+        (SubjectName::WildcardDnsName(by), SubjectName::WildcardDnsName(vhost)) => {
+            by.eq_ignore_ascii_case(vhost)
+        }
+        (_, SubjectName::WildcardDnsName(_)) => false,
+        _ => by.test(&vhost.to_string()),
+    }
 }
 
 #[derive(Debug)]
@@ -108,3 +208,80 @@ impl TryFrom<Server> for ParsedServer {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use taxy_api::proxy::{HttpProxy, Proxy};
+
+    fn proxy(id: &str, vhosts: &[&str], paths: &[&str]) -> ProxyEntry {
+        ProxyEntry {
+            id: id.parse().unwrap(),
+            proxy: Proxy {
+                kind: ProxyKind::Http(HttpProxy {
+                    vhosts: vhosts.iter().map(|vhost| vhost.parse().unwrap()).collect(),
+                    routes: paths
+                        .iter()
+                        .map(|path| Route {
+                            path: path.to_string(),
+                            servers: vec![],
+                        })
+                        .collect(),
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn kinds(entries: &[ProxyEntry]) -> Vec<(String, String, RouteConflictKind)> {
+        find_conflicts("port".parse().unwrap(), entries)
+            .into_iter()
+            .map(|(id, conflict)| (id.to_string(), conflict.path, conflict.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        // Distinct vhosts and more specific paths first do not conflict.
+        assert!(kinds(&[
+            proxy("a", &["example.com"], &["/api", "/"]),
+            proxy("b", &["example.org"], &["/"]),
+            proxy("c", &["a.example.com"], &["/"]),
+            proxy("d", &["*.example.com"], &["/"]),
+            proxy("e", &[], &["/"]),
+        ])
+        .is_empty());
+
+        assert_eq!(
+            kinds(&[
+                proxy("a", &["example.com"], &["/", "/api"]),
+                proxy("b", &["EXAMPLE.com"], &["/"]),
+                proxy("c", &["*.example.com"], &["/"]),
+                proxy("d", &["a.example.com"], &["/api"]),
+                proxy("e", &["b.example.com", "example.net"], &["/"]),
+                proxy("f", &[], &["/"]),
+                proxy("g", &["example.org"], &["/"]),
+            ]),
+            vec![
+                ("a".into(), "/api".into(), RouteConflictKind::Shadowed),
+                ("b".into(), "/".into(), RouteConflictKind::Duplicate),
+                ("d".into(), "/api".into(), RouteConflictKind::Shadowed),
+                ("e".into(), "/".into(), RouteConflictKind::Overlapping),
+                ("g".into(), "/".into(), RouteConflictKind::Shadowed),
+            ]
+        );
+
+        let conflicts = find_conflicts(
+            "port".parse().unwrap(),
+            &[
+                proxy("a", &["*.example.com"], &["/"]),
+                proxy("b", &["b.example.com", "example.net"], &["/"]),
+            ],
+        );
+        assert_eq!(conflicts[0].1.proxy, "a".parse().unwrap());
+        assert_eq!(
+            conflicts[0].1.vhosts,
+            vec!["b.example.com".parse().unwrap()]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use super::{error::ProxyError, rewrite_request, route::RouteMismatch, HttpPortContext};
+use hyper::{
+    header::HOST,
+    http::{uri::Scheme, HeaderName, HeaderValue},
+    HeaderMap, Method, Request, StatusCode, Uri,
+};
+use std::{collections::BTreeSet, net::Ipv4Addr};
+use taxy_api::error::Error;
+use taxy_api::route_test::{
+    HeaderChange, RouteTestRejection, RouteTestRejectionReason, RouteTestRequest, RouteTestResult,
+};
+
+impl HttpPortContext {
+    /// Runs a request through the live router and header rewriter without sending it.
+    pub fn test_route(&self, request: RouteTestRequest) -> Result<RouteTestResult, Error> {
+        let invalid = |reason: String| Error::InvalidTestRequest { reason };
+
+        let method = Method::from_bytes(request.method.as_bytes())
+            .map_err(|_| invalid(format!("invalid method: {}", request.method)))?;
+        let url = request
+            .url
+            .parse::<Uri>()
+            .map_err(|err| invalid(format!("invalid url: {err}")))?;
+
+        let mut headers = HeaderMap::new();
+        for header in &request.headers {
+            let name = HeaderName::from_bytes(header.name.as_bytes())
+                .map_err(|_| invalid(format!("invalid header name: {}", header.name)))?;
+            let value = HeaderValue::from_str(&header.value)
+                .map_err(|_| invalid(format!("invalid header value: {}", header.value)))?;
+            headers.append(name, value);
+        }
+        if let Some(host) = url.host().filter(|_| !headers.contains_key(HOST)) {
+            // Clients never send the userinfo of the URL in the Host header.
+            let host = match url.port_u16() {
+                Some(port) => format!("{host}:{port}"),
+                None => host.to_string(),
+            };
+            let value = HeaderValue::from_str(&host)
+                .map_err(|_| invalid(format!("invalid host: {host}")))?;
+            headers.insert(HOST, value);
+        }
+
+        let mut req = Request::builder()
+            .method(method)
+            .uri(url.path_and_query().map_or("/", |path| path.as_str()))
+            .body(())
+            .map_err(|err| invalid(err.to_string()))?;
+        *req.headers_mut() = headers.clone();
+
+        let tls = self
+            .tls_termination
+            .as_ref()
+            .and_then(|tls| tls.acceptor.as_ref())
+            .is_some();
+        if tls && self.listen.port() != 80 && url.scheme() == Some(&Scheme::HTTP) {
+            return Ok(rejection(
+                RouteTestRejectionReason::HttpsRedirect,
+                StatusCode::PERMANENT_REDIRECT,
+                "plain HTTP requests to this port are redirected to HTTPS".into(),
+            ));
+        }
+
+        let sni = if tls {
+            request
+                .sni
+                .or_else(|| url.host().map(|host| host.to_string()))
+        } else {
+            None
+        };
+        let remote = request
+            .client_ip
+            .unwrap_or_else(|| Ipv4Addr::LOCALHOST.into());
+
+        let shared = self.shared.load();
+        let err = match rewrite_request(&shared, &mut req, sni.as_deref(), remote) {
+            Ok((route, id)) => {
+                return Ok(RouteTestResult {
+                    proxy: Some(id),
+                    route: Some(route.path.clone()),
+                    upstream: (!route.servers.is_empty()).then(|| req.uri().to_string()),
+                    header_changes: diff_headers(&headers, req.headers()),
+                    rejection: None,
+                })
+            }
+            Err(err) => err,
+        };
+
+        let host = req
+            .headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .unwrap_or_default();
+        let (reason, message) = match err {
+            ProxyError::DomainFrontingDetected => (
+                RouteTestRejectionReason::DomainFronting,
+                format!(
+                    "host {host} does not match the TLS server name {}",
+                    sni.unwrap_or_default()
+                ),
+            ),
+            _ => match shared.router.find_mismatch(&req) {
+                RouteMismatch::NoRoutes => (
+                    RouteTestRejectionReason::NoProxies,
+                    "no active HTTP proxy is bound to this port".into(),
+                ),
+                RouteMismatch::Host if host.is_empty() => (
+                    RouteTestRejectionReason::HostNotMatched,
+                    "the request has no Host header and every proxy on this port requires one"
+                        .into(),
+                ),
+                RouteMismatch::Host => (
+                    RouteTestRejectionReason::HostNotMatched,
+                    format!("no proxy on this port accepts host {host}"),
+                ),
+                RouteMismatch::Path => (
+                    RouteTestRejectionReason::PathNotMatched,
+                    format!("no route for host {host} matches path {}", req.uri().path()),
+                ),
+            },
+        };
+        Ok(rejection(reason, err.code(), message))
+    }
+}
+
+fn rejection(
+    reason: RouteTestRejectionReason,
+    status: StatusCode,
+    message: String,
+) -> RouteTestResult {
+    RouteTestResult {
+        rejection: Some(RouteTestRejection {
+            reason,
+            status: status.as_u16(),
+            message,
+        }),
+        ..Default::default()
+    }
+}
+
+fn diff_headers(before: &HeaderMap, after: &HeaderMap) -> Vec<HeaderChange> {
+    let value = |headers: &HeaderMap, name: &str| {
+        let values = headers
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .collect::<Vec<_>>();
+        (!values.is_empty()).then(|| values.join(", "))
+    };
+    before
+        .keys()
+        .chain(after.keys())
+        .map(|name| name.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|name| {
+            let change = HeaderChange {
+                name: name.to_string(),
+                before: value(before, name),
+                after: value(after, name),
+            };
+            (change.before != change.after).then_some(change)
+        })
+        .collect()
+}
@@ -1,6 +1,7 @@
 use self::{http::HttpPortContext, tcp::TcpPortContext};
 use crate::server::cert_list::CertList;
 use once_cell::sync::OnceCell;
+use std::net::SocketAddr;
 use taxy_api::error::Error;
 use taxy_api::multiaddr::Multiaddr;
 use taxy_api::port::{PortStatus, SocketState};
@@ -34,7 +35,7 @@ impl PortContext {
         Ok(Self { entry, kind })
     }
 
-    pub fn reserved() -> Self {
+    pub fn reserved(listen: SocketAddr) -> Self {
         Self {
             entry: PortEntry {
                 id: "reserved".parse().unwrap(),
@@ -45,7 +46,7 @@ impl PortContext {
                     opts: Default::default(),
                 },
             },
-            kind: PortContextKind::Reserved,
+            kind: PortContextKind::Reserved(listen),
         }
     }
 
@@ -65,7 +66,7 @@ impl PortContext {
         match &mut self.kind {
             PortContextKind::Tcp(ctx) => ctx.setup(certs, proxies).await,
             PortContextKind::Http(ctx) => ctx.setup(certs, proxies).await,
-            PortContextKind::Reserved => Ok(()),
+            PortContextKind::Reserved(_) => Ok(()),
         }
     }
 
@@ -82,7 +83,7 @@ impl PortContext {
         match &mut self.kind {
             PortContextKind::Tcp(ctx) => ctx.event(event),
             PortContextKind::Http(ctx) => ctx.event(event),
-            PortContextKind::Reserved => (),
+            PortContextKind::Reserved(_) => (),
         }
     }
 
@@ -90,7 +91,7 @@ impl PortContext {
         match &self.kind {
             PortContextKind::Tcp(ctx) => ctx.status(),
             PortContextKind::Http(ctx) => ctx.status(),
-            PortContextKind::Reserved => {
+            PortContextKind::Reserved(_) => {
                 static STATUS: OnceCell<PortStatus> = OnceCell::new();
                 STATUS.get_or_init(PortStatus::default)
             }
@@ -101,7 +102,7 @@ impl PortContext {
         match &mut self.kind {
             PortContextKind::Tcp(ctx) => ctx.reset(),
             PortContextKind::Http(ctx) => ctx.reset(),
-            PortContextKind::Reserved => (),
+            PortContextKind::Reserved(_) => (),
         }
     }
 }
@@ -110,5 +111,5 @@ impl PortContext {
 pub enum PortContextKind {
     Tcp(TcpPortContext),
     Http(HttpPortContext),
-    Reserved,
+    Reserved(SocketAddr),
 }
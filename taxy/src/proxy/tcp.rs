@@ -1,4 +1,7 @@
-use super::{tls::TlsTermination, PortContextEvent, PortStatus, SocketState};
+use super::{
+    tls::{TlsPortAcceptor, TlsTermination, ACME_TLS_ALPN_NAME},
+    PortContextEvent, PortStatus, SocketState,
+};
 use crate::server::cert_list::CertList;
 use std::{net::SocketAddr, sync::Arc, time::SystemTime};
 use taxy_api::{error::Error, multiaddr::Multiaddr, proxy::ProxyKind};
@@ -13,7 +16,7 @@ use tokio::{
 };
 use tokio_rustls::{
     rustls::{client::ServerName, ClientConfig, RootCertStore},
-    TlsAcceptor, TlsConnector,
+    TlsConnector,
 };
 use tracing::{debug, error, info, span, Instrument, Level, Span};
 
@@ -152,7 +155,7 @@ pub async fn start(
     mut stream: BufStream<TcpStream>,
     conn: Connection,
     tls_client_config: Option<Arc<ClientConfig>>,
-    tls_acceptor: Option<TlsAcceptor>,
+    tls_acceptor: Option<TlsPortAcceptor>,
     stop_notifier: Arc<Notify>,
 ) -> anyhow::Result<()> {
     let remote = stream.get_ref().peer_addr()?;
@@ -172,6 +175,17 @@ pub async fn start(
         }
     });
 
+    let mut stream: Box<dyn IoStream> = Box::new(server_stream);
+    if let Some(acceptor) = tls_acceptor {
+        debug!(%remote, "server: tls handshake");
+        let accepted = acceptor.accept(stream).await?;
+        if accepted.get_ref().1.alpn_protocol() == Some(ACME_TLS_ALPN_NAME) {
+            debug!(%remote, "acme tls-alpn-01 challenge completed");
+            return Ok(());
+        }
+        stream = Box::new(accepted);
+    }
+
     let name = match &conn.name {
         ServerName::DnsName(name) => name.as_ref().to_string(),
         ServerName::IpAddress(addr) => addr.to_string(),
@@ -193,12 +207,6 @@ pub async fn start(
     let out = sock.connect(resolved).await?;
     debug!(%resolved, "connected");
 
-    let mut stream: Box<dyn IoStream> = Box::new(server_stream);
-    if let Some(acceptor) = tls_acceptor {
-        debug!(%remote, "server: tls handshake");
-        stream = Box::new(acceptor.accept(stream).await?);
-    }
-
     let mut out: Box<dyn IoStream> = Box::new(out);
     if let Some(config) = tls_client_config {
         debug!(%resolved, "client: tls handshake");
@@ -217,7 +225,7 @@ pub async fn start(
     Ok(())
 }
 
-fn multiaddr_to_host(addr: &Multiaddr) -> Result<Connection, Error> {
+pub(crate) fn multiaddr_to_host(addr: &Multiaddr) -> Result<Connection, Error> {
     let tls = addr.is_tls();
     match (addr.ip_addr(), addr.host(), addr.port()) {
         (Ok(addr), _, Ok(port)) => Ok(Connection {
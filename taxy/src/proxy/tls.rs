@@ -1,7 +1,9 @@
 use crate::certs::Cert;
+use crate::command::ServerCommand;
 use crate::server::cert_list::CertList;
 use dashmap::DashMap;
 use std::fmt;
+use std::io;
 use std::str::FromStr;
 use std::sync::Arc;
 use taxy_api::cert::CertKind;
@@ -9,15 +11,20 @@ use taxy_api::error::Error;
 use taxy_api::id::ShortId;
 use taxy_api::subject_name::SubjectName;
 use taxy_api::tls::TlsState;
-use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::server::{Acceptor, ClientHello, ResolvesServerCert};
 use tokio_rustls::rustls::sign::CertifiedKey;
 use tokio_rustls::rustls::ServerConfig;
-use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::{LazyConfigAcceptor, TlsAcceptor};
 use tracing::error;
 
+pub const ACME_TLS_ALPN_NAME: &[u8] = b"acme-tls/1";
+
 pub struct TlsTermination {
     pub server_names: Vec<SubjectName>,
-    pub acceptor: Option<TlsAcceptor>,
+    pub acceptor: Option<TlsPortAcceptor>,
     pub alpn_protocols: Vec<Vec<u8>>,
 }
 
@@ -55,6 +62,8 @@ impl TlsTermination {
                 .collect(),
             self.server_names.clone(),
             true,
+            certs.acme_challenges().to_vec(),
+            certs.on_demand().cloned(),
         ));
 
         let mut server_config = ServerConfig::builder()
@@ -65,33 +74,153 @@ impl TlsTermination {
             .alpn_protocols
             .clone_from(&self.alpn_protocols);
 
-        let server_config = Arc::new(server_config);
-        self.acceptor = Some(TlsAcceptor::from(server_config));
+        // Advertising acme-tls/1 on a port without ALPN would make rustls reject
+        // clients offering other protocols, so only extend a non-empty list and
+        // pick a dedicated config for challenge handshakes otherwise.
+        let mut challenge_config = None;
+        if !certs.acme_challenges().is_empty() {
+            if server_config.alpn_protocols.is_empty() {
+                let mut config = server_config.clone();
+                config.alpn_protocols = vec![ACME_TLS_ALPN_NAME.to_vec()];
+                challenge_config = Some(Arc::new(config));
+            } else {
+                server_config
+                    .alpn_protocols
+                    .push(ACME_TLS_ALPN_NAME.to_vec());
+            }
+        }
+
+        self.acceptor = Some(TlsPortAcceptor {
+            config: Arc::new(server_config),
+            challenge_config,
+        });
 
         TlsState::Active
     }
 }
 
+/// Accepts TLS connections on a port, switching to the challenge config when
+/// a client offers acme-tls/1 to a port that does not negotiate ALPN.
+#[derive(Clone)]
+pub struct TlsPortAcceptor {
+    config: Arc<ServerConfig>,
+    challenge_config: Option<Arc<ServerConfig>>,
+}
+
+impl TlsPortAcceptor {
+    pub async fn accept<IO>(&self, stream: IO) -> io::Result<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let Some(challenge_config) = &self.challenge_config else {
+            return TlsAcceptor::from(self.config.clone()).accept(stream).await;
+        };
+        let start = LazyConfigAcceptor::new(Acceptor::default(), stream).await?;
+        let acme_tls_alpn = start
+            .client_hello()
+            .alpn()
+            .into_iter()
+            .flatten()
+            .any(|proto| proto == ACME_TLS_ALPN_NAME);
+        let config = if acme_tls_alpn {
+            challenge_config
+        } else {
+            &self.config
+        };
+        start.into_stream(config.clone()).await
+    }
+}
+
+pub fn acme_challenge_acceptor(challenges: Vec<Arc<Cert>>) -> TlsAcceptor {
+    let resolver: Arc<dyn ResolvesServerCert> =
+        Arc::new(CertResolver::new(vec![], vec![], true, challenges, None));
+    let mut server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+    server_config.alpn_protocols = vec![ACME_TLS_ALPN_NAME.to_vec()];
+    TlsAcceptor::from(Arc::new(server_config))
+}
+
+/// Asks the server to issue a certificate for a server name without one.
+#[derive(Debug, Clone)]
+pub struct OnDemandRequester {
+    sender: mpsc::Sender<ServerCommand>,
+}
+
+impl OnDemandRequester {
+    pub fn new(sender: mpsc::Sender<ServerCommand>) -> Self {
+        Self { sender }
+    }
+
+    fn request(&self, name: &str) {
+        // Never block the handshake; the server deduplicates the requests anyway.
+        let _ = self.sender.try_send(ServerCommand::RequestOnDemandCert {
+            name: name.to_ascii_lowercase(),
+        });
+    }
+}
+
 pub struct CertResolver {
     certs: Vec<Arc<Cert>>,
     default_names: Vec<SubjectName>,
     sni: bool,
+    acme_challenges: Vec<Arc<Cert>>,
+    on_demand: Option<OnDemandRequester>,
     cache: DashMap<ShortId, Arc<CertifiedKey>>,
 }
 
 impl CertResolver {
-    pub fn new(certs: Vec<Arc<Cert>>, default_names: Vec<SubjectName>, sni: bool) -> Self {
+    pub fn new(
+        certs: Vec<Arc<Cert>>,
+        default_names: Vec<SubjectName>,
+        sni: bool,
+        acme_challenges: Vec<Arc<Cert>>,
+        on_demand: Option<OnDemandRequester>,
+    ) -> Self {
         Self {
             certs,
             default_names,
             sni,
+            acme_challenges,
+            on_demand,
             cache: DashMap::new(),
         }
     }
+
+    fn certified_key(&self, cert: &Cert) -> Option<Arc<CertifiedKey>> {
+        if let Some(cert) = self.cache.get(&cert.id()) {
+            Some(cert.clone())
+        } else {
+            let certified = match cert.certified_key() {
+                Ok(certified) => Arc::new(certified),
+                Err(err) => {
+                    error!("failed to load certified key: {}", err);
+                    return None;
+                }
+            };
+            self.cache.insert(cert.id(), certified.clone());
+            Some(certified)
+        }
+    }
 }
 
 impl ResolvesServerCert for CertResolver {
     fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let acme_tls_alpn = client_hello
+            .alpn()
+            .into_iter()
+            .flatten()
+            .any(|proto| proto == ACME_TLS_ALPN_NAME);
+        if acme_tls_alpn {
+            let name = SubjectName::DnsName(client_hello.server_name()?.into());
+            let cert = self
+                .acme_challenges
+                .iter()
+                .find(|cert| cert.has_subject_name(&name))?;
+            return self.certified_key(cert);
+        }
+
         let sni = client_hello
             .server_name()
             .filter(|_| self.sni)
@@ -108,20 +237,14 @@ impl ResolvesServerCert for CertResolver {
         let cert = self
             .certs
             .iter()
-            .find(|cert| cert.is_valid() && names.iter().all(|name| cert.has_subject_name(name)))?;
+            .find(|cert| cert.is_valid() && names.iter().all(|name| cert.has_subject_name(name)));
 
-        if let Some(cert) = self.cache.get(&cert.id()) {
-            Some(cert.clone())
-        } else {
-            let certified = match cert.certified_key() {
-                Ok(certified) => Arc::new(certified),
-                Err(err) => {
-                    error!("failed to load certified key: {}", err);
-                    return None;
-                }
-            };
-            self.cache.insert(cert.id(), certified.clone());
-            Some(certified)
+        if cert.is_none() {
+            if let (Some(on_demand), [SubjectName::DnsName(name)]) = (&self.on_demand, &sni[..]) {
+                on_demand.request(name);
+            }
         }
+
+        self.certified_key(cert?)
     }
 }
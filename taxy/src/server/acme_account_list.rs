@@ -0,0 +1,91 @@
+use crate::certs::acme::{AcmeAccountEntry, Directory};
+use indexmap::IndexMap;
+use std::{collections::HashMap, sync::Arc};
+use taxy_api::{error::Error, id::ShortId};
+
+#[derive(Default)]
+pub struct AcmeAccountList {
+    entries: IndexMap<ShortId, AcmeAccountEntry>,
+    directories: HashMap<ShortId, Arc<Directory>>,
+}
+
+impl std::fmt::Debug for AcmeAccountList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcmeAccountList")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl FromIterator<AcmeAccountEntry> for AcmeAccountList {
+    fn from_iter<I: IntoIterator<Item = AcmeAccountEntry>>(iter: I) -> Self {
+        Self {
+            entries: iter
+                .into_iter()
+                .map(|account| (account.id, account))
+                .collect(),
+            directories: HashMap::new(),
+        }
+    }
+}
+
+impl AcmeAccountList {
+    pub fn get(&self, id: ShortId) -> Option<&AcmeAccountEntry> {
+        self.entries.get(&id)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &AcmeAccountEntry> {
+        self.entries.values()
+    }
+
+    /// Finds a valid account that can be shared by a new ACME entry.
+    pub fn find_reusable(
+        &self,
+        server_url: &str,
+        contacts: &[String],
+    ) -> Option<&AcmeAccountEntry> {
+        self.entries.values().find(|account| {
+            account.is_valid() && account.server_url == server_url && account.contacts == contacts
+        })
+    }
+
+    pub fn add(&mut self, entry: AcmeAccountEntry) -> Result<(), Error> {
+        if self.entries.contains_key(&entry.id) {
+            Err(Error::IdAlreadyExists { id: entry.id })
+        } else {
+            self.entries.insert(entry.id, entry);
+            Ok(())
+        }
+    }
+
+    /// Returns the cached directory of the account's ACME server.
+    pub fn directory(&self, id: ShortId) -> Option<Arc<Directory>> {
+        self.directories.get(&id).cloned()
+    }
+
+    pub fn set_directory(&mut self, id: ShortId, directory: Arc<Directory>) {
+        if self.entries.contains_key(&id) {
+            self.directories.insert(id, directory);
+        }
+    }
+
+    pub fn update(&mut self, entry: AcmeAccountEntry) -> Result<(), Error> {
+        if let Some(account) = self.entries.get_mut(&entry.id) {
+            *account = entry;
+            Ok(())
+        } else {
+            Err(Error::IdNotFound {
+                id: entry.id.to_string(),
+            })
+        }
+    }
+
+    pub fn delete(&mut self, id: ShortId) -> Result<(), Error> {
+        if self.entries.remove(&id).is_none() {
+            Err(Error::IdNotFound { id: id.to_string() })
+        } else {
+            self.directories.remove(&id);
+            Ok(())
+        }
+    }
+}
@@ -1,16 +1,54 @@
-use crate::certs::acme::AcmeEntry;
+use crate::certs::{acme::AcmeEntry, Cert};
+use backoff::{backoff::Backoff, ExponentialBackoff, ExponentialBackoffBuilder};
 use indexmap::IndexMap;
-use taxy_api::{acme::AcmeConfig, error::Error, id::ShortId};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+use taxy_api::{
+    acme::{AcmeConfig, AcmeOrderStatus, AcmeStatus},
+    error::Error,
+    id::ShortId,
+};
+use tokio::time::Instant;
+
+const RETRY_INITIAL_INTERVAL: Duration = Duration::from_secs(60);
+const RETRY_MAX_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
 
 #[derive(Debug, Default)]
 pub struct AcmeList {
     entries: IndexMap<ShortId, AcmeEntry>,
+    states: HashMap<ShortId, EntryState>,
+}
+
+#[derive(Debug, Default)]
+struct EntryState {
+    status: AcmeStatus,
+    suggested_renewal_at: Option<SystemTime>,
+    renewal_check: Option<RenewalCheck>,
+    retry: Option<Retry>,
+    renewal_requested: bool,
+}
+
+/// When to ask the ACME server again for the renewal window of a certificate.
+#[derive(Debug)]
+struct RenewalCheck {
+    cert: ShortId,
+    next_check_at: SystemTime,
+}
+
+#[derive(Debug)]
+struct Retry {
+    backoff: ExponentialBackoff,
+    next_attempt: Instant,
+    next_attempt_at: SystemTime,
 }
 
 impl FromIterator<AcmeEntry> for AcmeList {
     fn from_iter<I: IntoIterator<Item = AcmeEntry>>(iter: I) -> Self {
         Self {
             entries: iter.into_iter().map(|acme| (acme.id, acme)).collect(),
+            states: HashMap::new(),
         }
     }
 }
@@ -34,6 +72,7 @@ impl AcmeList {
     }
 
     pub fn update(&mut self, id: ShortId, config: AcmeConfig) -> Result<AcmeEntry, Error> {
+        config.validate()?;
         if let Some(entry) = self.entries.get_mut(&id) {
             entry.acme.config = config;
             Ok(entry.clone())
@@ -47,7 +86,126 @@ impl AcmeList {
             Err(Error::IdNotFound { id: id.to_string() })
         } else {
             self.entries.remove(&id);
+            self.states.remove(&id);
             Ok(())
         }
     }
+    pub fn status(&self, id: ShortId, cert: Option<&Cert>) -> Option<AcmeStatus> {
+        let entry = self.entries.get(&id)?;
+        let state = self.states.get(&id);
+        let mut status = state.map(|state| state.status.clone()).unwrap_or_default();
+        status.next_renewal_at = if !entry.acme.config.active {
+            None
+        } else if let Some(retry) = state.and_then(|state| state.retry.as_ref()) {
+            Some(retry.next_attempt_at)
+        } else if let Some(cert) = cert {
+            let renewal_at = entry.renewal_time(cert);
+            Some(
+                state
+                    .and_then(|state| state.suggested_renewal_at)
+                    .map_or(renewal_at, |suggested| suggested.min(renewal_at)),
+            )
+        } else {
+            None
+        };
+        Some(status)
+    }
+
+    pub fn set_order_status(&mut self, id: ShortId, order: AcmeOrderStatus) {
+        let state = self.states.entry(id).or_default();
+        if order == AcmeOrderStatus::Requesting {
+            state.status.last_attempt_at = Some(SystemTime::now());
+        }
+        state.status.order = order;
+    }
+
+    pub fn set_suggested_renewal(
+        &mut self,
+        id: ShortId,
+        cert: ShortId,
+        time: Option<SystemTime>,
+        next_check_at: SystemTime,
+    ) {
+        let state = self.states.entry(id).or_default();
+        state.suggested_renewal_at = time;
+        state.renewal_check = Some(RenewalCheck {
+            cert,
+            next_check_at,
+        });
+    }
+
+    pub fn suggested_renewal(&self, id: ShortId) -> Option<SystemTime> {
+        self.states
+            .get(&id)
+            .and_then(|state| state.suggested_renewal_at)
+    }
+
+    /// Returns `true` if the renewal window of `cert` is unknown or outdated.
+    pub fn is_renewal_check_due(&self, id: ShortId, cert: ShortId) -> bool {
+        self.states
+            .get(&id)
+            .and_then(|state| state.renewal_check.as_ref())
+            .filter(|check| check.cert == cert)
+            .is_none_or(|check| check.next_check_at <= SystemTime::now())
+    }
+
+    pub fn record_success(&mut self, id: ShortId) {
+        let state = self.states.entry(id).or_default();
+        state.status.order = AcmeOrderStatus::Idle;
+        state.status.last_success_at = Some(SystemTime::now());
+        state.status.last_error = None;
+        state.suggested_renewal_at = None;
+        state.renewal_check = None;
+        state.retry = None;
+    }
+
+    /// Records a failed request and returns the delay before the next attempt.
+    pub fn record_failure(&mut self, id: ShortId, error: String) -> Duration {
+        let state = self.states.entry(id).or_default();
+        state.status.order = AcmeOrderStatus::Idle;
+        state.status.last_error = Some(error);
+        let retry = state.retry.get_or_insert_with(|| Retry {
+            backoff: ExponentialBackoffBuilder::new()
+                .with_initial_interval(RETRY_INITIAL_INTERVAL)
+                .with_max_interval(RETRY_MAX_INTERVAL)
+                .with_max_elapsed_time(None)
+                .build(),
+            next_attempt: Instant::now(),
+            next_attempt_at: SystemTime::now(),
+        });
+        let delay = retry.backoff.next_backoff().unwrap_or(RETRY_MAX_INTERVAL);
+        retry.next_attempt = Instant::now() + delay;
+        retry.next_attempt_at = SystemTime::now() + delay;
+        delay
+    }
+
+    /// Requests an order on the next run regardless of the renewal schedule.
+    pub fn request_renewal(&mut self, id: ShortId) -> Result<(), Error> {
+        if !self.entries.contains_key(&id) {
+            return Err(Error::IdNotFound { id: id.to_string() });
+        }
+        self.states.entry(id).or_default().renewal_requested = true;
+        Ok(())
+    }
+
+    pub fn is_renewal_requested(&self, id: ShortId) -> bool {
+        self.states
+            .get(&id)
+            .map(|state| state.renewal_requested)
+            .unwrap_or_default()
+    }
+
+    pub fn clear_renewal_request(&mut self, id: ShortId) {
+        if let Some(state) = self.states.get_mut(&id) {
+            state.renewal_requested = false;
+        }
+    }
+
+    pub fn is_retry_pending(&self, id: ShortId) -> bool {
+        self.states
+            .get(&id)
+            .and_then(|state| state.retry.as_ref())
+            .map(|retry| retry.next_attempt > Instant::now())
+            .unwrap_or_default()
+    }
 }
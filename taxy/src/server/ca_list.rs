@@ -0,0 +1,47 @@
+use crate::certs::ca::CaEntry;
+use indexmap::IndexMap;
+use taxy_api::{error::Error, id::ShortId};
+
+#[derive(Debug, Default)]
+pub struct CaList {
+    entries: IndexMap<ShortId, CaEntry>,
+}
+
+impl FromIterator<CaEntry> for CaList {
+    fn from_iter<I: IntoIterator<Item = CaEntry>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().map(|ca| (ca.id, ca)).collect(),
+        }
+    }
+}
+
+impl CaList {
+    pub fn get(&self, id: ShortId) -> Option<&CaEntry> {
+        self.entries.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: ShortId) -> Option<&mut CaEntry> {
+        self.entries.get_mut(&id)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &CaEntry> {
+        self.entries.values()
+    }
+
+    pub fn add(&mut self, entry: CaEntry) -> Result<(), Error> {
+        if self.entries.contains_key(&entry.id) {
+            Err(Error::CaAlreadyExists { id: entry.id })
+        } else {
+            self.entries.insert(entry.id, entry);
+            Ok(())
+        }
+    }
+
+    pub fn delete(&mut self, id: ShortId) -> Result<(), Error> {
+        if self.entries.remove(&id).is_none() {
+            Err(Error::IdNotFound { id: id.to_string() })
+        } else {
+            Ok(())
+        }
+    }
+}
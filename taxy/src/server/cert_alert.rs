@@ -0,0 +1,156 @@
+use super::cert_list::CertList;
+use crate::certs::acme::https_client;
+use hyper::{header::CONTENT_TYPE, Body, Request};
+use serde_derive::Serialize;
+use std::{collections::HashMap, time::Duration};
+use taxy_api::{
+    cert::{CertExpiryAlert, CertExpiryStatus, CertKind, CertUsage},
+    id::ShortId,
+};
+use tokio::time::Instant;
+use tokio_rustls::rustls::RootCertStore;
+use tracing::warn;
+use x509_parser::time::ASN1Time;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Default)]
+pub struct CertAlertList {
+    alerted: HashMap<ShortId, (CertExpiryStatus, Instant)>,
+}
+
+impl CertAlertList {
+    /// Drops alerts that have already been sent with the same status within `repeat_interval`.
+    pub fn filter(
+        &mut self,
+        alerts: Vec<CertExpiryAlert>,
+        repeat_interval: Duration,
+    ) -> Vec<CertExpiryAlert> {
+        let now = Instant::now();
+        self.alerted
+            .retain(|id, _| alerts.iter().any(|alert| alert.id == *id));
+        alerts
+            .into_iter()
+            .filter(|alert| match self.alerted.get(&alert.id) {
+                Some((status, sent_at))
+                    if *status == alert.status && now - *sent_at < repeat_interval =>
+                {
+                    false
+                }
+                _ => {
+                    self.alerted.insert(alert.id, (alert.status, now));
+                    true
+                }
+            })
+            .collect()
+    }
+}
+
+/// Finds the certificates presented for `names` that expire within `threshold`.
+///
+/// If no valid certificate covers a name, the expired or not yet valid certificate
+/// that matches the name is reported instead.
+pub fn find_alerts(
+    certs: &CertList,
+    names: &[CertUsage],
+    threshold: Duration,
+) -> Vec<CertExpiryAlert> {
+    let now = ASN1Time::now();
+    let mut alerts: Vec<CertExpiryAlert> = Vec::new();
+    for usage in names {
+        let (cert, status) = if let Some(cert) = certs.find_server_cert(&usage.name) {
+            let remaining = cert.not_after.timestamp() - now.timestamp();
+            if remaining >= threshold.as_secs() as i64 {
+                continue;
+            }
+            (cert, CertExpiryStatus::Expiring)
+        } else if let Some(cert) = certs
+            .iter()
+            .find(|cert| cert.kind == CertKind::Server && cert.has_subject_name(&usage.name))
+        {
+            if cert.not_after < now {
+                (cert, CertExpiryStatus::Expired)
+            } else {
+                (cert, CertExpiryStatus::NotYetValid)
+            }
+        } else {
+            continue;
+        };
+        match alerts.iter_mut().find(|alert| alert.id == cert.id()) {
+            Some(alert) => alert.used_by.push(usage.clone()),
+            None => alerts.push(CertExpiryAlert {
+                id: cert.id(),
+                status,
+                san: cert.san.clone(),
+                not_after: cert.not_after.timestamp(),
+                not_before: cert.not_before.timestamp(),
+                used_by: vec![usage.clone()],
+            }),
+        }
+    }
+    alerts
+}
+
+/// Returns a human readable summary of the alert.
+pub fn message(alert: &CertExpiryAlert) -> String {
+    let names = alert
+        .san
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let state = match alert.status {
+        CertExpiryStatus::Expiring => {
+            let days = (alert.not_after - ASN1Time::now().timestamp()) / (60 * 60 * 24);
+            format!("expires in {days} days")
+        }
+        CertExpiryStatus::Expired => "has expired".into(),
+        CertExpiryStatus::NotYetValid => "is not yet valid".into(),
+    };
+    format!(
+        "Certificate {} ({names}) {state}; used by {} server names",
+        alert.id,
+        alert.used_by.len()
+    )
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    text: String,
+    #[serde(flatten)]
+    alert: &'a CertExpiryAlert,
+}
+
+/// POSTs the alert as JSON to `url`.
+///
+/// The `text` field makes the payload compatible with the incoming webhooks of common chat services.
+pub async fn send_webhook(url: String, alert: CertExpiryAlert, root_certs: RootCertStore) {
+    match tokio::time::timeout(WEBHOOK_TIMEOUT, send_impl(&url, &alert, &root_certs)).await {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => {
+            warn!(?err, url, "failed to send certificate alert webhook");
+        }
+        Err(_) => {
+            warn!(url, "certificate alert webhook timed out");
+        }
+    }
+}
+
+async fn send_impl(
+    url: &str,
+    alert: &CertExpiryAlert,
+    root_certs: &RootCertStore,
+) -> anyhow::Result<()> {
+    let payload = WebhookPayload {
+        text: message(alert),
+        alert,
+    };
+    let req = Request::post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload)?))?;
+    let res = https_client(root_certs).request(req).await?;
+    if !res.status().is_success() {
+        anyhow::bail!("unexpected status: {}", res.status());
+    }
+    Ok(())
+}
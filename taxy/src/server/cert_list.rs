@@ -1,8 +1,9 @@
 use crate::certs::Cert;
+use crate::proxy::tls::OnDemandRequester;
 use indexmap::IndexMap;
 use log::warn;
 use std::sync::Arc;
-use taxy_api::{cert::CertKind, error::Error, id::ShortId};
+use taxy_api::{cert::CertKind, error::Error, id::ShortId, subject_name::SubjectName};
 use tokio_rustls::rustls::{Certificate, RootCertStore};
 
 #[derive(Debug)]
@@ -10,6 +11,8 @@ pub struct CertList {
     certs: IndexMap<ShortId, Arc<Cert>>,
     system_root_certs: RootCertStore,
     root_certs: RootCertStore,
+    acme_challenges: Vec<Arc<Cert>>,
+    on_demand: Option<OnDemandRequester>,
 }
 
 impl CertList {
@@ -41,6 +44,8 @@ impl CertList {
             certs,
             system_root_certs: system_root_certs.clone(),
             root_certs: RootCertStore::empty(),
+            acme_challenges: Vec::new(),
+            on_demand: None,
         };
         this.update_root_certs();
         this
@@ -54,6 +59,22 @@ impl CertList {
         &self.root_certs
     }
 
+    pub fn acme_challenges(&self) -> &[Arc<Cert>] {
+        &self.acme_challenges
+    }
+
+    pub fn set_acme_challenges(&mut self, certs: Vec<Arc<Cert>>) {
+        self.acme_challenges = certs;
+    }
+
+    pub fn on_demand(&self) -> Option<&OnDemandRequester> {
+        self.on_demand.as_ref()
+    }
+
+    pub fn set_on_demand(&mut self, on_demand: Option<OnDemandRequester>) {
+        self.on_demand = on_demand;
+    }
+
     pub fn find_certs_by_acme(&self, acme: ShortId) -> Vec<&Arc<Cert>> {
         self.certs
             .values()
@@ -65,6 +86,13 @@ impl CertList {
             .collect()
     }
 
+    /// Returns the server certificate that is presented for `name`.
+    pub fn find_server_cert(&self, name: &SubjectName) -> Option<&Arc<Cert>> {
+        self.certs.values().find(|cert| {
+            cert.kind == CertKind::Server && cert.is_valid() && cert.has_subject_name(name)
+        })
+    }
+
     pub fn get(&self, id: ShortId) -> Option<&Arc<Cert>> {
         self.certs.get(&id)
     }
@@ -77,6 +105,31 @@ impl CertList {
         }
     }
 
+    /// Replaces the certificates loaded from external directories.
+    ///
+    /// A stored certificate with the same ID takes precedence over an external one.
+    /// Returns `true` if the list has changed.
+    pub fn set_external(&mut self, certs: Vec<Arc<Cert>>) -> bool {
+        let len = self.certs.len();
+        self.certs.retain(|id, cert| {
+            cert.source.is_none()
+                || certs
+                    .iter()
+                    .any(|new| new.id() == *id && new.source == cert.source)
+        });
+        let mut changed = self.certs.len() != len;
+        for cert in certs {
+            if !self.certs.contains_key(&cert.id()) {
+                self.certs.insert(cert.id(), cert);
+                changed = true;
+            }
+        }
+        if changed {
+            self.certs.sort_unstable_by(|_, v1, _, v2| v1.cmp(v2));
+        }
+        changed
+    }
+
     pub fn delete(&mut self, id: ShortId) -> Result<(), Error> {
         if !self.certs.contains_key(&id) {
             Err(Error::IdNotFound { id: id.to_string() })
@@ -0,0 +1,160 @@
+use crate::{command::ServerCommand, config::file::read_cert_pair};
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use taxy_api::{app::CertWatchConfig, cert::CertKind};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{error, info};
+
+/// File name pairs of a certificate chain and its private key, in order of preference.
+///
+/// These match the output of certbot, cert-manager and taxy's own certificate store.
+const CERT_PAIRS: &[(&str, &str)] = &[
+    ("fullchain.pem", "privkey.pem"),
+    ("tls.crt", "tls.key"),
+    ("cert.pem", "key.pem"),
+];
+
+/// Shortest interval between two scans. A zero interval from the config is raised to this.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+pub struct CertWatcher {
+    config: Option<CertWatchConfig>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl CertWatcher {
+    /// Restarts the watcher if the configuration has changed.
+    pub fn update(
+        &mut self,
+        config: Option<&CertWatchConfig>,
+        command: mpsc::Sender<ServerCommand>,
+    ) {
+        if self.config.as_ref() == config {
+            return;
+        }
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.config = config.cloned();
+        if let Some(config) = config {
+            self.task = Some(tokio::spawn(watch(config.clone(), command)));
+        }
+    }
+}
+
+impl Drop for CertWatcher {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CertPair {
+    dir: PathBuf,
+    chain: PathBuf,
+    key: PathBuf,
+    modified: (Option<SystemTime>, Option<SystemTime>),
+}
+
+async fn watch(config: CertWatchConfig, command: mpsc::Sender<ServerCommand>) {
+    let mut interval = tokio::time::interval(config.interval.max(MIN_INTERVAL));
+    let mut last_pairs = None;
+    loop {
+        interval.tick().await;
+        let dirs = config.dirs.clone();
+        let Ok(pairs) = tokio::task::spawn_blocking(move || find_pairs(&dirs)).await else {
+            continue;
+        };
+        if last_pairs.as_ref() == Some(&pairs) {
+            continue;
+        }
+
+        let mut certs = Vec::new();
+        for pair in &pairs {
+            match read_cert_pair(CertKind::Server, &pair.chain, &pair.key).await {
+                Ok(mut cert) => {
+                    cert.source = Some(pair.dir.clone());
+                    certs.push(Arc::new(cert));
+                }
+                Err(err) => error!(path = ?pair.dir, "failed to load: {err}"),
+            }
+        }
+        info!(count = certs.len(), "loaded external certificates");
+        last_pairs = Some(pairs);
+
+        if command
+            .send(ServerCommand::SetExternalCerts { certs })
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn find_pairs(dirs: &[PathBuf]) -> Vec<CertPair> {
+    let patterns = CERT_PAIRS
+        .iter()
+        .map(|(chain, _)| format!("**/{chain}"))
+        .collect::<Vec<_>>();
+
+    let mut cert_dirs = BTreeSet::new();
+    for dir in dirs {
+        let walker = match globwalk::GlobWalkerBuilder::from_patterns(dir, &patterns)
+            .follow_links(true)
+            .build()
+        {
+            Ok(walker) => walker,
+            Err(err) => {
+                error!(path = ?dir, "failed to watch: {err}");
+                continue;
+            }
+        };
+        for entry in walker.filter_map(Result::ok) {
+            if let Some(parent) = entry.path().parent() {
+                if !is_hidden_data_dir(parent.strip_prefix(dir).unwrap_or(parent)) {
+                    cert_dirs.insert(parent.to_path_buf());
+                }
+            }
+        }
+    }
+
+    cert_dirs
+        .into_iter()
+        .filter_map(|dir| {
+            let (chain, key) = CERT_PAIRS
+                .iter()
+                .map(|(chain, key)| (dir.join(chain), dir.join(key)))
+                .find(|(chain, _)| chain.is_file())?;
+            let modified = (modified(&chain), modified(&key));
+            Some(CertPair {
+                dir,
+                chain,
+                key,
+                modified,
+            })
+        })
+        .collect()
+}
+
+/// Kubernetes mounts secrets through `..data` symlinks to timestamped directories.
+/// Only the top-level links are loaded so that each secret appears once.
+fn is_hidden_data_dir(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with(".."),
+        _ => false,
+    })
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
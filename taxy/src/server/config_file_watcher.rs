@@ -0,0 +1,102 @@
+use crate::{command::ServerCommand, config::managed};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{error, info};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reloads the config file when it changes or on SIGHUP.
+#[derive(Debug)]
+pub struct ConfigFileWatcher {
+    task: JoinHandle<()>,
+}
+
+impl ConfigFileWatcher {
+    pub fn new(path: PathBuf, command: mpsc::Sender<ServerCommand>) -> Self {
+        Self {
+            task: tokio::spawn(watch(path, command)),
+        }
+    }
+}
+
+impl Drop for ConfigFileWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn watch(path: PathBuf, command: mpsc::Sender<ServerCommand>) {
+    let mut hangup = hangup_signal();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut last_modified = modified(&path).await;
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let modified = modified(&path).await;
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+                info!(?path, "config file changed");
+            }
+            _ = recv_hangup(&mut hangup) => {
+                info!(?path, "received SIGHUP");
+            }
+        }
+
+        let snapshot = match managed::load(&path).await {
+            Ok(snapshot) => Box::new(snapshot),
+            Err(err) => {
+                error!("config file not reloaded: {err}");
+                continue;
+            }
+        };
+        if command
+            .send(ServerCommand::ApplyConfigFile { snapshot })
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+async fn modified(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(unix)]
+type Hangup = Option<tokio::signal::unix::Signal>;
+
+#[cfg(not(unix))]
+type Hangup = ();
+
+#[cfg(unix)]
+fn hangup_signal() -> Hangup {
+    use tokio::signal::unix::{signal, SignalKind};
+    signal(SignalKind::hangup())
+        .map_err(|err| error!(?err, "failed to listen for SIGHUP"))
+        .ok()
+}
+
+#[cfg(not(unix))]
+fn hangup_signal() -> Hangup {}
+
+#[cfg(unix)]
+async fn recv_hangup(signal: &mut Hangup) {
+    match signal {
+        Some(signal) => {
+            signal.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(not(unix))]
+async fn recv_hangup(_signal: &mut Hangup) {
+    std::future::pending().await
+}
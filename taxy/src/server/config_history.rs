@@ -0,0 +1,169 @@
+use serde::Serialize;
+use std::{collections::VecDeque, fmt::Display, time::SystemTime};
+use taxy_api::history::{
+    ChangeAction, ConfigChange, ConfigResource, ConfigSnapshot, ConfigVersion, ConfigVersionInfo,
+};
+
+/// The number of versions kept in the history.
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct ConfigHistory {
+    versions: VecDeque<ConfigVersion>,
+}
+
+impl FromIterator<ConfigVersion> for ConfigHistory {
+    fn from_iter<I: IntoIterator<Item = ConfigVersion>>(iter: I) -> Self {
+        let mut versions = iter.into_iter().collect::<Vec<_>>();
+        versions.sort_by_key(|version| version.version);
+        Self {
+            versions: versions.into(),
+        }
+    }
+}
+
+impl ConfigHistory {
+    pub fn latest(&self) -> Option<&ConfigVersion> {
+        self.versions.back()
+    }
+
+    pub fn get(&self, version: u64) -> Option<&ConfigVersion> {
+        self.versions.iter().find(|entry| entry.version == version)
+    }
+
+    /// Returns the version recorded just before `version`.
+    pub fn previous(&self, version: u64) -> Option<&ConfigVersion> {
+        self.versions
+            .iter()
+            .rev()
+            .find(|entry| entry.version < version)
+    }
+
+    /// Lists the versions, newest first, with the changes from the previous version.
+    pub fn list(&self) -> Vec<ConfigVersionInfo> {
+        let empty = ConfigSnapshot::default();
+        let mut prev = &empty;
+        let mut list = Vec::with_capacity(self.versions.len());
+        for entry in &self.versions {
+            let changes = diff(prev, &entry.snapshot)
+                .into_iter()
+                .map(|change| ConfigChange {
+                    before: None,
+                    after: None,
+                    ..change
+                })
+                .collect();
+            list.push(ConfigVersionInfo {
+                version: entry.version,
+                author: entry.author.clone(),
+                timestamp: entry.timestamp,
+                changes,
+            });
+            prev = &entry.snapshot;
+        }
+        list.reverse();
+        list
+    }
+
+    /// Adds a version and returns it, with the numbers of the versions that no longer fit.
+    pub fn push(
+        &mut self,
+        author: Option<String>,
+        snapshot: ConfigSnapshot,
+    ) -> (ConfigVersion, Vec<u64>) {
+        let version = ConfigVersion {
+            version: self.latest().map_or(1, |latest| latest.version + 1),
+            author,
+            timestamp: SystemTime::now(),
+            snapshot,
+        };
+        self.versions.push_back(version.clone());
+        let mut removed = Vec::new();
+        while self.versions.len() > HISTORY_LIMIT {
+            if let Some(entry) = self.versions.pop_front() {
+                removed.push(entry.version);
+            }
+        }
+        (version, removed)
+    }
+}
+
+/// Lists the differences between two snapshots, by resource ID.
+pub fn diff(from: &ConfigSnapshot, to: &ConfigSnapshot) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    if from.config != to.config {
+        changes.push(ConfigChange {
+            resource: ConfigResource::AppConfig,
+            id: None,
+            action: ChangeAction::Updated,
+            before: serde_json::to_value(&from.config).ok(),
+            after: serde_json::to_value(&to.config).ok(),
+        });
+    }
+    diff_entries(
+        &mut changes,
+        ConfigResource::Port,
+        from.ports.iter().map(|entry| (entry.id, &entry.port)),
+        to.ports.iter().map(|entry| (entry.id, &entry.port)),
+    );
+    diff_entries(
+        &mut changes,
+        ConfigResource::Proxy,
+        from.proxies.iter().map(|entry| (entry.id, &entry.proxy)),
+        to.proxies.iter().map(|entry| (entry.id, &entry.proxy)),
+    );
+    diff_entries(
+        &mut changes,
+        ConfigResource::Acme,
+        from.acme.iter().map(|entry| (entry.id, entry)),
+        to.acme.iter().map(|entry| (entry.id, entry)),
+    );
+    diff_entries(
+        &mut changes,
+        ConfigResource::Cert,
+        from.certs.iter().map(|id| (*id, id)),
+        to.certs.iter().map(|id| (*id, id)),
+    );
+    changes
+}
+
+fn diff_entries<'a, K, T, F, U>(
+    changes: &mut Vec<ConfigChange>,
+    resource: ConfigResource,
+    from: F,
+    to: U,
+) where
+    K: PartialEq + Display,
+    T: PartialEq + Serialize + 'a,
+    F: Iterator<Item = (K, &'a T)>,
+    U: Iterator<Item = (K, &'a T)>,
+{
+    let from = from.collect::<Vec<_>>();
+    let to = to.collect::<Vec<_>>();
+    for (id, before) in &from {
+        let action = match to.iter().find(|(other, _)| other == id) {
+            Some((_, after)) if after == before => continue,
+            Some(_) => ChangeAction::Updated,
+            None => ChangeAction::Removed,
+        };
+        let after = to.iter().find(|(other, _)| other == id).map(|(_, v)| *v);
+        changes.push(ConfigChange {
+            resource,
+            id: Some(id.to_string()),
+            action,
+            before: serde_json::to_value(before).ok(),
+            after: after.and_then(|after| serde_json::to_value(after).ok()),
+        });
+    }
+    for (id, after) in &to {
+        if !from.iter().any(|(other, _)| other == id) {
+            changes.push(ConfigChange {
+                resource,
+                id: Some(id.to_string()),
+                action: ChangeAction::Added,
+                before: None,
+                after: serde_json::to_value(after).ok(),
+            });
+        }
+    }
+}
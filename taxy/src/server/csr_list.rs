@@ -0,0 +1,43 @@
+use crate::certs::csr::CsrEntry;
+use indexmap::IndexMap;
+use taxy_api::{error::Error, id::ShortId};
+
+#[derive(Debug, Default)]
+pub struct CsrList {
+    entries: IndexMap<ShortId, CsrEntry>,
+}
+
+impl FromIterator<CsrEntry> for CsrList {
+    fn from_iter<I: IntoIterator<Item = CsrEntry>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().map(|csr| (csr.id, csr)).collect(),
+        }
+    }
+}
+
+impl CsrList {
+    pub fn get(&self, id: ShortId) -> Option<&CsrEntry> {
+        self.entries.get(&id)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &CsrEntry> {
+        self.entries.values()
+    }
+
+    pub fn add(&mut self, entry: CsrEntry) -> Result<(), Error> {
+        if self.entries.contains_key(&entry.id) {
+            Err(Error::IdAlreadyExists { id: entry.id })
+        } else {
+            self.entries.insert(entry.id, entry);
+            Ok(())
+        }
+    }
+
+    pub fn delete(&mut self, id: ShortId) -> Result<(), Error> {
+        if self.entries.remove(&id).is_none() {
+            Err(Error::IdNotFound { id: id.to_string() })
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -12,19 +12,19 @@ use tracing::{error, info, span, Instrument, Level};
 #[derive(Debug)]
 pub struct TcpListenerPool {
     listeners: Vec<TcpListenerStream>,
-    http_challenge_addr: Option<SocketAddr>,
+    reserved_addrs: Vec<SocketAddr>,
 }
 
 impl TcpListenerPool {
     pub fn new() -> Self {
         Self {
             listeners: Vec::new(),
-            http_challenge_addr: None,
+            reserved_addrs: Vec::new(),
         }
     }
 
-    pub fn set_http_challenge_addr(&mut self, addr: Option<SocketAddr>) {
-        self.http_challenge_addr = addr;
+    pub fn set_reserved_addrs(&mut self, addrs: Vec<SocketAddr>) {
+        self.reserved_addrs = addrs;
     }
 
     pub fn has_active_listeners(&self) -> bool {
@@ -32,25 +32,28 @@ impl TcpListenerPool {
     }
 
     pub async fn update(&mut self, ports: &mut [PortContext]) {
-        let mut reserved_ports = Vec::new();
-        if let Some(reserved_addr) = self.http_challenge_addr {
-            let port_used = ports.iter().any(|ctx| match ctx.kind() {
-                PortContextKind::Tcp(state) => state.listen.port() == reserved_addr.port(),
-                PortContextKind::Http(state) => state.listen.port() == reserved_addr.port(),
-                _ => false,
-            });
+        let mut reserved_ports = Vec::<PortContext>::new();
+        for &reserved_addr in &self.reserved_addrs {
+            let port_used = ports
+                .iter()
+                .chain(&reserved_ports)
+                .any(|ctx| match ctx.kind() {
+                    PortContextKind::Tcp(state) => state.listen.port() == reserved_addr.port(),
+                    PortContextKind::Http(state) => state.listen.port() == reserved_addr.port(),
+                    PortContextKind::Reserved(addr) => addr.port() == reserved_addr.port(),
+                });
             if !port_used {
-                reserved_ports.push(PortContext::reserved());
+                reserved_ports.push(PortContext::reserved(reserved_addr));
             }
         }
 
         let used_addrs = ports
             .iter()
             .chain(&reserved_ports)
-            .filter_map(|ctx| match ctx.kind() {
-                PortContextKind::Tcp(state) => Some(state.listen),
-                PortContextKind::Http(state) => Some(state.listen),
-                _ => None,
+            .map(|ctx| match ctx.kind() {
+                PortContextKind::Tcp(state) => state.listen,
+                PortContextKind::Http(state) => state.listen,
+                PortContextKind::Reserved(addr) => *addr,
             })
             .collect::<HashSet<_>>();
 
@@ -76,13 +79,7 @@ impl TcpListenerPool {
             let bind = match ctx.kind() {
                 PortContextKind::Tcp(state) => state.listen,
                 PortContextKind::Http(state) => state.listen,
-                _ => {
-                    if let Some(addr) = self.http_challenge_addr {
-                        addr
-                    } else {
-                        continue;
-                    }
-                }
+                PortContextKind::Reserved(addr) => *addr,
             };
             let (listener, state) = if !ctx.entry.port.active {
                 (None, SocketState::Inactive)
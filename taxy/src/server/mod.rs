@@ -1,4 +1,6 @@
+pub(crate) use self::on_demand::ON_DEMAND_PROVIDER;
 use self::rpc::RpcCallback;
+pub(crate) use self::state::random_id;
 use self::state::ServerState;
 use crate::command::ServerCommand;
 use crate::config::storage::Storage;
@@ -8,13 +10,22 @@ use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{info, warn};
 
+mod acme_account_list;
 mod acme_list;
+mod ca_list;
+mod cert_alert;
 pub mod cert_list;
+mod cert_watcher;
+mod config_file_watcher;
+mod config_history;
+mod csr_list;
 mod listener;
+mod on_demand;
 mod port_list;
 mod proxy_list;
 pub mod rpc;
 mod state;
+mod validation;
 
 pub struct Server {
     app_info: AppInfo,
@@ -33,6 +44,7 @@ impl Server {
         let (event_send, _) = broadcast::channel(16);
         let server_state = ServerState::new(
             config,
+            app_info.config_file.clone(),
             command_send.clone(),
             callback_send,
             event_send.clone(),
@@ -0,0 +1,154 @@
+use crate::certs::acme::https_client;
+use hyper::StatusCode;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+use taxy_api::app::OnDemandTlsConfig;
+use tokio::time::Instant;
+use tokio_rustls::rustls::RootCertStore;
+use tracing::{debug, warn};
+use url::Url;
+
+/// The provider name of the ACME entries added by on-demand TLS.
+pub const ON_DEMAND_PROVIDER: &str = "On-Demand TLS";
+
+const ASK_TIMEOUT: Duration = Duration::from_secs(10);
+const DENIED_RETRY_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+/// Maximum number of names being asked about at the same time.
+const MAX_PENDING: usize = 8;
+
+/// Maximum number of denied names remembered. The oldest ones are forgotten first.
+const MAX_DENIED: usize = 1024;
+
+/// Maximum number of names accepted for checking per window.
+const RATE_LIMIT_REQUESTS: usize = 10;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+pub struct OnDemandList {
+    pending: HashSet<String>,
+    denied: HashMap<String, Instant>,
+    window: Option<(Instant, usize)>,
+}
+
+impl OnDemandList {
+    pub fn start(&mut self, name: &str) -> bool {
+        if self.pending.contains(name) {
+            return false;
+        }
+        if let Some(denied_at) = self.denied.get(name) {
+            if denied_at.elapsed() < DENIED_RETRY_INTERVAL {
+                return false;
+            }
+            self.denied.remove(name);
+        }
+        if self.pending.len() >= MAX_PENDING {
+            debug!(name, "too many pending on-demand requests");
+            return false;
+        }
+
+        let (started_at, count) = match self.window {
+            Some((started_at, count)) if started_at.elapsed() < RATE_LIMIT_WINDOW => {
+                (started_at, count)
+            }
+            _ => (Instant::now(), 0),
+        };
+        self.window = Some((started_at, count + 1));
+        if count >= RATE_LIMIT_REQUESTS {
+            if count == RATE_LIMIT_REQUESTS {
+                warn!(name, "on-demand tls rate limit exceeded");
+            }
+            return false;
+        }
+        self.pending.insert(name.to_string())
+    }
+
+    pub fn finish(&mut self, name: &str, allowed: bool) {
+        self.pending.remove(name);
+        if !allowed {
+            self.deny(name);
+        }
+    }
+
+    /// Ignores further requests for a name until `DENIED_RETRY_INTERVAL` has passed.
+    pub fn deny(&mut self, name: &str) {
+        self.denied
+            .retain(|_, denied_at| denied_at.elapsed() < DENIED_RETRY_INTERVAL);
+        if self.denied.len() >= MAX_DENIED {
+            if let Some(oldest) = self
+                .denied
+                .iter()
+                .min_by_key(|(_, denied_at)| **denied_at)
+                .map(|(name, _)| name.clone())
+            {
+                self.denied.remove(&oldest);
+            }
+        }
+        self.denied.insert(name.to_string(), Instant::now());
+    }
+}
+
+pub async fn ask(config: &OnDemandTlsConfig, name: &str, root_certs: &RootCertStore) -> bool {
+    let Some(ask_url) = &config.ask_url else {
+        return false;
+    };
+    match tokio::time::timeout(ASK_TIMEOUT, ask_impl(ask_url, name, root_certs)).await {
+        Ok(Ok(status)) => status.is_success(),
+        Ok(Err(err)) => {
+            warn!(?err, name, "failed to ask on-demand tls endpoint");
+            false
+        }
+        Err(_) => {
+            warn!(name, "on-demand tls endpoint timed out");
+            false
+        }
+    }
+}
+
+async fn ask_impl(
+    ask_url: &str,
+    name: &str,
+    root_certs: &RootCertStore,
+) -> anyhow::Result<StatusCode> {
+    let mut url = Url::parse(ask_url)?;
+    url.query_pairs_mut().append_pair("domain", name);
+    let res = https_client(root_certs).get(url.as_str().parse()?).await?;
+    Ok(res.status())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_on_demand_limits() {
+        let mut list = OnDemandList::default();
+        for i in 0..MAX_PENDING {
+            assert!(list.start(&format!("{i}.example.com")));
+        }
+        assert!(!list.start("0.example.com"));
+        assert!(!list.start("full.example.com"));
+
+        for i in 0..MAX_PENDING {
+            list.finish(&format!("{i}.example.com"), false);
+        }
+        assert!(!list.start("0.example.com"));
+        for i in MAX_PENDING..RATE_LIMIT_REQUESTS {
+            assert!(list.start(&format!("{i}.example.com")));
+            list.finish(&format!("{i}.example.com"), true);
+        }
+        assert!(!list.start("limited.example.com"));
+
+        // A new window starts.
+        list.window = None;
+        assert!(list.start("limited.example.com"));
+        assert!(!list.start("0.example.com"));
+
+        for i in 0..MAX_DENIED + 1 {
+            list.deny(&format!("denied{i}.example.com"));
+        }
+        assert_eq!(list.denied.len(), MAX_DENIED);
+    }
+}
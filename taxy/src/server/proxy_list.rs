@@ -1,9 +1,10 @@
 use indexmap::map::Entry;
 use indexmap::IndexMap;
+use std::collections::HashMap;
 use taxy_api::error::Error;
 use taxy_api::id::ShortId;
 use taxy_api::port::PortEntry;
-use taxy_api::proxy::{Proxy, ProxyEntry, ProxyKind, ProxyState, ProxyStatus};
+use taxy_api::proxy::{Proxy, ProxyEntry, ProxyKind, ProxyState, ProxyStatus, RouteConflict};
 
 #[derive(Debug)]
 pub struct ProxyContext {
@@ -20,7 +21,10 @@ impl ProxyContext {
         };
         Self {
             entry,
-            status: ProxyStatus { state },
+            status: ProxyStatus {
+                state,
+                conflicts: Vec::new(),
+            },
         }
     }
 }
@@ -81,6 +85,23 @@ impl ProxyList {
         }
     }
 
+    /// Replaces the route conflicts of every proxy. Returns the IDs of the proxies whose status changed.
+    pub fn set_conflicts(&mut self, conflicts: Vec<(ShortId, RouteConflict)>) -> Vec<ShortId> {
+        let mut grouped = HashMap::<ShortId, Vec<RouteConflict>>::new();
+        for (id, conflict) in conflicts {
+            grouped.entry(id).or_default().push(conflict);
+        }
+        let mut changed = Vec::new();
+        for ctx in self.entries.values_mut() {
+            let conflicts = grouped.remove(&ctx.entry.id).unwrap_or_default();
+            if ctx.status.conflicts != conflicts {
+                ctx.status.conflicts = conflicts;
+                changed.push(ctx.entry.id);
+            }
+        }
+        changed
+    }
+
     pub fn remove_incompatible_ports(&mut self, ports: &[PortEntry]) -> bool {
         let mut changed = false;
         for ctx in self.entries.values_mut() {
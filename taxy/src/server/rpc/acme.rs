@@ -1,7 +1,10 @@
 use super::RpcMethod;
-use crate::{certs::acme::AcmeEntry, server::state::ServerState};
+use crate::{
+    certs::acme::{AcmeAccountEntry, AcmeEntry},
+    server::state::ServerState,
+};
 use taxy_api::{
-    acme::{AcmeConfig, AcmeInfo, AcmeRequest},
+    acme::{AcmeAccountRequest, AcmeConfig, AcmeInfo, AcmeRequest, AcmeRevokeRequest, AcmeStatus},
     error::Error,
     id::ShortId,
 };
@@ -36,6 +39,21 @@ impl RpcMethod for GetAcme {
     }
 }
 
+pub struct GetAcmeStatus {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetAcmeStatus {
+    type Output = AcmeStatus;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.acme_status(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })
+    }
+}
+
 pub struct AddAcme {
     pub request: AcmeRequest,
 }
@@ -45,7 +63,42 @@ impl RpcMethod for AddAcme {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
-        let entry = AcmeEntry::new(state.generate_id(), self.request).await?;
+        state.check_writable()?;
+        let request = self.request;
+        request.acme.validate()?;
+
+        let account_id = if let Some(account_id) = request.account_id {
+            state
+                .acme_accounts
+                .get(account_id)
+                .ok_or(Error::IdNotFound {
+                    id: account_id.to_string(),
+                })?
+                .ensure_valid()?;
+            account_id
+        } else if let Some(account) = state
+            .acme_accounts
+            .find_reusable(&request.server_url, &request.contacts)
+        {
+            account.id
+        } else {
+            let account = AcmeAccountEntry::new(
+                state.generate_id(),
+                AcmeAccountRequest {
+                    server_url: request.server_url,
+                    contacts: request.contacts,
+                    eab: request.eab,
+                },
+                state.certs.root_certs(),
+            )
+            .await?;
+            state.acme_accounts.add(account.clone())?;
+            state.storage.save_acme_account(&account).await;
+            state.update_acme_accounts();
+            account.id
+        };
+
+        let entry = AcmeEntry::new(state.generate_id(), request.acme, account_id)?;
         state.acmes.add(entry.clone())?;
         state.storage.save_acme(&entry).await;
         state.update_acmes().await;
@@ -63,6 +116,7 @@ impl RpcMethod for UpdateAcme {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         let entry = state.acmes.update(self.id, self.config)?;
         state.storage.save_acme(&entry).await;
         state.update_acmes().await;
@@ -79,9 +133,70 @@ impl RpcMethod for DeleteAcme {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         state.acmes.delete(self.id)?;
         state.update_acmes().await;
         state.storage.delete_acme(self.id).await;
         Ok(())
     }
 }
+
+pub struct RenewAcme {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for RenewAcme {
+    type Output = ();
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.acmes.request_renewal(self.id)?;
+        state.start_acme_challenges().await;
+        Ok(())
+    }
+}
+
+pub struct RevokeAcmeCert {
+    pub id: ShortId,
+    pub request: AcmeRevokeRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for RevokeAcmeCert {
+    type Output = ();
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let entry = state.acmes.get(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })?;
+        let cert_id = self.request.cert_id;
+        let cert = state.certs.get(cert_id).ok_or(Error::IdNotFound {
+            id: cert_id.to_string(),
+        })?;
+        if cert.metadata.as_ref().map(|meta| meta.acme_id) != Some(self.id) {
+            return Err(Error::CertNotIssuedByAcme {
+                id: cert_id,
+                acme_id: self.id,
+            });
+        }
+        let account = state
+            .acme_accounts
+            .get(entry.account_id)
+            .ok_or(Error::IdNotFound {
+                id: entry.account_id.to_string(),
+            })?;
+        account
+            .revoke(cert, self.request.reason.code(), state.certs.root_certs())
+            .await
+            .map_err(|err| Error::AcmeRevocationFailed {
+                detail: err.to_string(),
+            })?;
+
+        state.certs.delete(cert_id)?;
+        state.update_certs().await;
+        state.reload_proxies().await;
+        state.storage.delete_cert(cert_id).await;
+        state.update_acme_status(self.id);
+        Ok(())
+    }
+}
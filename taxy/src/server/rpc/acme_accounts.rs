@@ -0,0 +1,175 @@
+use super::RpcMethod;
+use crate::{certs::acme::AcmeAccountEntry, server::state::ServerState};
+use instant_acme::AccountCredentials;
+use taxy_api::{
+    acme::{AcmeAccountImport, AcmeAccountInfo, AcmeAccountRequest, AcmeAccountUpdate},
+    error::Error,
+    id::ShortId,
+};
+
+pub struct GetAcmeAccountList;
+
+#[async_trait::async_trait]
+impl RpcMethod for GetAcmeAccountList {
+    type Output = Vec<AcmeAccountInfo>;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(state
+            .acme_accounts
+            .entries()
+            .map(|account| account.info())
+            .collect())
+    }
+}
+
+pub struct GetAcmeAccount {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetAcmeAccount {
+    type Output = AcmeAccountInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state
+            .acme_accounts
+            .get(self.id)
+            .map(|account| account.info())
+            .ok_or(Error::IdNotFound {
+                id: self.id.to_string(),
+            })
+    }
+}
+
+pub struct AddAcmeAccount {
+    pub request: AcmeAccountRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for AddAcmeAccount {
+    type Output = AcmeAccountInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let account =
+            AcmeAccountEntry::new(state.generate_id(), self.request, state.certs.root_certs())
+                .await?;
+        add_account(state, account).await
+    }
+}
+
+pub struct ImportAcmeAccount {
+    pub request: AcmeAccountImport,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for ImportAcmeAccount {
+    type Output = AcmeAccountInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let credentials: AccountCredentials = serde_json::from_value(self.request.credentials)
+            .map_err(|_| Error::InvalidAcmeAccountCredentials)?;
+        let account =
+            AcmeAccountEntry::import(state.generate_id(), credentials, state.certs.root_certs())
+                .await?;
+        add_account(state, account).await
+    }
+}
+
+pub struct UpdateAcmeAccount {
+    pub id: ShortId,
+    pub request: AcmeAccountUpdate,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for UpdateAcmeAccount {
+    type Output = AcmeAccountInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let mut account = get_account(state, self.id)?;
+        account
+            .update_contacts(self.request.contacts, state.certs.root_certs())
+            .await?;
+        update_account(state, account).await
+    }
+}
+
+pub struct RolloverAcmeAccountKey {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for RolloverAcmeAccountKey {
+    type Output = AcmeAccountInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let mut account = get_account(state, self.id)?;
+        account.rollover_key(state.certs.root_certs()).await?;
+        update_account(state, account).await
+    }
+}
+
+pub struct DeactivateAcmeAccount {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for DeactivateAcmeAccount {
+    type Output = AcmeAccountInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let mut account = get_account(state, self.id)?;
+        account.deactivate(state.certs.root_certs()).await?;
+        update_account(state, account).await
+    }
+}
+
+pub struct DeleteAcmeAccount {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for DeleteAcmeAccount {
+    type Output = ();
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        if state
+            .acmes
+            .entries()
+            .any(|entry| entry.account_id == self.id)
+        {
+            return Err(Error::AcmeAccountInUse { id: self.id });
+        }
+        state.acme_accounts.delete(self.id)?;
+        state.storage.delete_acme_account(self.id).await;
+        state.update_acme_accounts();
+        Ok(())
+    }
+}
+
+fn get_account(state: &ServerState, id: ShortId) -> Result<AcmeAccountEntry, Error> {
+    state
+        .acme_accounts
+        .get(id)
+        .cloned()
+        .ok_or(Error::IdNotFound { id: id.to_string() })
+}
+
+async fn add_account(
+    state: &mut ServerState,
+    account: AcmeAccountEntry,
+) -> Result<AcmeAccountInfo, Error> {
+    state.acme_accounts.add(account.clone())?;
+    state.storage.save_acme_account(&account).await;
+    state.update_acme_accounts();
+    Ok(account.info())
+}
+
+async fn update_account(
+    state: &mut ServerState,
+    account: AcmeAccountEntry,
+) -> Result<AcmeAccountInfo, Error> {
+    state.acme_accounts.update(account.clone())?;
+    state.storage.save_acme_account(&account).await;
+    state.update_acme_accounts();
+    Ok(account.info())
+}
@@ -0,0 +1,199 @@
+use super::RpcMethod;
+use crate::{
+    certs::{ca::CaEntry, Cert},
+    server::state::ServerState,
+};
+use std::sync::Arc;
+use taxy_api::{
+    acme::RevocationReason,
+    ca::{CaImport, CaInfo, CaRequest, IssueCertRequest, IssuedCert},
+    cert::CertInfo,
+    error::Error,
+    id::ShortId,
+};
+
+pub struct GetCaList;
+
+#[async_trait::async_trait]
+impl RpcMethod for GetCaList {
+    type Output = Vec<CaInfo>;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(state.ca_infos())
+    }
+}
+
+pub struct GetCa {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetCa {
+    type Output = CaInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let (ca, cert) = get_ca(state, self.id)?;
+        Ok(ca.info(&cert))
+    }
+}
+
+pub struct CreateCa {
+    pub request: CaRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for CreateCa {
+    type Output = CaInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let (ca, cert) = CaEntry::generate(&self.request)?;
+        add_ca(state, ca, cert).await
+    }
+}
+
+pub struct ImportCa {
+    pub request: CaImport,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for ImportCa {
+    type Output = CaInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let (ca, cert) = CaEntry::import(&self.request)?;
+        add_ca(state, ca, cert).await
+    }
+}
+
+async fn add_ca(state: &mut ServerState, ca: CaEntry, cert: Cert) -> Result<CaInfo, Error> {
+    let info = ca.info(&cert);
+    state.cas.add(ca.clone())?;
+    state.storage.save_ca(&ca).await;
+    let cert = Arc::new(cert);
+    if state.certs.get(cert.id()).is_none() {
+        state.certs.add(cert.clone());
+        state.storage.save_cert(&cert).await;
+        state.update_certs().await;
+        state.reload_proxies().await;
+    }
+    state.update_cas();
+    Ok(info)
+}
+
+pub struct DeleteCa {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for DeleteCa {
+    type Output = ();
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.cas.delete(self.id)?;
+        state.storage.delete_ca(self.id).await;
+        state.update_cas();
+        Ok(())
+    }
+}
+
+pub struct IssueCert {
+    pub id: ShortId,
+    pub request: IssueCertRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for IssueCert {
+    type Output = CertInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let (_, ca_cert) = get_ca(state, self.id)?;
+        let ca = state.cas.get_mut(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })?;
+        let cert = Arc::new(ca.issue(&ca_cert, &self.request)?);
+        let ca = ca.clone();
+        state.storage.save_ca(&ca).await;
+        state.certs.add(cert.clone());
+        state.storage.save_cert(&cert).await;
+        state.update_certs().await;
+        state.update_cas();
+        state.reload_proxies().await;
+        Ok(cert.info())
+    }
+}
+
+pub struct GetIssuedCerts {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetIssuedCerts {
+    type Output = Vec<IssuedCert>;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let (ca, _) = get_ca(state, self.id)?;
+        Ok(ca.issued)
+    }
+}
+
+pub struct RevokeIssuedCert {
+    pub id: ShortId,
+    pub serial: String,
+    pub reason: RevocationReason,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for RevokeIssuedCert {
+    type Output = IssuedCert;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let ca = state.cas.get_mut(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })?;
+        let issued = ca.revoke(&self.serial, self.reason)?.clone();
+        let ca = ca.clone();
+        state.storage.save_ca(&ca).await;
+
+        // Stop serving the revoked certificate.
+        let stored = state
+            .certs
+            .get(issued.cert_id)
+            .is_some_and(|cert| cert.source.is_none());
+        if stored && state.certs.delete(issued.cert_id).is_ok() {
+            state.update_certs().await;
+            state.reload_proxies().await;
+            state.storage.delete_cert(issued.cert_id).await;
+        }
+        Ok(issued)
+    }
+}
+
+pub struct GetCrl {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetCrl {
+    type Output = Vec<u8>;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let (_, ca_cert) = get_ca(state, self.id)?;
+        let ca = state.cas.get_mut(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })?;
+        let (crl, updated) = ca.crl(&ca_cert)?;
+        if updated {
+            let ca = ca.clone();
+            state.storage.save_ca(&ca).await;
+            state.update_cas();
+        }
+        Ok(crl)
+    }
+}
+
+fn get_ca(state: &ServerState, id: ShortId) -> Result<(CaEntry, Arc<Cert>), Error> {
+    let not_found = || Error::IdNotFound { id: id.to_string() };
+    let ca = state.cas.get(id).ok_or_else(not_found)?;
+    let cert = state.certs.get(id).ok_or_else(not_found)?;
+    Ok((ca.clone(), cert.clone()))
+}
@@ -1,10 +1,18 @@
 use super::RpcMethod;
-use crate::{certs::Cert, server::state::ServerState};
+use crate::{
+    certs::{chain, csr::CsrEntry, Cert},
+    server::state::ServerState,
+};
 use flate2::{write::GzEncoder, Compression};
 use hyper::body::Bytes;
 use std::{sync::Arc, time::SystemTime};
 use tar::Header;
-use taxy_api::{cert::CertInfo, error::Error, id::ShortId};
+use taxy_api::{
+    cert::{CertInfo, CertUploadResult, CertUsageGraph, CsrInfo, CsrRequest},
+    error::Error,
+    id::ShortId,
+};
+use tracing::warn;
 
 pub struct GetCertList;
 
@@ -32,6 +40,17 @@ impl RpcMethod for GetCert {
     }
 }
 
+pub struct GetCertUsage;
+
+#[async_trait::async_trait]
+impl RpcMethod for GetCertUsage {
+    type Output = CertUsageGraph;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(state.cert_usage())
+    }
+}
+
 pub struct AddCert {
     pub cert: Arc<Cert>,
 }
@@ -49,6 +68,66 @@ impl RpcMethod for AddCert {
     }
 }
 
+/// Returns what is needed to complete certificate chains without blocking the server.
+///
+/// Fetching missing issuers can take a while, so callers complete the chain
+/// with [`chain::ChainResolver::complete`] before calling [`UploadCert`] or [`CompleteCsr`].
+pub struct GetChainResolver;
+
+#[async_trait::async_trait]
+impl RpcMethod for GetChainResolver {
+    type Output = chain::ChainResolver;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(chain::ChainResolver {
+            stored: state.certs.iter().cloned().collect(),
+            root_certs: state.certs.root_certs().clone(),
+        })
+    }
+}
+
+pub struct UploadCert {
+    pub cert: Cert,
+    pub strict: bool,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for UploadCert {
+    type Output = CertUploadResult;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let (cert, warnings) = complete_and_validate(state, self.cert).await?;
+        if self.strict && !warnings.is_empty() {
+            return Err(Error::InvalidCertChain {
+                reason: warnings.join(", "),
+            });
+        }
+        let cert = Arc::new(cert);
+        state.certs.add(cert.clone());
+        state.update_certs().await;
+        state.reload_proxies().await;
+        state.storage.save_cert(&cert).await;
+        Ok(CertUploadResult {
+            info: cert.info(),
+            warnings,
+        })
+    }
+}
+
+/// Completes the chain from the stored certificates only, and validates it.
+async fn complete_and_validate(
+    state: &ServerState,
+    cert: Cert,
+) -> Result<(Cert, Vec<String>), Error> {
+    let stored = state.certs.iter().cloned().collect::<Vec<_>>();
+    let cert = chain::complete_chain(cert, &stored, state.certs.root_certs(), false).await?;
+    let warnings = chain::validate_chain(&cert, state.certs.root_certs())?;
+    for warning in &warnings {
+        warn!(id = %cert.id(), "{warning}");
+    }
+    Ok((cert, warnings))
+}
+
 pub struct DeleteCert {
     pub id: ShortId,
 }
@@ -58,10 +137,106 @@ impl RpcMethod for DeleteCert {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        if let Some(cert) = state.certs.get(self.id) {
+            if cert.source.is_some() {
+                return Err(Error::ReadOnlyCert { id: self.id });
+            }
+        }
         state.certs.delete(self.id)?;
         state.update_certs().await;
         state.reload_proxies().await;
         state.storage.delete_cert(self.id).await;
+        if state.cas.delete(self.id).is_ok() {
+            state.storage.delete_ca(self.id).await;
+            state.update_cas();
+        }
+        Ok(())
+    }
+}
+
+pub struct GetCsrList;
+
+#[async_trait::async_trait]
+impl RpcMethod for GetCsrList {
+    type Output = Vec<CsrInfo>;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(state.csrs.entries().map(|csr| csr.info()).collect())
+    }
+}
+
+pub struct GetCsr {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetCsr {
+    type Output = CsrInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state
+            .csrs
+            .get(self.id)
+            .map(|csr| csr.info())
+            .ok_or(Error::IdNotFound {
+                id: self.id.to_string(),
+            })
+    }
+}
+
+pub struct CreateCsr {
+    pub request: CsrRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for CreateCsr {
+    type Output = CsrInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let csr = CsrEntry::new(state.generate_id(), self.request)?;
+        state.csrs.add(csr.clone())?;
+        state.storage.save_csr(&csr).await;
+        Ok(csr.info())
+    }
+}
+
+pub struct CompleteCsr {
+    pub id: ShortId,
+    pub chain: Vec<u8>,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for CompleteCsr {
+    type Output = CertInfo;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let csr = state.csrs.get(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })?;
+        let cert = csr.complete(&self.chain)?;
+        let (cert, _) = complete_and_validate(state, cert).await?;
+        let cert = Arc::new(cert);
+        state.csrs.delete(self.id)?;
+        state.storage.delete_csr(self.id).await;
+        state.certs.add(cert.clone());
+        state.storage.save_cert(&cert).await;
+        state.update_certs().await;
+        state.reload_proxies().await;
+        Ok(cert.info())
+    }
+}
+
+pub struct DeleteCsr {
+    pub id: ShortId,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for DeleteCsr {
+    type Output = ();
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.csrs.delete(self.id)?;
+        state.storage.delete_csr(self.id).await;
         Ok(())
     }
 }
@@ -85,6 +260,24 @@ impl RpcMethod for DownloadCert {
     }
 }
 
+pub struct ExportPkcs12 {
+    pub id: ShortId,
+    pub password: String,
+    pub legacy: bool,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for ExportPkcs12 {
+    type Output = Bytes;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let cert = state.certs.get(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })?;
+        Ok(cert.to_pkcs12(&self.password, self.legacy)?.into())
+    }
+}
+
 fn cert_to_tar_gz(cert: &Cert) -> anyhow::Result<Bytes> {
     let mut buf = Vec::<u8>::new();
 
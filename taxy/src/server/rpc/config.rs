@@ -1,7 +1,8 @@
 use super::RpcMethod;
-use crate::server::state::ServerState;
+use crate::server::{config_history, state::ServerState};
 use taxy_api::app::AppConfig;
 use taxy_api::error::Error;
+use taxy_api::history::{ConfigImportPlan, ConfigSnapshot};
 
 pub struct GetConfig;
 
@@ -26,3 +27,48 @@ impl RpcMethod for SetConfig {
         state.set_config(self.config).await
     }
 }
+
+pub struct ExportConfig;
+
+#[async_trait::async_trait]
+impl RpcMethod for ExportConfig {
+    type Output = ConfigSnapshot;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(state.snapshot())
+    }
+}
+
+pub struct ImportConfig {
+    pub snapshot: ConfigSnapshot,
+    pub dry_run: bool,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for ImportConfig {
+    type Output = ConfigImportPlan;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
+        let current = state.snapshot();
+        let mut desired = self.snapshot;
+
+        // Certificates are only referenced, so they must already exist and are never removed.
+        if let Some(id) = desired
+            .certs
+            .iter()
+            .find(|id| state.certs.get(**id).is_none())
+        {
+            return Err(Error::IdNotFound { id: id.to_string() });
+        }
+        desired.certs.clone_from(&current.certs);
+
+        state.validate_snapshot(&desired)?;
+        let changes = config_history::diff(&current, &desired);
+        let applied = !self.dry_run && desired != current;
+        if applied {
+            state.apply_snapshot(desired).await?;
+        }
+        Ok(ConfigImportPlan { changes, applied })
+    }
+}
@@ -0,0 +1,69 @@
+use super::RpcMethod;
+use crate::server::state::ServerState;
+use taxy_api::error::Error;
+use taxy_api::history::{ConfigChange, ConfigVersion, ConfigVersionInfo};
+
+pub struct GetConfigHistory;
+
+#[async_trait::async_trait]
+impl RpcMethod for GetConfigHistory {
+    type Output = Vec<ConfigVersionInfo>;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(state.history().list())
+    }
+}
+
+pub struct GetConfigVersion {
+    pub version: u64,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetConfigVersion {
+    type Output = ConfigVersion;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state
+            .history()
+            .get(self.version)
+            .cloned()
+            .ok_or(Error::IdNotFound {
+                id: self.version.to_string(),
+            })
+    }
+}
+
+pub struct GetConfigDiff {
+    pub version: u64,
+    pub from: Option<u64>,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for GetConfigDiff {
+    type Output = Vec<ConfigChange>;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.config_diff(self.version, self.from)
+    }
+}
+
+pub struct RollbackConfig {
+    pub version: u64,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for RollbackConfig {
+    type Output = ();
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
+        let snapshot = state
+            .history()
+            .get(self.version)
+            .map(|version| version.snapshot.clone())
+            .ok_or(Error::IdNotFound {
+                id: self.version.to_string(),
+            })?;
+        state.apply_snapshot(snapshot).await
+    }
+}
@@ -3,9 +3,12 @@ use std::any::Any;
 use taxy_api::error::Error;
 
 pub mod acme;
+pub mod acme_accounts;
 pub mod auth;
+pub mod ca;
 pub mod certs;
 pub mod config;
+pub mod history;
 pub mod ports;
 pub mod proxies;
 
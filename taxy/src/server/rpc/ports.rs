@@ -1,10 +1,13 @@
 use super::RpcMethod;
-use crate::proxy::PortContext;
+use crate::proxy::{PortContext, PortContextKind};
 use crate::server::state::ServerState;
+use crate::server::validation;
 use network_interface::NetworkInterfaceConfig;
 use taxy_api::error::Error;
 use taxy_api::id::ShortId;
 use taxy_api::port::{NetworkAddr, NetworkInterface, Port, PortEntry, PortStatus};
+use taxy_api::route_test::{RouteTestRequest, RouteTestResult};
+use taxy_api::validation::{PortValidationRequest, ValidationResult};
 
 pub struct GetPortList;
 
@@ -64,6 +67,7 @@ impl RpcMethod for DeletePort {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         if state.ports.delete(self.id) {
             state.update_ports().await;
             state.reload_proxies().await;
@@ -85,6 +89,7 @@ impl RpcMethod for AddPort {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         let entry: PortEntry = (state.generate_id(), self.entry).into();
         if state.ports.get(entry.id).is_some() {
             Err(Error::IdAlreadyExists { id: entry.id })
@@ -104,6 +109,7 @@ impl RpcMethod for UpdatePort {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         if state.ports.get(self.entry.id).is_some() {
             state.update_port(PortContext::new(self.entry)?).await;
             Ok(())
@@ -115,6 +121,39 @@ impl RpcMethod for UpdatePort {
     }
 }
 
+pub struct ValidatePort {
+    pub request: PortValidationRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for ValidatePort {
+    type Output = ValidationResult;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(validation::validate_port(state, self.request).await)
+    }
+}
+
+pub struct TestRoute {
+    pub id: ShortId,
+    pub request: RouteTestRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for TestRoute {
+    type Output = RouteTestResult;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        let port = state.ports.get(self.id).ok_or(Error::IdNotFound {
+            id: self.id.to_string(),
+        })?;
+        match port.kind() {
+            PortContextKind::Http(ctx) => ctx.test_route(self.request),
+            _ => Err(Error::NotHttpPort { id: self.id }),
+        }
+    }
+}
+
 pub struct ResetPort {
     pub id: ShortId,
 }
@@ -1,8 +1,10 @@
 use super::RpcMethod;
 use crate::server::state::ServerState;
+use crate::server::validation;
 use taxy_api::error::Error;
 use taxy_api::id::ShortId;
 use taxy_api::proxy::{Proxy, ProxyEntry, ProxyStatus};
+use taxy_api::validation::{ProxyValidationRequest, ValidationResult};
 
 pub struct GetProxyList;
 
@@ -46,7 +48,7 @@ impl RpcMethod for GetProxyStatus {
         state
             .proxies
             .get(self.id)
-            .map(|ctx| ctx.status)
+            .map(|ctx| ctx.status.clone())
             .ok_or(Error::IdNotFound {
                 id: self.id.to_string(),
             })
@@ -62,6 +64,7 @@ impl RpcMethod for DeleteProxy {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         state.proxies.delete(self.id)?;
         state.update_proxies().await;
         state.reload_proxies().await;
@@ -78,6 +81,7 @@ impl RpcMethod for AddProxy {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         if state.proxies.set((state.generate_id(), self.entry).into()) {
             state.update_proxies().await;
             state.reload_proxies().await;
@@ -95,6 +99,7 @@ impl RpcMethod for UpdateProxy {
     type Output = ();
 
     async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        state.check_writable()?;
         if state.proxies.set(self.entry) {
             state.update_proxies().await;
             state.reload_proxies().await;
@@ -102,3 +107,16 @@ impl RpcMethod for UpdateProxy {
         Ok(())
     }
 }
+
+pub struct ValidateProxy {
+    pub request: ProxyValidationRequest,
+}
+
+#[async_trait::async_trait]
+impl RpcMethod for ValidateProxy {
+    type Output = ValidationResult;
+
+    async fn call(self, state: &mut ServerState) -> Result<Self::Output, Error> {
+        Ok(validation::validate_proxy(state, self.request))
+    }
+}
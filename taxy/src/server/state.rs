@@ -1,37 +1,58 @@
+use super::acme_account_list::AcmeAccountList;
 use super::acme_list::AcmeList;
+use super::ca_list::CaList;
+use super::cert_alert::{self, CertAlertList};
 use super::cert_list::CertList;
+use super::cert_watcher::CertWatcher;
+use super::config_file_watcher::ConfigFileWatcher;
+use super::config_history::{self, ConfigHistory};
+use super::csr_list::CsrList;
+use super::on_demand::{self, OnDemandList};
 use super::proxy_list::ProxyList;
+use super::validation;
 use super::{listener::TcpListenerPool, port_list::PortList, rpc::RpcCallback};
-use crate::certs::acme::AcmeOrder;
+use crate::certs::acme::{
+    move_url_credentials, AcmeAccountEntry, AcmeEntry, AcmeOrder, Directory, RenewalInfo,
+    DEFAULT_RETRY_AFTER,
+};
+use crate::certs::Cert;
 use crate::config::storage::Storage;
 use crate::log::DatabaseLayer;
 use crate::{
-    command::ServerCommand,
-    proxy::{PortContext, PortContextKind},
+    command::{AcmeStatusUpdate, ServerCommand},
+    proxy::{
+        http::route,
+        tls::{acme_challenge_acceptor, OnDemandRequester},
+        PortContext, PortContextKind,
+    },
 };
 use hyper::server::conn::Http;
 use hyper::{service::service_fn, Body};
 use rand::seq::SliceRandom;
 use std::collections::HashSet;
 use std::convert::Infallible;
-use std::str;
-use std::{
-    collections::HashMap,
-    sync::Arc,
-    time::{Duration, SystemTime},
-};
+use std::path::PathBuf;
+use std::str::{self, FromStr};
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+use taxy_api::acme::{Acme, AcmeConfig, AcmeOrderStatus, AcmeStatus};
 use taxy_api::app::{AppConfig, AppInfo};
+use taxy_api::ca::CaInfo;
+use taxy_api::cert::{CertKind, CertUsage, CertUsageEntry, CertUsageGraph};
 use taxy_api::error::Error;
 use taxy_api::event::ServerEvent;
+use taxy_api::history::{AcmeSnapshot, ConfigChange, ConfigSnapshot};
 use taxy_api::id::ShortId;
-use taxy_api::proxy::ProxyEntry;
+use taxy_api::proxy::{ProxyEntry, ProxyKind};
+use taxy_api::subject_name::SubjectName;
+use taxy_api::validation::ProxyValidationRequest;
 use tokio::io::AsyncBufReadExt;
 use tokio::{
     io::BufStream,
     net::TcpStream,
     sync::{broadcast, mpsc},
 };
-use tracing::{error, info, span, Instrument, Level};
+use tokio_rustls::{rustls::RootCertStore, TlsAcceptor};
+use tracing::{debug, error, info, span, warn, Instrument, Level};
 use warp::http::Response;
 use x509_parser::time::ASN1Time;
 
@@ -39,11 +60,22 @@ pub struct ServerState {
     pub proxies: ProxyList,
     pub certs: CertList,
     pub acmes: AcmeList,
+    pub acme_accounts: AcmeAccountList,
+    pub cas: CaList,
+    pub csrs: CsrList,
     pub ports: PortList,
     pub storage: Box<dyn Storage>,
     config: AppConfig,
     pool: TcpListenerPool,
     http_challenges: HashMap<String, String>,
+    tls_alpn_challenge_acceptor: Option<TlsAcceptor>,
+    acme_running: bool,
+    acme_pending: bool,
+    on_demand: OnDemandList,
+    cert_alerts: CertAlertList,
+    cert_watcher: CertWatcher,
+    history: ConfigHistory,
+    config_file: Option<(PathBuf, ConfigFileWatcher)>,
     command_sender: mpsc::Sender<ServerCommand>,
     br_sender: broadcast::Sender<ServerEvent>,
     callback_sender: mpsc::Sender<RpcCallback>,
@@ -53,6 +85,7 @@ pub struct ServerState {
 impl ServerState {
     pub async fn new(
         storage: impl Storage,
+        config_file: Option<PathBuf>,
         command_sender: mpsc::Sender<ServerCommand>,
         callback_sender: mpsc::Sender<RpcCallback>,
         br_sender: broadcast::Sender<ServerEvent>,
@@ -64,7 +97,11 @@ impl ServerState {
 
         let certs = storage.load_certs().await;
         let acmes = storage.load_acmes().await;
+        let acme_accounts = storage.load_acme_accounts().await;
+        let cas = storage.load_cas().await;
+        let csrs = storage.load_csrs().await;
         let proxies = storage.load_proxies().await;
+        let history = storage.load_config_history().await;
 
         let mut ports = PortList::default();
         for entry in storage.load_ports().await {
@@ -82,11 +119,25 @@ impl ServerState {
             proxies: proxies.into_iter().collect(),
             certs: CertList::new(certs).await,
             acmes: acmes.into_iter().collect(),
+            acme_accounts: acme_accounts.into_iter().collect(),
+            cas: cas.into_iter().collect(),
+            csrs: csrs.into_iter().collect(),
             ports,
             storage: Box::new(storage),
             config,
             pool: TcpListenerPool::new(),
             http_challenges: HashMap::new(),
+            tls_alpn_challenge_acceptor: None,
+            acme_running: false,
+            acme_pending: false,
+            on_demand: OnDemandList::default(),
+            cert_alerts: CertAlertList::default(),
+            cert_watcher: CertWatcher::default(),
+            history: history.into_iter().collect(),
+            config_file: config_file.map(|path| {
+                let watcher = ConfigFileWatcher::new(path.clone(), command_sender.clone());
+                (path, watcher)
+            }),
             command_sender,
             br_sender,
             callback_sender,
@@ -96,8 +147,15 @@ impl ServerState {
         this.update_ports().await;
         this.update_certs().await;
         this.update_proxies().await;
+        this.update_acme_accounts();
+        this.update_cas();
         this.update_acmes().await;
+        this.update_on_demand();
+        this.update_cert_watch().await;
         this.reload_proxies().await;
+
+        // Record changes made while the server was stopped, such as manual edits.
+        this.record_changes().await;
         this
     }
 
@@ -105,22 +163,102 @@ impl ServerState {
         match cmd {
             ServerCommand::AddCert { cert } => {
                 self.certs.add(cert.clone());
+                if let Some(meta) = &cert.metadata {
+                    self.acmes.record_success(meta.acme_id);
+                    self.update_acme_status(meta.acme_id);
+                }
                 self.update_certs().await;
                 self.reload_proxies().await;
                 self.storage.save_cert(&cert).await;
+                self.record_changes().await;
+            }
+            ServerCommand::SetExternalCerts { certs } => {
+                if self.certs.set_external(certs) {
+                    self.update_certs().await;
+                    self.reload_proxies().await;
+                }
+            }
+            ServerCommand::ApplyConfigFile { snapshot } => {
+                self.apply_config_file(*snapshot).await;
             }
             ServerCommand::SetBroadcastEvents { enabled } => {
                 self.broadcast_events = enabled;
             }
-            ServerCommand::SetHttpChallenges { orders } => {
+            ServerCommand::SetAcmeChallenges { orders } => {
                 if orders.is_empty() {
-                    self.stop_http_challenges().await;
+                    self.stop_acme_challenges().await;
+                } else {
+                    self.continue_acme_challenges(orders).await;
+                }
+            }
+            ServerCommand::StartAcmeChallenges => {
+                self.start_acme_challenges().await;
+            }
+            ServerCommand::RequestOnDemandCert { name } => {
+                self.request_on_demand_cert(name);
+            }
+            ServerCommand::AddOnDemandAcme { name, allowed } => {
+                self.on_demand.finish(&name, allowed);
+                if allowed {
+                    if let Err(err) = self.add_on_demand_acme(&name).await {
+                        error!(?err, name, "failed to add on-demand acme entry");
+                    }
+                    self.record_changes().await;
                 } else {
-                    self.continue_http_challenges(orders).await;
+                    info!(name, "on-demand certificate request denied");
+                }
+            }
+            ServerCommand::UpdateAcmeStatus { id, update } => {
+                match update {
+                    AcmeStatusUpdate::Requesting => {
+                        self.acmes.set_order_status(id, AcmeOrderStatus::Requesting);
+                    }
+                    AcmeStatusUpdate::RenewalSuggested {
+                        cert,
+                        time,
+                        next_check_at,
+                    } => {
+                        self.acmes
+                            .set_suggested_renewal(id, cert, time, next_check_at);
+                    }
+                    AcmeStatusUpdate::Failed { .. }
+                        if self.remove_failed_on_demand_acme(id).await =>
+                    {
+                        self.record_changes().await;
+                    }
+                    AcmeStatusUpdate::Failed { error } => {
+                        let delay = self.acmes.record_failure(id, error);
+                        info!(
+                            resource_id = id.to_string(),
+                            ?delay,
+                            "retrying acme request"
+                        );
+                        let command = self.command_sender.clone();
+                        tokio::task::spawn(async move {
+                            tokio::time::sleep(delay).await;
+                            let _ = command.send(ServerCommand::StartAcmeChallenges).await;
+                        });
+                    }
                 }
+                self.update_acme_status(id);
+            }
+            ServerCommand::SetAcmeDirectory {
+                account_id,
+                directory,
+            } => {
+                self.acme_accounts.set_directory(account_id, directory);
             }
-            ServerCommand::CallMethod { id, mut arg } => {
+            ServerCommand::CallMethod {
+                id,
+                author,
+                mut arg,
+            } => {
+                let before = self.snapshot();
                 let result = arg.call(self).await;
+                let snapshot = self.snapshot();
+                if snapshot != before {
+                    self.record_version(author, snapshot).await;
+                }
                 let _ = self.callback_sender.send(RpcCallback { id, result }).await;
             }
         }
@@ -166,8 +304,15 @@ impl ServerState {
                 PortContextKind::Http(http) => {
                     http.start_proxy(stream);
                 }
-                PortContextKind::Reserved => (),
+                PortContextKind::Reserved(_) => (),
             }
+        } else if let Some(acceptor) = &self.tls_alpn_challenge_acceptor {
+            let acceptor = acceptor.clone();
+            tokio::task::spawn(async move {
+                if let Err(err) = acceptor.accept(stream).await {
+                    debug!(?err, "tls-alpn-01 challenge handshake failed");
+                }
+            });
         }
     }
 
@@ -201,7 +346,7 @@ impl ServerState {
             for ctx in self.proxies.contexts() {
                 let _ = self.br_sender.send(ServerEvent::ProxyStatusUpdated {
                     id: ctx.entry.id,
-                    status: ctx.status,
+                    status: ctx.status.clone(),
                 });
             }
         }
@@ -217,7 +362,120 @@ impl ServerState {
         let _ = self.br_sender.send(ServerEvent::AcmeUpdated {
             entries: self.acmes.entries().map(|acme| acme.info()).collect(),
         });
-        self.start_http_challenges().await;
+        self.start_acme_challenges().await;
+    }
+
+    pub fn update_acme_accounts(&self) {
+        let _ = self.br_sender.send(ServerEvent::AcmeAccountsUpdated {
+            entries: self
+                .acme_accounts
+                .entries()
+                .map(|account| account.info())
+                .collect(),
+        });
+    }
+
+    pub fn update_cas(&self) {
+        let _ = self.br_sender.send(ServerEvent::CaUpdated {
+            entries: self.ca_infos(),
+        });
+    }
+
+    pub fn ca_infos(&self) -> Vec<CaInfo> {
+        self.cas
+            .entries()
+            .filter_map(|ca| Some(ca.info(self.certs.get(ca.id)?)))
+            .collect()
+    }
+
+    /// Returns the server names served by active TLS ports and the vhosts of HTTP proxies bound to them.
+    pub fn tls_server_names(&self) -> Vec<CertUsage> {
+        let tls_ports = self
+            .ports
+            .entries()
+            .filter(|entry| entry.port.active)
+            .filter_map(|entry| {
+                let tls = entry.port.opts.tls_termination.as_ref()?;
+                Some((entry.id, tls))
+            })
+            .collect::<Vec<_>>();
+
+        let mut names = Vec::new();
+        for &(port, tls) in &tls_ports {
+            for name in &tls.server_names {
+                if let Ok(name) = SubjectName::from_str(name) {
+                    names.push(CertUsage {
+                        name,
+                        port,
+                        proxy: None,
+                    });
+                }
+            }
+        }
+        for entry in self.proxies.entries().filter(|entry| entry.proxy.active) {
+            if let ProxyKind::Http(http) = &entry.proxy.kind {
+                let ports = entry
+                    .proxy
+                    .ports
+                    .iter()
+                    .filter(|&&id| tls_ports.iter().any(|(port, _)| *port == id));
+                for port in ports {
+                    for name in &http.vhosts {
+                        names.push(CertUsage {
+                            name: name.clone(),
+                            port: *port,
+                            proxy: Some(entry.id),
+                        });
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    pub fn cert_usage(&self) -> CertUsageGraph {
+        let mut graph = CertUsageGraph {
+            certs: self
+                .certs
+                .iter()
+                .filter(|cert| cert.kind == CertKind::Server)
+                .map(|cert| CertUsageEntry {
+                    id: cert.id(),
+                    used_by: Vec::new(),
+                })
+                .collect(),
+            uncovered: Vec::new(),
+        };
+        for usage in self.tls_server_names() {
+            let cert = self
+                .certs
+                .find_server_cert(&usage.name)
+                .map(|cert| cert.id());
+            match graph.certs.iter_mut().find(|entry| Some(entry.id) == cert) {
+                Some(entry) => entry.used_by.push(usage),
+                None => graph.uncovered.push(usage),
+            }
+        }
+        graph
+    }
+
+    pub fn acme_status(&self, id: ShortId) -> Option<AcmeStatus> {
+        let cert = self
+            .certs
+            .find_certs_by_acme(id)
+            .into_iter()
+            .max_by_key(|cert| cert.not_after.timestamp());
+        self.acmes.status(id, cert.map(|cert| cert.as_ref()))
+    }
+
+    pub fn update_acme_status(&self, id: ShortId) {
+        if self.broadcast_events {
+            if let Some(status) = self.acme_status(id) {
+                let _ = self
+                    .br_sender
+                    .send(ServerEvent::AcmeStatusUpdated { id, status });
+            }
+        }
     }
 
     pub async fn update_port(&mut self, ctx: PortContext) {
@@ -244,6 +502,7 @@ impl ServerState {
     }
 
     pub async fn reload_proxies(&mut self) {
+        let mut conflicts = Vec::new();
         for ctx in self.ports.as_mut_slice() {
             let proxies = self
                 .proxies
@@ -252,7 +511,10 @@ impl ServerState {
                     entry.proxy.active && entry.proxy.ports.contains(&ctx.entry.id)
                 })
                 .cloned()
-                .collect();
+                .collect::<Vec<_>>();
+            if ctx.entry.port.listen.is_http() {
+                conflicts.extend(route::find_conflicts(ctx.entry.id, &proxies));
+            }
             let span = span!(Level::INFO, "port", resource_id = ctx.entry.id.to_string());
             if let Err(err) = ctx
                 .setup(&self.certs, proxies)
@@ -264,6 +526,21 @@ impl ServerState {
                 });
             }
         }
+
+        for id in self.proxies.set_conflicts(conflicts) {
+            let Some(ctx) = self.proxies.get(id) else {
+                continue;
+            };
+            for conflict in &ctx.status.conflicts {
+                warn!(proxy = %id, "{conflict}");
+            }
+            if self.broadcast_events {
+                let _ = self.br_sender.send(ServerEvent::ProxyStatusUpdated {
+                    id,
+                    status: ctx.status.clone(),
+                });
+            }
+        }
     }
 
     pub async fn run_background_tasks(&mut self, app_info: &AppInfo) {
@@ -271,12 +548,16 @@ impl ServerState {
             error!(?err, "failed to cleanup old logs");
         }
 
-        self.start_http_challenges().await;
+        self.start_acme_challenges().await;
         self.reload_proxies().await;
-        self.remove_expired_certs();
+        let removed = self.remove_expired_certs();
+        self.check_cert_expiry();
+        if removed {
+            self.record_changes().await;
+        }
     }
 
-    fn remove_expired_certs(&mut self) {
+    fn remove_expired_certs(&mut self) -> bool {
         let mut removing_items = Vec::new();
         for acme in self.acmes.entries() {
             let certs = self.certs.find_certs_by_acme(acme.id);
@@ -300,6 +581,26 @@ impl ServerState {
                 entries: self.certs.iter().map(|item| item.info()).collect(),
             });
         }
+        !removing_items.is_empty()
+    }
+
+    fn check_cert_expiry(&mut self) {
+        let config = &self.config.cert_expiry_alert;
+        let alerts =
+            cert_alert::find_alerts(&self.certs, &self.tls_server_names(), config.threshold);
+        for alert in self.cert_alerts.filter(alerts, config.repeat_interval) {
+            warn!(id = %alert.id, status = %alert.status, "{}", cert_alert::message(&alert));
+            if let Some(url) = &config.webhook_url {
+                tokio::spawn(cert_alert::send_webhook(
+                    url.clone(),
+                    alert.clone(),
+                    self.certs.root_certs().clone(),
+                ));
+            }
+            let _ = self
+                .br_sender
+                .send(ServerEvent::CertExpiryAlerted { alert });
+        }
     }
 
     async fn cleanup_old_logs(&mut self, app_info: &AppInfo) -> anyhow::Result<()> {
@@ -311,38 +612,123 @@ impl ServerState {
         Ok(())
     }
 
-    async fn start_http_challenges(&mut self) {
-        let entries = self.acmes.entries().cloned();
-        let entries = entries
+    pub async fn start_acme_challenges(&mut self) {
+        if self.acme_running {
+            self.acme_pending = true;
+            return;
+        }
+
+        let now = SystemTime::now();
+        let jobs = self
+            .acmes
+            .entries()
             .filter(|entry| {
-                entry.acme.config.active
-                    && self
-                        .certs
-                        .find_certs_by_acme(entry.id)
-                        .iter()
-                        .map(|cert| {
-                            cert.metadata
-                                .as_ref()
-                                .map(|meta| meta.created_at)
-                                .unwrap_or(SystemTime::UNIX_EPOCH)
-                        })
-                        .max()
-                        .unwrap_or(SystemTime::UNIX_EPOCH)
-                        .elapsed()
-                        .unwrap_or_default()
-                        > Duration::from_secs(60 * 60 * 24 * entry.acme.config.renewal_days)
+                self.acmes.is_renewal_requested(entry.id)
+                    || (entry.acme.config.active && !self.acmes.is_retry_pending(entry.id))
+            })
+            .filter_map(|entry| {
+                let account = self
+                    .acme_accounts
+                    .get(entry.account_id)
+                    .filter(|account| account.is_valid())?;
+                let cert = self
+                    .certs
+                    .find_certs_by_acme(entry.id)
+                    .into_iter()
+                    .max_by_key(|cert| cert.not_after.timestamp())
+                    .cloned();
+                let forced = self.acmes.is_renewal_requested(entry.id);
+                let check_renewal_info = cert
+                    .as_ref()
+                    .is_some_and(|cert| self.acmes.is_renewal_check_due(entry.id, cert.id()));
+                let suggested = self.acmes.suggested_renewal(entry.id);
+                let due = forced
+                    || check_renewal_info
+                    || cert.as_ref().is_none_or(|cert| {
+                        entry.needs_renewal(cert) || suggested.is_some_and(|time| time <= now)
+                    });
+                due.then(|| AcmeJob {
+                    entry: entry.clone(),
+                    account: account.clone(),
+                    directory: self.acme_accounts.directory(entry.account_id),
+                    cert,
+                    forced,
+                    check_renewal_info,
+                    suggested,
+                })
             })
             .collect::<Vec<_>>();
 
-        if entries.is_empty() {
+        if jobs.is_empty() {
             return;
         }
+        for job in &jobs {
+            self.acmes.clear_renewal_request(job.entry.id);
+        }
 
+        self.acme_running = true;
+        let root_certs = self.certs.root_certs().clone();
         let command = self.command_sender.clone();
         tokio::task::spawn(async move {
             let mut orders = Vec::new();
-            for entry in entries {
+            let mut directories = HashMap::new();
+            for job in jobs {
+                let AcmeJob {
+                    entry,
+                    account,
+                    directory,
+                    cert,
+                    forced,
+                    check_renewal_info,
+                    suggested,
+                } = job;
                 let span = span!(Level::INFO, "acme", resource_id = entry.id.to_string());
+                if let Some(cert) = cert.filter(|_| !forced) {
+                    let suggested = if check_renewal_info {
+                        // Entries sharing an account fetch its directory only once.
+                        let directory = match directory
+                            .or_else(|| directories.get(&account.id).cloned())
+                        {
+                            Some(directory) => Some(directory),
+                            None => {
+                                let directory = fetch_directory(&account, &root_certs, &command)
+                                    .instrument(span.clone())
+                                    .await;
+                                if let Some(directory) = &directory {
+                                    directories.insert(account.id, directory.clone());
+                                }
+                                directory
+                            }
+                        };
+                        let info = match &directory {
+                            Some(directory) => {
+                                renewal_info(&entry, directory, &cert, &root_certs)
+                                    .instrument(span.clone())
+                                    .await
+                            }
+                            None => None,
+                        };
+                        let time = info.map(|info| info.window.select(&cert));
+                        let retry_after = info.map_or(DEFAULT_RETRY_AFTER, |info| info.retry_after);
+                        let _ = command
+                            .send(ServerCommand::UpdateAcmeStatus {
+                                id: entry.id,
+                                update: AcmeStatusUpdate::RenewalSuggested {
+                                    cert: cert.id(),
+                                    time,
+                                    next_check_at: SystemTime::now() + retry_after,
+                                },
+                            })
+                            .await;
+                        time
+                    } else {
+                        suggested
+                    };
+                    let suggested = suggested.is_some_and(|time| time <= SystemTime::now());
+                    if !entry.needs_renewal(&cert) && !suggested {
+                        continue;
+                    }
+                }
                 span.in_scope(|| {
                     info!(
                         provider = entry.acme.config.provider,
@@ -350,36 +736,88 @@ impl ServerState {
                         "starting acme request"
                     );
                 });
-                match entry.request().instrument(span.clone()).await {
+                let _ = command
+                    .send(ServerCommand::UpdateAcmeStatus {
+                        id: entry.id,
+                        update: AcmeStatusUpdate::Requesting,
+                    })
+                    .await;
+                match entry
+                    .request(&account, &root_certs)
+                    .instrument(span.clone())
+                    .await
+                {
                     Ok(request) => orders.push(request),
                     Err(err) => {
-                        let _enter = span.enter();
-                        error!("failed to request challenge: {}", err)
+                        span.in_scope(|| {
+                            error!("failed to request challenge: {}", err);
+                        });
+                        let _ = command
+                            .send(ServerCommand::UpdateAcmeStatus {
+                                id: entry.id,
+                                update: AcmeStatusUpdate::Failed {
+                                    error: err.to_string(),
+                                },
+                            })
+                            .await;
                     }
                 }
             }
             let _ = command
-                .send(ServerCommand::SetHttpChallenges { orders })
+                .send(ServerCommand::SetAcmeChallenges { orders })
                 .await;
         });
     }
 
-    async fn stop_http_challenges(&mut self) {
-        self.http_challenges.clear();
-        self.pool.set_http_challenge_addr(None);
-        self.pool.update(self.ports.as_mut_slice()).await;
+    async fn stop_acme_challenges(&mut self) {
+        let active = !self.http_challenges.is_empty() || self.tls_alpn_challenge_acceptor.is_some();
+        if active {
+            self.http_challenges.clear();
+            self.tls_alpn_challenge_acceptor = None;
+            self.certs.set_acme_challenges(Vec::new());
+            self.pool.set_reserved_addrs(Vec::new());
+            self.pool.update(self.ports.as_mut_slice()).await;
+            self.reload_proxies().await;
+        }
+
+        self.acme_running = false;
+        if self.acme_pending {
+            self.acme_pending = false;
+            self.start_acme_challenges().await;
+        }
     }
 
-    async fn continue_http_challenges(&mut self, orders: Vec<AcmeOrder>) {
-        let challenges = orders
+    async fn continue_acme_challenges(&mut self, orders: Vec<AcmeOrder>) {
+        let http_challenges = orders
             .iter()
             .flat_map(|req| req.http_challenges.clone())
-            .collect();
+            .collect::<HashMap<_, _>>();
+        let tls_alpn_challenges = orders
+            .iter()
+            .flat_map(|req| req.tls_alpn_challenges.clone())
+            .collect::<Vec<_>>();
+
+        let mut reserved_addrs = Vec::new();
+        if !http_challenges.is_empty() {
+            reserved_addrs.push(self.config.http_challenge_addr);
+        }
+        if !tls_alpn_challenges.is_empty() {
+            reserved_addrs.push(self.config.tls_alpn_challenge_addr);
+            self.tls_alpn_challenge_acceptor =
+                Some(acme_challenge_acceptor(tls_alpn_challenges.clone()));
+        }
 
-        self.http_challenges = challenges;
-        self.pool
-            .set_http_challenge_addr(Some(self.config.http_challenge_addr));
+        for order in &orders {
+            self.acmes
+                .set_order_status(order.id, AcmeOrderStatus::Validating);
+            self.update_acme_status(order.id);
+        }
+
+        self.http_challenges = http_challenges;
+        self.certs.set_acme_challenges(tls_alpn_challenges);
+        self.pool.set_reserved_addrs(reserved_addrs);
         self.pool.update(self.ports.as_mut_slice()).await;
+        self.reload_proxies().await;
 
         let command = self.command_sender.clone();
         tokio::task::spawn(async move {
@@ -397,56 +835,477 @@ impl ServerState {
                             .await;
                     }
                     Err(err) => {
-                        let _enter = span.enter();
-                        error!(?err, "failed to start challenge");
+                        span.in_scope(|| {
+                            error!(?err, "failed to start challenge");
+                        });
+                        let _ = command
+                            .send(ServerCommand::UpdateAcmeStatus {
+                                id: order.id,
+                                update: AcmeStatusUpdate::Failed {
+                                    error: err.to_string(),
+                                },
+                            })
+                            .await;
                     }
                 }
             }
             let _ = command
-                .send(ServerCommand::SetHttpChallenges { orders: vec![] })
+                .send(ServerCommand::SetAcmeChallenges { orders: vec![] })
                 .await;
         });
     }
 
+    fn update_on_demand(&mut self) {
+        let on_demand = self
+            .config
+            .on_demand_tls
+            .as_ref()
+            .map(|_| OnDemandRequester::new(self.command_sender.clone()));
+        self.certs.set_on_demand(on_demand);
+    }
+
+    async fn update_cert_watch(&mut self) {
+        self.cert_watcher
+            .update(self.config.cert_watch.as_ref(), self.command_sender.clone());
+        if self.config.cert_watch.is_none() && self.certs.set_external(vec![]) {
+            self.update_certs().await;
+            self.reload_proxies().await;
+        }
+    }
+
+    fn request_on_demand_cert(&mut self, name: String) {
+        let Some(config) = self.config.on_demand_tls.clone() else {
+            return;
+        };
+        let exists = self
+            .acmes
+            .entries()
+            .any(|entry| entry.acme.identifiers.iter().any(|id| id.test(&name)));
+        if exists || !self.on_demand.start(&name) {
+            return;
+        }
+
+        let root_certs = self.certs.root_certs().clone();
+        let command = self.command_sender.clone();
+        tokio::task::spawn(async move {
+            let allowed =
+                config.is_allowed_name(&name) || on_demand::ask(&config, &name, &root_certs).await;
+            let _ = command
+                .send(ServerCommand::AddOnDemandAcme { name, allowed })
+                .await;
+        });
+    }
+
+    async fn add_on_demand_acme(&mut self, name: &str) -> Result<(), Error> {
+        let Some(config) = &self.config.on_demand_tls else {
+            return Ok(());
+        };
+        self.acme_accounts
+            .get(config.account_id)
+            .ok_or(Error::IdNotFound {
+                id: config.account_id.to_string(),
+            })?
+            .ensure_valid()?;
+
+        let acme = Acme {
+            config: AcmeConfig {
+                provider: on_demand::ON_DEMAND_PROVIDER.to_string(),
+                ..Default::default()
+            },
+            identifiers: vec![SubjectName::DnsName(name.to_string())],
+            challenge_type: config.challenge_type.clone(),
+            dns_challenge: None,
+        };
+        let entry = AcmeEntry::new(self.generate_id(), acme, config.account_id)?;
+        info!(
+            resource_id = entry.id.to_string(),
+            name, "requesting on-demand certificate"
+        );
+        self.acmes.add(entry.clone())?;
+        self.storage.save_acme(&entry).await;
+        self.update_acmes().await;
+        Ok(())
+    }
+
+    /// Removes an on-demand entry whose first order has failed.
+    ///
+    /// Requests for its names are ignored for a while, and then start over.
+    async fn remove_failed_on_demand_acme(&mut self, id: ShortId) -> bool {
+        let Some(entry) = self.acmes.get(id) else {
+            return false;
+        };
+        if entry.acme.config.provider != on_demand::ON_DEMAND_PROVIDER
+            || !self.certs.find_certs_by_acme(id).is_empty()
+        {
+            return false;
+        }
+        for name in &entry.acme.identifiers {
+            self.on_demand.deny(&name.to_string());
+        }
+        info!(
+            resource_id = id.to_string(),
+            "removing failed on-demand acme entry"
+        );
+        let _ = self.acmes.delete(id);
+        self.storage.delete_acme(id).await;
+        self.update_acmes().await;
+        true
+    }
+
     pub fn config(&self) -> &AppConfig {
         &self.config
     }
 
     pub async fn set_config(&mut self, config: AppConfig) -> Result<(), Error> {
+        let reload = self.config.on_demand_tls.is_some() != config.on_demand_tls.is_some();
         self.config.clone_from(&config);
+        self.storage.save_app_config(&config).await;
+        if reload {
+            self.update_on_demand();
+            self.reload_proxies().await;
+        }
+        self.update_cert_watch().await;
         let _ = self
             .br_sender
             .send(ServerEvent::AppConfigUpdated { config });
         Ok(())
     }
 
-    pub fn generate_id(&self) -> ShortId {
-        const TABLE: &[u8] = b"bcdfghjklmnpqrstvwxyz";
+    /// Fails if ports, proxies and ACME entries are managed by a config file.
+    pub fn check_writable(&self) -> Result<(), Error> {
+        match &self.config_file {
+            Some((path, _)) => Err(Error::ManagedByConfigFile {
+                path: path.display().to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Replaces the ports, proxies and ACME entries with those of a reloaded config file.
+    async fn apply_config_file(&mut self, file: ConfigSnapshot) {
+        let current = self.snapshot();
+        let mut snapshot = current.clone();
+        snapshot.ports = file.ports;
+        snapshot.proxies = file.proxies;
+
+        // Keep the entries added by on-demand TLS, which are never in the file.
+        let on_demand = current
+            .acme
+            .iter()
+            .filter(|entry| {
+                entry.acme.config.provider == on_demand::ON_DEMAND_PROVIDER
+                    && !file.acme.iter().any(|other| other.id == entry.id)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        snapshot.acme = file.acme;
+        snapshot.acme.extend(on_demand);
 
+        if snapshot == current {
+            return;
+        }
+        if let Err(err) = self.apply_snapshot(snapshot).await {
+            error!(?err, "config file not reloaded");
+            return;
+        }
+        info!("config file reloaded");
+        let snapshot = self.snapshot();
+        self.record_version(None, snapshot).await;
+    }
+
+    pub fn history(&self) -> &ConfigHistory {
+        &self.history
+    }
+
+    /// Captures the configuration that can be changed through the API.
+    pub fn snapshot(&self) -> ConfigSnapshot {
+        ConfigSnapshot {
+            config: self.config.clone(),
+            ports: self.ports.entries().cloned().collect(),
+            proxies: self.proxies.entries().cloned().collect(),
+            acme: self
+                .acmes
+                .entries()
+                .map(|entry| {
+                    let mut acme = entry.acme.clone();
+                    if let Some(dns) = &mut acme.dns_challenge {
+                        dns.webhook_credentials = None;
+                    }
+                    AcmeSnapshot {
+                        id: entry.id,
+                        account_id: entry.account_id,
+                        acme,
+                    }
+                })
+                .collect(),
+            certs: self
+                .certs
+                .iter()
+                .filter(|cert| cert.source.is_none())
+                .map(|cert| cert.id())
+                .collect(),
+        }
+    }
+
+    /// Checks that `snapshot` can be applied without changing anything.
+    pub fn validate_snapshot(&self, snapshot: &ConfigSnapshot) -> Result<(), Error> {
+        check_unique_ids(snapshot.ports.iter().map(|entry| entry.id))?;
+        check_unique_ids(snapshot.proxies.iter().map(|entry| entry.id))?;
+        check_unique_ids(snapshot.acme.iter().map(|entry| entry.id))?;
+        for entry in &snapshot.ports {
+            PortContext::new(entry.clone())?;
+        }
+        let ports = snapshot.ports.iter().collect::<Vec<_>>();
+        let proxies = snapshot.proxies.iter().collect::<Vec<_>>();
+        for entry in &snapshot.proxies {
+            let request = ProxyValidationRequest {
+                id: Some(entry.id),
+                proxy: entry.proxy.clone(),
+            };
+            let result = validation::validate_proxy_in(self, &ports, &proxies, request);
+            if let Some(issue) = result.errors.first() {
+                return Err(Error::InvalidConfigDocument {
+                    reason: format!("proxy {}: {}", entry.id, issue.message),
+                });
+            }
+        }
+        for entry in &snapshot.acme {
+            entry.acme.validate()?;
+            if self.acme_accounts.get(entry.account_id).is_none() {
+                return Err(Error::IdNotFound {
+                    id: entry.account_id.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the ports, proxies, ACME entries and app config with those of `snapshot`.
+    ///
+    /// Everything is validated before anything is changed. Certificates are left as they are.
+    pub async fn apply_snapshot(&mut self, snapshot: ConfigSnapshot) -> Result<(), Error> {
+        self.validate_snapshot(&snapshot)?;
+        let ports = snapshot
+            .ports
+            .into_iter()
+            .map(PortContext::new)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let port_ids = ports.iter().map(|ctx| ctx.entry.id).collect::<HashSet<_>>();
+        let removed = self
+            .ports
+            .entries()
+            .map(|entry| entry.id)
+            .filter(|id| !port_ids.contains(id))
+            .collect::<Vec<_>>();
+        for id in removed {
+            self.ports.delete(id);
+        }
+        for ctx in ports {
+            self.ports.update(ctx);
+        }
+
+        let proxy_ids = snapshot
+            .proxies
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<HashSet<_>>();
+        let removed = self
+            .proxies
+            .entries()
+            .map(|entry| entry.id)
+            .filter(|id| !proxy_ids.contains(id))
+            .collect::<Vec<_>>();
+        for id in removed {
+            self.proxies.delete(id)?;
+        }
+        for entry in snapshot.proxies {
+            self.proxies.set(entry);
+        }
+
+        let acme_ids = snapshot
+            .acme
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<HashSet<_>>();
+        let removed = self
+            .acmes
+            .entries()
+            .map(|entry| entry.id)
+            .filter(|id| !acme_ids.contains(id))
+            .collect::<Vec<_>>();
+        for id in removed {
+            self.acmes.delete(id)?;
+            self.storage.delete_acme(id).await;
+        }
+        for entry in snapshot.acme {
+            let mut acme = entry.acme;
+            if let Some(dns) = &mut acme.dns_challenge {
+                move_url_credentials(dns);
+                // Snapshots never contain the webhook credentials, so keep the current ones.
+                let current = self
+                    .acmes
+                    .get(entry.id)
+                    .and_then(|current| current.acme.dns_challenge.as_ref())
+                    .filter(|current| current.webhook_url == dns.webhook_url);
+                if let (None, Some(current)) = (&dns.webhook_credentials, current) {
+                    dns.webhook_credentials
+                        .clone_from(&current.webhook_credentials);
+                }
+            }
+            let entry = AcmeEntry {
+                id: entry.id,
+                acme,
+                account_id: entry.account_id,
+            };
+            if let Some(current) = self.acmes.get(entry.id) {
+                if current.acme == entry.acme && current.account_id == entry.account_id {
+                    continue;
+                }
+                self.acmes.delete(entry.id)?;
+            }
+            self.acmes.add(entry.clone())?;
+            self.storage.save_acme(&entry).await;
+        }
+
+        self.update_ports().await;
+        self.update_proxies().await;
+        self.update_acmes().await;
+        if self.config != snapshot.config {
+            self.set_config(snapshot.config).await?;
+        }
+        self.reload_proxies().await;
+        Ok(())
+    }
+
+    /// Records a version without an author if the configuration has changed
+    /// outside of an API call, e.g. by an ACME renewal.
+    async fn record_changes(&mut self) {
+        let snapshot = self.snapshot();
+        if self.history.latest().map(|latest| &latest.snapshot) != Some(&snapshot) {
+            self.record_version(None, snapshot).await;
+        }
+    }
+
+    async fn record_version(&mut self, author: Option<String>, snapshot: ConfigSnapshot) {
+        let (version, removed) = self.history.push(author, snapshot);
+        info!(version = version.version, author = ?version.author, "config changed");
+        self.storage.save_config_version(&version).await;
+        for version in removed {
+            self.storage.delete_config_version(version).await;
+        }
+    }
+
+    /// Lists the changes made by `version`, or since `from` if given.
+    pub fn config_diff(&self, version: u64, from: Option<u64>) -> Result<Vec<ConfigChange>, Error> {
+        let get = |version: u64| {
+            self.history.get(version).ok_or(Error::IdNotFound {
+                id: version.to_string(),
+            })
+        };
+        let to = get(version)?;
+        let empty = ConfigSnapshot::default();
+        let from = match from {
+            Some(from) => &get(from)?.snapshot,
+            None => self
+                .history
+                .previous(version)
+                .map_or(&empty, |prev| &prev.snapshot),
+        };
+        Ok(config_history::diff(from, &to.snapshot))
+    }
+
+    pub fn generate_id(&self) -> ShortId {
         let used_ids = self
             .acmes
             .entries()
             .map(|acme| acme.id)
+            .chain(self.acme_accounts.entries().map(|account| account.id))
+            .chain(self.csrs.entries().map(|csr| csr.id))
             .chain(self.ports.entries().map(|port| port.id))
             .chain(self.proxies.entries().map(|site| site.id))
             .collect::<HashSet<_>>();
+        random_id(&used_ids)
+    }
+}
 
-        let mut rng = rand::thread_rng();
-        let mut id = [b'a'; 6];
-        loop {
-            for c in &mut id {
-                *c = *TABLE.choose(&mut rng).unwrap();
-            }
-            let id = format!(
-                "{}-{}",
-                str::from_utf8(&id[..3]).unwrap(),
-                str::from_utf8(&id[3..]).unwrap()
-            )
-            .parse()
-            .unwrap();
-            if !used_ids.contains(&id) {
-                return id;
-            }
+/// Generates a random id in the `abc-def` form that is not in `used_ids`.
+pub(crate) fn random_id(used_ids: &HashSet<ShortId>) -> ShortId {
+    const TABLE: &[u8] = b"bcdfghjklmnpqrstvwxyz";
+
+    let mut rng = rand::thread_rng();
+    let mut id = [b'a'; 6];
+    loop {
+        for c in &mut id {
+            *c = *TABLE.choose(&mut rng).unwrap();
+        }
+        let id = format!(
+            "{}-{}",
+            str::from_utf8(&id[..3]).unwrap(),
+            str::from_utf8(&id[3..]).unwrap()
+        )
+        .parse()
+        .unwrap();
+        if !used_ids.contains(&id) {
+            return id;
+        }
+    }
+}
+
+struct AcmeJob {
+    entry: AcmeEntry,
+    account: AcmeAccountEntry,
+    directory: Option<Arc<Directory>>,
+    cert: Option<Arc<Cert>>,
+    forced: bool,
+    check_renewal_info: bool,
+    suggested: Option<SystemTime>,
+}
+
+async fn fetch_directory(
+    account: &AcmeAccountEntry,
+    root_certs: &RootCertStore,
+    command: &mpsc::Sender<ServerCommand>,
+) -> Option<Arc<Directory>> {
+    match account.directory(root_certs).await {
+        Ok(directory) => {
+            let directory = Arc::new(directory?);
+            let _ = command
+                .send(ServerCommand::SetAcmeDirectory {
+                    account_id: account.id,
+                    directory: directory.clone(),
+                })
+                .await;
+            Some(directory)
+        }
+        Err(err) => {
+            debug!(?err, "failed to fetch acme directory");
+            None
+        }
+    }
+}
+
+async fn renewal_info(
+    entry: &AcmeEntry,
+    directory: &Directory,
+    cert: &Cert,
+    root_certs: &RootCertStore,
+) -> Option<RenewalInfo> {
+    match entry.renewal_info(directory, cert, root_certs).await {
+        Ok(info) => info,
+        Err(err) => {
+            debug!(?err, "failed to fetch renewal info");
+            None
+        }
+    }
+}
+
+fn check_unique_ids(ids: impl Iterator<Item = ShortId>) -> Result<(), Error> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(Error::IdAlreadyExists { id });
         }
     }
+    Ok(())
 }
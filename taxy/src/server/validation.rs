@@ -0,0 +1,285 @@
+use super::state::ServerState;
+use crate::proxy::{
+    http::route::{self, ParsedServer},
+    tcp::multiaddr_to_host,
+    PortContext,
+};
+use std::io;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use taxy_api::error::Error;
+use taxy_api::id::ShortId;
+use taxy_api::port::{PortEntry, SocketState};
+use taxy_api::proxy::{ProxyEntry, ProxyKind};
+use taxy_api::subject_name::SubjectName;
+use taxy_api::validation::{
+    PortValidationRequest, ProxyValidationRequest, ValidationIssue, ValidationIssueKind,
+    ValidationResult,
+};
+use tokio::net::TcpListener;
+
+/// Runs the checks of a port update without applying it.
+pub async fn validate_port(
+    state: &ServerState,
+    request: PortValidationRequest,
+) -> ValidationResult {
+    let mut result = ValidationResult::default();
+    let id = request.id.unwrap_or_else(|| state.generate_id());
+    let entry = PortEntry {
+        id,
+        port: request.port,
+    };
+    if let Err(err) = PortContext::new(entry.clone()) {
+        result.errors.push(error_issue(None, err));
+        return result;
+    }
+    let Ok(addr) = entry.port.listen.socket_addr() else {
+        return result;
+    };
+
+    let mut conflict = false;
+    for other in state.ports.entries().filter(|other| other.id != id) {
+        let Ok(other_addr) = other.port.listen.socket_addr() else {
+            continue;
+        };
+        if !addrs_overlap(addr, other_addr) {
+            continue;
+        }
+        let issue = ValidationIssue {
+            kind: ValidationIssueKind::AddressConflict,
+            field: Some("listen".into()),
+            message: format!("{addr} overlaps with port {} ({other_addr})", other.id),
+            conflict: Some(other.id),
+        };
+        if entry.port.active && other.port.active {
+            conflict = true;
+            result.errors.push(issue);
+        } else {
+            result.warnings.push(issue);
+        }
+    }
+
+    let listening = state.ports.get(id).is_some_and(|ctx| {
+        ctx.status().state.socket == SocketState::Listening
+            && ctx.entry().port.listen.socket_addr().ok() == Some(addr)
+    });
+    if entry.port.active && !conflict && !listening {
+        if let Err(err) = TcpListener::bind(addr).await {
+            let kind = match err.kind() {
+                io::ErrorKind::AddrInUse => ValidationIssueKind::AddressAlreadyInUse,
+                io::ErrorKind::PermissionDenied => ValidationIssueKind::PermissionDenied,
+                io::ErrorKind::AddrNotAvailable => ValidationIssueKind::AddressNotAvailable,
+                _ => ValidationIssueKind::InvalidConfig,
+            };
+            result.errors.push(ValidationIssue {
+                kind,
+                field: Some("listen".into()),
+                message: format!("failed to listen on {addr}: {err}"),
+                conflict: None,
+            });
+        }
+    }
+
+    if let Some(tls) = &entry.port.opts.tls_termination {
+        for (i, name) in tls.server_names.iter().enumerate() {
+            let field = format!("tls_termination.server_names[{i}]");
+            match SubjectName::from_str(name) {
+                Ok(name) => check_cert(state, &mut result, field, &name),
+                Err(err) => result.errors.push(error_issue(Some(field), err)),
+            }
+        }
+    }
+    result
+}
+
+/// Runs the checks of a proxy update without applying it.
+pub fn validate_proxy(state: &ServerState, request: ProxyValidationRequest) -> ValidationResult {
+    let ports = state.ports.entries().collect::<Vec<_>>();
+    let proxies = state.proxies.entries().collect::<Vec<_>>();
+    validate_proxy_in(state, &ports, &proxies, request)
+}
+
+/// Runs the checks of a proxy update against the given ports and proxies
+/// instead of the current ones.
+pub fn validate_proxy_in(
+    state: &ServerState,
+    ports: &[&PortEntry],
+    proxies: &[&ProxyEntry],
+    request: ProxyValidationRequest,
+) -> ValidationResult {
+    let mut result = ValidationResult::default();
+    let proxy = &request.proxy;
+    let is_tcp = matches!(proxy.kind, ProxyKind::Tcp(_));
+
+    let mut compatible_ports = Vec::new();
+    let mut tls_ports = Vec::new();
+    for (i, &port) in proxy.ports.iter().enumerate() {
+        let field = Some(format!("ports[{i}]"));
+        let Some(entry) = ports.iter().find(|entry| entry.id == port) else {
+            result.errors.push(ValidationIssue {
+                kind: ValidationIssueKind::PortNotFound,
+                field,
+                message: format!("port {port} does not exist"),
+                conflict: Some(port),
+            });
+            continue;
+        };
+        let listen = &entry.port.listen;
+        if listen.is_http() == is_tcp {
+            result.errors.push(ValidationIssue {
+                kind: ValidationIssueKind::IncompatiblePort,
+                field,
+                message: format!(
+                    "port {port} is a {} port and cannot be used by this proxy",
+                    listen.protocol_name()
+                ),
+                conflict: Some(port),
+            });
+            continue;
+        }
+        compatible_ports.push(port);
+        if listen.is_tls() {
+            tls_ports.push(port);
+        }
+    }
+
+    match &proxy.kind {
+        ProxyKind::Tcp(tcp) => {
+            for (i, server) in tcp.upstream_servers.iter().enumerate() {
+                if let Err(err) = multiaddr_to_host(&server.addr) {
+                    let field = format!("upstream_servers[{i}].addr");
+                    result.errors.push(error_issue(Some(field), err));
+                }
+            }
+            let others = proxies
+                .iter()
+                .filter(|other| Some(other.id) != request.id && other.proxy.active)
+                .filter(|other| matches!(other.proxy.kind, ProxyKind::Tcp(_)));
+            for other in others {
+                for port in other.proxy.ports.iter().filter(|p| proxy.ports.contains(p)) {
+                    result.warnings.push(ValidationIssue {
+                        kind: ValidationIssueKind::PortTaken,
+                        field: None,
+                        message: format!("port {port} will be removed from proxy {}", other.id),
+                        conflict: Some(other.id),
+                    });
+                }
+            }
+        }
+        ProxyKind::Http(http) => {
+            for (i, route) in http.routes.iter().enumerate() {
+                for (j, server) in route.servers.iter().enumerate() {
+                    if let Err(err) = ParsedServer::try_from(server.clone()) {
+                        let field = format!("routes[{i}].servers[{j}].url");
+                        result.errors.push(error_issue(Some(field), err));
+                    }
+                }
+            }
+            if proxy.active {
+                check_routes(state, proxies, &mut result, &request, &compatible_ports);
+            }
+            if !tls_ports.is_empty() {
+                for (i, name) in http.vhosts.iter().enumerate() {
+                    check_cert(state, &mut result, format!("vhosts[{i}]"), name);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Reports conflicts between the routes of the proxy and those of the proxies sharing its ports.
+fn check_routes(
+    state: &ServerState,
+    proxies: &[&ProxyEntry],
+    result: &mut ValidationResult,
+    request: &ProxyValidationRequest,
+    ports: &[ShortId],
+) {
+    let ProxyKind::Http(http) = &request.proxy.kind else {
+        return;
+    };
+    let id = request.id.unwrap_or_else(|| state.generate_id());
+    let candidate = ProxyEntry {
+        id,
+        proxy: request.proxy.clone(),
+    };
+    let field = |path: &str| {
+        http.routes
+            .iter()
+            .position(|route| route.path == path)
+            .map(|i| format!("routes[{i}].path"))
+    };
+
+    for &port in ports {
+        let mut entries = proxies
+            .iter()
+            .map(|&entry| if entry.id == id { &candidate } else { entry })
+            .filter(|entry| entry.proxy.active && entry.proxy.ports.contains(&port))
+            .cloned()
+            .collect::<Vec<_>>();
+        // New proxies are appended, so they come after all existing ones.
+        if proxies.iter().all(|entry| entry.id != id) {
+            entries.push(candidate.clone());
+        }
+
+        for (owner, conflict) in route::find_conflicts(port, &entries) {
+            if owner == id {
+                let (message, other) = if conflict.proxy == id {
+                    let message = format!(
+                        "route {} is shadowed by route {} of this proxy",
+                        conflict.path, conflict.by_path
+                    );
+                    (message, None)
+                } else {
+                    (conflict.to_string(), Some(conflict.proxy))
+                };
+                result.warnings.push(ValidationIssue {
+                    kind: ValidationIssueKind::RouteConflict,
+                    field: field(&conflict.path),
+                    message,
+                    conflict: other,
+                });
+            } else if conflict.proxy == id {
+                result.warnings.push(ValidationIssue {
+                    kind: ValidationIssueKind::RouteConflict,
+                    field: field(&conflict.by_path),
+                    message: format!(
+                        "route {} shadows route {} of proxy {owner} on port {port}",
+                        conflict.by_path, conflict.path
+                    ),
+                    conflict: Some(owner),
+                });
+            }
+        }
+    }
+}
+
+fn check_cert(
+    state: &ServerState,
+    result: &mut ValidationResult,
+    field: String,
+    name: &SubjectName,
+) {
+    if state.certs.find_server_cert(name).is_none() {
+        result.warnings.push(ValidationIssue {
+            kind: ValidationIssueKind::UncoveredName,
+            field: Some(field),
+            message: format!("no certificate covers {}", name.to_string()),
+            conflict: None,
+        });
+    }
+}
+
+fn error_issue(field: Option<String>, err: Error) -> ValidationIssue {
+    ValidationIssue {
+        kind: ValidationIssueKind::InvalidConfig,
+        field,
+        message: err.to_string(),
+        conflict: None,
+    }
+}
+
+fn addrs_overlap(a: SocketAddr, b: SocketAddr) -> bool {
+    a.port() == b.port() && (a.ip() == b.ip() || a.ip().is_unspecified() || b.ip().is_unspecified())
+}
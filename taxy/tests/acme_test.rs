@@ -0,0 +1,817 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use taxy::{
+    certs::{
+        acme::{AcmeAccountEntry, AcmeEntry},
+        Cert,
+    },
+    command::ServerCommand,
+    server::rpc::{
+        acme::{AddAcme, GetAcmeList, RenewAcme, RevokeAcmeCert},
+        acme_accounts::{
+            AddAcmeAccount, DeactivateAcmeAccount, DeleteAcmeAccount, GetAcmeAccountList,
+            ImportAcmeAccount, RolloverAcmeAccountKey, UpdateAcmeAccount,
+        },
+        certs::GetCert,
+        config::ExportConfig,
+        history::{GetConfigHistory, GetConfigVersion},
+    },
+};
+use taxy_api::{
+    acme::{
+        Acme, AcmeAccountImport, AcmeAccountRequest, AcmeAccountStatus, AcmeAccountUpdate,
+        AcmeConfig, AcmeOrderStatus, AcmeRequest, AcmeRevokeRequest, DnsChallengeConfig,
+        RevocationReason,
+    },
+    app::{AppConfig, OnDemandTlsConfig},
+    cert::{CertInfo, KeyAlgorithm},
+    error::Error,
+    event::ServerEvent,
+    id::ShortId,
+    port::{Port, PortEntry, PortOptions, UpstreamServer},
+    proxy::{Proxy, ProxyEntry, ProxyKind, TcpProxy},
+    subject_name::SubjectName,
+    tls::TlsTermination,
+};
+use tokio::sync::broadcast;
+use tokio_rustls::rustls::RootCertStore;
+
+mod common;
+use common::{
+    acme::{AcmeServer, AcmeValidation},
+    alloc_port, call_method, with_server, TestStorage,
+};
+
+async fn new_acme_account(server: &AcmeServer) -> anyhow::Result<AcmeAccountEntry> {
+    let request = AcmeAccountRequest {
+        server_url: server.directory_url(),
+        contacts: vec![],
+        eab: None,
+    };
+    let id = "account".parse().unwrap();
+    Ok(AcmeAccountEntry::new(id, request, &RootCertStore::empty()).await?)
+}
+
+fn new_acme_entry(
+    account: &AcmeAccountEntry,
+    id: ShortId,
+    challenge_type: &str,
+) -> anyhow::Result<AcmeEntry> {
+    let acme = Acme {
+        config: AcmeConfig::default(),
+        identifiers: vec!["example.com".parse().unwrap()],
+        challenge_type: challenge_type.into(),
+        dns_challenge: None,
+    };
+    Ok(AcmeEntry::new(id, acme, account.id)?)
+}
+
+async fn wait_for_acme_cert(
+    events: &mut broadcast::Receiver<ServerEvent>,
+    id: ShortId,
+) -> anyhow::Result<CertInfo> {
+    tokio::time::timeout(Duration::from_secs(30), async {
+        loop {
+            if let ServerEvent::CertsUpdated { entries } = events.recv().await? {
+                if let Some(cert) = entries
+                    .into_iter()
+                    .find(|cert| cert.metadata.as_ref().map(|meta| meta.acme_id) == Some(id))
+                {
+                    return Ok(cert);
+                }
+            }
+        }
+    })
+    .await?
+}
+
+async fn wait_for_named_cert(
+    events: &mut broadcast::Receiver<ServerEvent>,
+    name: &str,
+) -> anyhow::Result<CertInfo> {
+    let name = SubjectName::DnsName(name.into());
+    tokio::time::timeout(Duration::from_secs(30), async {
+        loop {
+            if let ServerEvent::CertsUpdated { entries } = events.recv().await? {
+                if let Some(cert) = entries.into_iter().find(|cert| cert.san.contains(&name)) {
+                    return Ok(cert);
+                }
+            }
+        }
+    })
+    .await?
+}
+
+#[tokio::test]
+async fn acme_tls_alpn_challenge() -> anyhow::Result<()> {
+    let challenge_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(challenge_port.socket_addr()),
+        ..Default::default()
+    })
+    .await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            tls_alpn_challenge_addr: challenge_port.socket_addr(),
+            ..Default::default()
+        })
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |channels| async move {
+        let mut events = channels.event.subscribe();
+        wait_for_acme_cert(&mut events, id).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_tls_alpn_challenge_on_https_port() -> anyhow::Result<()> {
+    let proxy_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(proxy_port.socket_addr()),
+        ..Default::default()
+    })
+    .await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            tls_alpn_challenge_addr: proxy_port.socket_addr(),
+            ..Default::default()
+        })
+        .ports(vec![PortEntry {
+            id: "test".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: proxy_port.multiaddr_https(),
+                opts: PortOptions {
+                    tls_termination: Some(TlsTermination {
+                        server_names: vec!["example.com".into()],
+                    }),
+                },
+            },
+        }])
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    let ca = reqwest::Certificate::from_pem(server.ca_pem().as_bytes())?;
+    with_server(config, |channels| async move {
+        let mut events = channels.event.subscribe();
+        wait_for_acme_cert(&mut events, id).await?;
+
+        let client = reqwest::Client::builder()
+            .add_root_certificate(ca)
+            .resolve("example.com", proxy_port.socket_addr())
+            .build()?;
+        let resp = client
+            .get(format!(
+                "https://example.com:{}/",
+                proxy_port.socket_addr().port()
+            ))
+            .send()
+            .await?;
+        assert_eq!(resp.status(), 502);
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_tls_alpn_challenge_on_tls_port() -> anyhow::Result<()> {
+    let proxy_port = alloc_port()?;
+    let upstream_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(proxy_port.socket_addr()),
+        tls_alpn_01_probe: vec![b"http/1.1".to_vec()],
+        ..Default::default()
+    })
+    .await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default())?);
+    let cert = Arc::new(Cert::new_self_signed(
+        &["example.com".parse().unwrap()],
+        &root,
+        KeyAlgorithm::default(),
+    )?);
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            tls_alpn_challenge_addr: proxy_port.socket_addr(),
+            ..Default::default()
+        })
+        .ports(vec![PortEntry {
+            id: "test".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: proxy_port.multiaddr_tls(),
+                opts: PortOptions {
+                    tls_termination: Some(TlsTermination {
+                        server_names: vec!["example.com".into()],
+                    }),
+                },
+            },
+        }])
+        .proxies(vec![ProxyEntry {
+            id: "test2".parse().unwrap(),
+            proxy: Proxy {
+                ports: vec!["test".parse().unwrap()],
+                kind: ProxyKind::Tcp(TcpProxy {
+                    upstream_servers: vec![UpstreamServer {
+                        addr: upstream_port.multiaddr_tcp(),
+                    }],
+                }),
+                ..Default::default()
+            },
+        }])
+        .certs([(cert.id, cert)].into_iter().collect())
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |channels| async move {
+        let mut events = channels.event.subscribe();
+        wait_for_acme_cert(&mut events, id).await?;
+
+        // A client offering http/1.1 must still complete the handshake while
+        // the port answers the challenge.
+        assert_eq!(server.tls_alpn_01_probes(), vec![true]);
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_dns_challenge_wildcard() -> anyhow::Result<()> {
+    let server = AcmeServer::start(AcmeValidation {
+        // "taxy:secret"
+        dns_webhook_auth: Some("Basic dGF4eTpzZWNyZXQ=".into()),
+        ..Default::default()
+    })
+    .await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let acme = Acme {
+        config: AcmeConfig::default(),
+        identifiers: vec![
+            "example.com".parse().unwrap(),
+            "*.example.com".parse().unwrap(),
+        ],
+        challenge_type: "dns-01".into(),
+        dns_challenge: Some(DnsChallengeConfig {
+            webhook_url: server
+                .dns_webhook_url()
+                .replace("http://", "http://taxy:secret@"),
+            webhook_credentials: None,
+            propagation_delay: Duration::ZERO,
+        }),
+    };
+    let entry = AcmeEntry::new(id, acme, account.id)?;
+
+    let config = TestStorage::builder()
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let mut events = channels.event.subscribe();
+        let cert = wait_for_acme_cert(&mut events, id).await?;
+        assert!(cert.san.contains(&"*.example.com".parse().unwrap()));
+        assert!(cert.san.contains(&"example.com".parse().unwrap()));
+
+        // The webhook credentials are neither exported nor recorded in the history.
+        let snapshot = call_method(&mut channels, ExportConfig).await?;
+        let dns = snapshot.acme[0].acme.dns_challenge.as_ref().unwrap();
+        assert_eq!(dns.webhook_url, server.dns_webhook_url());
+        assert!(dns.webhook_credentials.is_none());
+        let history = call_method(&mut channels, GetConfigHistory).await?;
+        for version in history {
+            let version = call_method(
+                &mut channels,
+                GetConfigVersion {
+                    version: version.version,
+                },
+            )
+            .await?;
+            assert!(!serde_json::to_string(&version)?.contains("secret"));
+        }
+        let acmes = call_method(&mut channels, GetAcmeList).await?;
+        assert!(!serde_json::to_string(&acmes)?.contains("secret"));
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_wildcard_requires_dns_challenge() -> anyhow::Result<()> {
+    let acme = Acme {
+        config: AcmeConfig::default(),
+        identifiers: vec!["*.example.com".parse().unwrap()],
+        challenge_type: "http-01".into(),
+        dns_challenge: None,
+    };
+    let result = AcmeEntry::new("acme".parse().unwrap(), acme, "account".parse().unwrap());
+    assert!(matches!(
+        result,
+        Err(Error::WildcardRequiresDnsChallenge { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn acme_key_algorithm() -> anyhow::Result<()> {
+    let acme = Acme {
+        config: AcmeConfig {
+            key_algorithm: KeyAlgorithm::Ed25519,
+            ..Default::default()
+        },
+        identifiers: vec!["example.com".parse().unwrap()],
+        challenge_type: "http-01".into(),
+        dns_challenge: None,
+    };
+    let result = AcmeEntry::new("acme".parse().unwrap(), acme, "account".parse().unwrap());
+    assert!(matches!(result, Err(Error::UnsupportedKeyAlgorithm { .. })));
+
+    let challenge_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(challenge_port.socket_addr()),
+        ..Default::default()
+    })
+    .await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let mut entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+    entry.acme.config.key_algorithm = KeyAlgorithm::Rsa2048;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            tls_alpn_challenge_addr: challenge_port.socket_addr(),
+            ..Default::default()
+        })
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let mut events = channels.event.subscribe();
+        let info = wait_for_acme_cert(&mut events, id).await?;
+        let cert = call_method(&mut channels, GetCert { id: info.id }).await?;
+        let (_, pem) = x509_parser::pem::parse_x509_pem(&cert.pem_chain)?;
+        let x509 = pem.parse_x509()?;
+        assert_eq!(
+            x509.public_key().algorithm.algorithm,
+            x509_parser::oid_registry::OID_PKCS1_RSAENCRYPTION
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_renewal_info() -> anyhow::Result<()> {
+    let challenge_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(challenge_port.socket_addr()),
+        ..Default::default()
+    })
+    .await?;
+    server.set_renewal_due(true);
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            background_task_interval: Duration::from_secs(1),
+            tls_alpn_challenge_addr: challenge_port.socket_addr(),
+            ..Default::default()
+        })
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |channels| async move {
+        let mut events = channels.event.subscribe();
+        wait_for_acme_cert(&mut events, id).await?;
+
+        // The server suggests renewing the first certificate right away.
+        tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                if let ServerEvent::CertsUpdated { entries } = events.recv().await? {
+                    let certs = entries
+                        .iter()
+                        .filter(|cert| cert.metadata.as_ref().map(|meta| meta.acme_id) == Some(id))
+                        .count();
+                    if certs >= 2 {
+                        server.set_renewal_due(false);
+                        return anyhow::Ok(());
+                    }
+                }
+            }
+        })
+        .await??;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_renewal_info_retry_after() -> anyhow::Result<()> {
+    let challenge_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(challenge_port.socket_addr()),
+        ..Default::default()
+    })
+    .await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            background_task_interval: Duration::from_secs(1),
+            tls_alpn_challenge_addr: challenge_port.socket_addr(),
+            ..Default::default()
+        })
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |channels| async move {
+        let mut events = channels.event.subscribe();
+        wait_for_acme_cert(&mut events, id).await?;
+
+        // The renewal window is not asked again until Retry-After has passed.
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        assert_eq!(server.renewal_info_requests(), 1);
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_status_on_failure() -> anyhow::Result<()> {
+    let challenge_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation::default()).await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            tls_alpn_challenge_addr: challenge_port.socket_addr(),
+            ..Default::default()
+        })
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |channels| async move {
+        let mut events = channels.event.subscribe();
+        channels
+            .command
+            .send(ServerCommand::SetBroadcastEvents { enabled: true })
+            .await?;
+
+        let status = tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                if let ServerEvent::AcmeStatusUpdated {
+                    id: acme_id,
+                    status,
+                } = events.recv().await?
+                {
+                    if acme_id == id && status.last_error.is_some() {
+                        return anyhow::Ok(status);
+                    }
+                }
+            }
+        })
+        .await??;
+
+        assert_eq!(status.order, AcmeOrderStatus::Idle);
+        assert!(status.last_attempt_at.is_some());
+        assert!(status.last_success_at.is_none());
+        assert!(status.next_renewal_at.unwrap() > SystemTime::now());
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_renew_and_revoke() -> anyhow::Result<()> {
+    let challenge_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(challenge_port.socket_addr()),
+        ..Default::default()
+    })
+    .await?;
+
+    let id = "acme".parse().unwrap();
+    let account = new_acme_account(&server).await?;
+    let entry = new_acme_entry(&account, id, "tls-alpn-01")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            tls_alpn_challenge_addr: challenge_port.socket_addr(),
+            ..Default::default()
+        })
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .acems([(id, entry)].into_iter().collect())
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let mut events = channels.event.subscribe();
+        let first = wait_for_acme_cert(&mut events, id).await?;
+
+        // The first certificate is far from expiry, so this must be forced.
+        call_method(&mut channels, RenewAcme { id }).await?;
+        let second = tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                if let ServerEvent::CertsUpdated { entries } = events.recv().await? {
+                    if let Some(cert) = entries.into_iter().find(|cert| {
+                        cert.id != first.id
+                            && cert.metadata.as_ref().map(|meta| meta.acme_id) == Some(id)
+                    }) {
+                        return anyhow::Ok(cert);
+                    }
+                }
+            }
+        })
+        .await??;
+
+        let result = call_method(
+            &mut channels,
+            RenewAcme {
+                id: "unknown".parse().unwrap(),
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::IdNotFound { .. })));
+
+        call_method(
+            &mut channels,
+            RevokeAcmeCert {
+                id,
+                request: AcmeRevokeRequest {
+                    cert_id: first.id,
+                    reason: RevocationReason::Superseded,
+                },
+            },
+        )
+        .await?;
+        assert_eq!(server.revocation_reasons(), vec![4]);
+
+        let ServerEvent::CertsUpdated { entries } = events.recv().await? else {
+            anyhow::bail!("unexpected event");
+        };
+        assert!(entries.iter().all(|cert| cert.id != first.id));
+        assert!(entries.iter().any(|cert| cert.id == second.id));
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_account_management() -> anyhow::Result<()> {
+    let server = AcmeServer::start(AcmeValidation::default()).await?;
+    let contacts = vec!["mailto:admin@example.com".to_string()];
+    let acme_request = AcmeRequest {
+        account_id: None,
+        server_url: server.directory_url(),
+        contacts: contacts.clone(),
+        eab: None,
+        acme: Acme {
+            config: AcmeConfig::default(),
+            identifiers: vec!["example.com".parse().unwrap()],
+            challenge_type: "dns-01".into(),
+            dns_challenge: Some(DnsChallengeConfig {
+                webhook_url: server.dns_webhook_url(),
+                webhook_credentials: None,
+                propagation_delay: Duration::ZERO,
+            }),
+        },
+    };
+
+    with_server(TestStorage::default(), |mut channels| async move {
+        let mut events = channels.event.subscribe();
+        let account = call_method(
+            &mut channels,
+            AddAcmeAccount {
+                request: AcmeAccountRequest {
+                    server_url: server.directory_url(),
+                    contacts: contacts.clone(),
+                    eab: None,
+                },
+            },
+        )
+        .await?;
+        assert_eq!(account.contacts, contacts);
+
+        // Entries with the same server and contacts share the account.
+        for _ in 0..2 {
+            let request = acme_request.clone();
+            call_method(&mut channels, AddAcme { request }).await?;
+        }
+        let entries = call_method(&mut channels, GetAcmeList).await?;
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.account_id == account.id));
+        assert_eq!(
+            call_method(&mut channels, GetAcmeAccountList).await?.len(),
+            1
+        );
+
+        let request = AcmeAccountUpdate {
+            contacts: vec!["mailto:ops@example.com".into()],
+        };
+        let updated = call_method(
+            &mut channels,
+            UpdateAcmeAccount {
+                id: account.id,
+                request: request.clone(),
+            },
+        )
+        .await?;
+        assert_eq!(updated.contacts, request.contacts);
+
+        // Orders keep working with the new key.
+        let id = entries[0].id;
+        let first = wait_for_acme_cert(&mut events, id).await?;
+        call_method(&mut channels, RolloverAcmeAccountKey { id: account.id }).await?;
+        call_method(&mut channels, RenewAcme { id }).await?;
+        tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                if let ServerEvent::CertsUpdated { entries } = events.recv().await? {
+                    if entries.iter().any(|cert| {
+                        cert.id != first.id
+                            && cert.metadata.as_ref().map(|meta| meta.acme_id) == Some(id)
+                    }) {
+                        return anyhow::Ok(());
+                    }
+                }
+            }
+        })
+        .await??;
+
+        let result = call_method(&mut channels, DeleteAcmeAccount { id: account.id }).await;
+        assert!(matches!(result, Err(Error::AcmeAccountInUse { .. })));
+
+        let existing = new_acme_account(&server).await?;
+        let imported = call_method(
+            &mut channels,
+            ImportAcmeAccount {
+                request: AcmeAccountImport {
+                    credentials: serde_json::to_value(&*existing.credentials)?,
+                },
+            },
+        )
+        .await?;
+        assert_eq!(imported.status, AcmeAccountStatus::Valid);
+        assert_eq!(imported.server_url, server.directory_url());
+
+        let deactivated =
+            call_method(&mut channels, DeactivateAcmeAccount { id: imported.id }).await?;
+        assert_eq!(deactivated.status, AcmeAccountStatus::Deactivated);
+        let request = AcmeRequest {
+            account_id: Some(imported.id),
+            ..acme_request
+        };
+        let result = call_method(&mut channels, AddAcme { request }).await;
+        assert!(matches!(result, Err(Error::AcmeAccountDeactivated { .. })));
+
+        call_method(&mut channels, DeleteAcmeAccount { id: imported.id }).await?;
+        assert_eq!(
+            call_method(&mut channels, GetAcmeAccountList).await?.len(),
+            1
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn acme_on_demand_tls() -> anyhow::Result<()> {
+    let proxy_port = alloc_port()?;
+    let server = AcmeServer::start(AcmeValidation {
+        tls_alpn_01: Some(proxy_port.socket_addr()),
+        ..Default::default()
+    })
+    .await?;
+    let account = new_acme_account(&server).await?;
+
+    let mut ask_server = mockito::Server::new_async().await;
+    let ask = ask_server
+        .mock("GET", "/check")
+        .match_query(mockito::Matcher::UrlEncoded(
+            "domain".into(),
+            "shop.example.net".into(),
+        ))
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            tls_alpn_challenge_addr: proxy_port.socket_addr(),
+            on_demand_tls: Some(OnDemandTlsConfig {
+                account_id: account.id,
+                challenge_type: "tls-alpn-01".into(),
+                allowed_names: vec!["*.example.com".parse().unwrap()],
+                ask_url: Some(format!("{}/check", ask_server.url())),
+            }),
+            ..Default::default()
+        })
+        .ports(vec![PortEntry {
+            id: "test".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: proxy_port.multiaddr_https(),
+                opts: PortOptions {
+                    tls_termination: Some(TlsTermination {
+                        server_names: vec![],
+                    }),
+                },
+            },
+        }])
+        .acme_accounts([(account.id, account)].into_iter().collect())
+        .build();
+
+    let ca = reqwest::Certificate::from_pem(server.ca_pem().as_bytes())?;
+    let port = proxy_port.socket_addr().port();
+    let client = reqwest::Client::builder()
+        .add_root_certificate(ca)
+        .resolve("foo.example.com", proxy_port.socket_addr())
+        .resolve("shop.example.net", proxy_port.socket_addr())
+        .resolve("other.example.net", proxy_port.socket_addr())
+        .build()?;
+
+    with_server(config, |mut channels| async move {
+        let mut events = channels.event.subscribe();
+
+        for name in ["other.example.net", "foo.example.com", "shop.example.net"] {
+            let url = format!("https://{name}:{port}/");
+            assert!(client.get(&url).send().await.is_err());
+        }
+
+        wait_for_named_cert(&mut events, "foo.example.com").await?;
+        wait_for_named_cert(&mut events, "shop.example.net").await?;
+
+        for name in ["foo.example.com", "shop.example.net"] {
+            let url = format!("https://{name}:{port}/");
+            assert_eq!(client.get(&url).send().await?.status(), 502);
+        }
+        assert!(client
+            .get(format!("https://other.example.net:{port}/"))
+            .send()
+            .await
+            .is_err());
+
+        let mut names = call_method(&mut channels, GetAcmeList)
+            .await?
+            .into_iter()
+            .flat_map(|acme| acme.identifiers)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["foo.example.com", "shop.example.net"]);
+
+        // The entries and certificates added by the server are recorded in the history.
+        let history = call_method(&mut channels, GetConfigHistory).await?;
+        let latest = call_method(
+            &mut channels,
+            GetConfigVersion {
+                version: history[0].version,
+            },
+        )
+        .await?;
+        assert!(latest.author.is_none());
+        assert_eq!(latest.snapshot.acme.len(), 2);
+        assert_eq!(latest.snapshot.certs.len(), 2);
+        Ok(())
+    })
+    .await?;
+
+    ask.assert_async().await;
+    Ok(())
+}
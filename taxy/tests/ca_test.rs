@@ -0,0 +1,303 @@
+use taxy::server::rpc::{
+    ca::{CreateCa, DeleteCa, GetCa, GetCrl, GetIssuedCerts, IssueCert, RevokeIssuedCert},
+    certs::GetCert,
+};
+use taxy_api::{
+    acme::RevocationReason,
+    ca::{CaRequest, ExtendedKeyUsage, IssueCertRequest},
+    cert::{CertKind, KeyAlgorithm},
+    error::Error,
+    port::{Port, PortEntry, PortOptions},
+    tls::TlsTermination,
+};
+use warp::Filter;
+use x509_parser::{
+    extensions::ParsedExtension,
+    prelude::{FromDer, X509Certificate},
+    revocation_list::CertificateRevocationList,
+};
+
+mod common;
+use common::{alloc_port, call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn ca_issue_and_revoke() -> anyhow::Result<()> {
+    let listen_port = alloc_port()?;
+    let config = TestStorage::builder().build();
+
+    with_server(config, |mut channels| async move {
+        let ca = call_method(
+            &mut channels,
+            CreateCa {
+                request: CaRequest {
+                    common_name: "Internal CA".into(),
+                    validity_days: 365,
+                    key_algorithm: KeyAlgorithm::EcdsaP256,
+                    crl_url: Some("http://ca.example.com/crl".into()),
+                },
+            },
+        )
+        .await?;
+        assert_eq!(ca.subject, "CN=Internal CA");
+
+        let ca_cert = call_method(&mut channels, GetCert { id: ca.id }).await?;
+        assert!(ca_cert.is_ca);
+
+        let root_request = IssueCertRequest {
+            kind: CertKind::Root,
+            san: vec![],
+            common_name: None,
+            validity_days: 30,
+            key_algorithm: KeyAlgorithm::default(),
+            key_usages: vec![],
+            extended_key_usages: vec![],
+        };
+        assert!(matches!(
+            call_method(
+                &mut channels,
+                IssueCert {
+                    id: ca.id,
+                    request: root_request
+                }
+            )
+            .await,
+            Err(Error::UnsupportedIssueCertKind { .. })
+        ));
+
+        let long_lived_request = IssueCertRequest {
+            kind: CertKind::Server,
+            san: vec!["localhost".parse().unwrap()],
+            common_name: None,
+            validity_days: 3_000_000,
+            key_algorithm: KeyAlgorithm::default(),
+            key_usages: vec![],
+            extended_key_usages: vec![],
+        };
+        assert!(matches!(
+            call_method(
+                &mut channels,
+                IssueCert {
+                    id: ca.id,
+                    request: long_lived_request
+                }
+            )
+            .await,
+            Err(Error::InvalidValidityDays { days: 3_000_000 })
+        ));
+        assert!(matches!(
+            call_method(
+                &mut channels,
+                CreateCa {
+                    request: CaRequest {
+                        common_name: "Long-lived CA".into(),
+                        validity_days: u64::MAX,
+                        key_algorithm: KeyAlgorithm::EcdsaP256,
+                        crl_url: None,
+                    },
+                },
+            )
+            .await,
+            Err(Error::InvalidValidityDays { .. })
+        ));
+
+        let server = call_method(
+            &mut channels,
+            IssueCert {
+                id: ca.id,
+                request: IssueCertRequest {
+                    kind: CertKind::Server,
+                    san: vec!["localhost".parse().unwrap()],
+                    common_name: None,
+                    validity_days: 30,
+                    key_algorithm: KeyAlgorithm::default(),
+                    key_usages: vec![],
+                    extended_key_usages: vec![],
+                },
+            },
+        )
+        .await?;
+        let client = call_method(
+            &mut channels,
+            IssueCert {
+                id: ca.id,
+                request: IssueCertRequest {
+                    kind: CertKind::Client,
+                    san: vec!["service.internal".parse().unwrap()],
+                    common_name: Some("service".into()),
+                    validity_days: 30,
+                    key_algorithm: KeyAlgorithm::default(),
+                    key_usages: vec![],
+                    extended_key_usages: vec![ExtendedKeyUsage::ClientAuth],
+                },
+            },
+        )
+        .await?;
+        assert_eq!(client.kind, CertKind::Client);
+
+        let server_cert = call_method(&mut channels, GetCert { id: server.id }).await?;
+        let der = &server_cert.certificates()?[0].0;
+        let (_, x509) = X509Certificate::from_der(der)?;
+        assert_eq!(x509.issuer().to_string(), "CN=Internal CA");
+        let crl_points = x509.extensions().iter().any(|ext| {
+            matches!(
+                ext.parsed_extension(),
+                ParsedExtension::CRLDistributionPoints(_)
+            )
+        });
+        assert!(crl_points);
+
+        let addr = listen_port.socket_addr();
+        let hello = warp::path!("hello").map(|| "Hello".to_string());
+        let (_, server_task) = warp::serve(hello)
+            .tls()
+            .cert(&server_cert.pem_chain)
+            .key(server_cert.pem_key.as_ref().unwrap())
+            .bind_ephemeral(addr);
+        tokio::spawn(server_task);
+
+        let root = reqwest::Certificate::from_pem(&ca_cert.pem_chain)?;
+        let http = reqwest::Client::builder()
+            .add_root_certificate(root)
+            .build()?;
+        let resp = http
+            .get(listen_port.https_url("/hello"))
+            .send()
+            .await?
+            .text()
+            .await?;
+        assert_eq!(resp, "Hello");
+
+        let issued = call_method(&mut channels, GetIssuedCerts { id: ca.id }).await?;
+        assert_eq!(issued.len(), 2);
+        assert_eq!(issued[0].cert_id, server.id);
+        assert_eq!(issued[1].common_name, "service");
+
+        let crl = call_method(&mut channels, GetCrl { id: ca.id }).await?;
+        let (_, parsed) = CertificateRevocationList::from_der(&crl)?;
+        assert_eq!(parsed.iter_revoked_certificates().count(), 0);
+
+        let serial = issued[1].serial.clone();
+        let revoked = call_method(
+            &mut channels,
+            RevokeIssuedCert {
+                id: ca.id,
+                serial: serial.clone(),
+                reason: RevocationReason::KeyCompromise,
+            },
+        )
+        .await?;
+        assert!(revoked.revoked.is_some());
+        assert!(matches!(
+            call_method(
+                &mut channels,
+                RevokeIssuedCert {
+                    id: ca.id,
+                    serial: serial.clone(),
+                    reason: RevocationReason::KeyCompromise,
+                },
+            )
+            .await,
+            Err(Error::CertAlreadyRevoked { .. })
+        ));
+
+        let crl = call_method(&mut channels, GetCrl { id: ca.id }).await?;
+        let (_, parsed) = CertificateRevocationList::from_der(&crl)?;
+        let revoked = parsed.iter_revoked_certificates().collect::<Vec<_>>();
+        assert_eq!(revoked.len(), 1);
+        assert_eq!(hex::encode(revoked[0].raw_serial()), serial);
+        let info = call_method(&mut channels, GetCa { id: ca.id }).await?;
+        assert_eq!(info.crl_number, 2);
+
+        call_method(&mut channels, DeleteCa { id: ca.id }).await?;
+        assert!(call_method(&mut channels, GetCrl { id: ca.id })
+            .await
+            .is_err());
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn ca_revoked_cert_not_served() -> anyhow::Result<()> {
+    let proxy_port = alloc_port()?;
+    let config = TestStorage::builder()
+        .ports(vec![PortEntry {
+            id: "test".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: proxy_port.multiaddr_https(),
+                opts: PortOptions {
+                    tls_termination: Some(TlsTermination {
+                        server_names: vec!["localhost".into()],
+                    }),
+                },
+            },
+        }])
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let ca = call_method(
+            &mut channels,
+            CreateCa {
+                request: CaRequest {
+                    common_name: "Internal CA".into(),
+                    validity_days: 365,
+                    key_algorithm: KeyAlgorithm::EcdsaP256,
+                    crl_url: None,
+                },
+            },
+        )
+        .await?;
+        let ca_cert = call_method(&mut channels, GetCert { id: ca.id }).await?;
+        let server = call_method(
+            &mut channels,
+            IssueCert {
+                id: ca.id,
+                request: IssueCertRequest {
+                    kind: CertKind::Server,
+                    san: vec!["localhost".parse().unwrap()],
+                    common_name: None,
+                    validity_days: 30,
+                    key_algorithm: KeyAlgorithm::default(),
+                    key_usages: vec![],
+                    extended_key_usages: vec![],
+                },
+            },
+        )
+        .await?;
+
+        let root = reqwest::Certificate::from_pem(&ca_cert.pem_chain)?;
+        let client = || {
+            reqwest::Client::builder()
+                .add_root_certificate(root.clone())
+                .build()
+        };
+        let resp = client()?.get(proxy_port.https_url("/")).send().await?;
+        assert_eq!(resp.status(), 502);
+
+        let issued = call_method(&mut channels, GetIssuedCerts { id: ca.id }).await?;
+        call_method(
+            &mut channels,
+            RevokeIssuedCert {
+                id: ca.id,
+                serial: issued[0].serial.clone(),
+                reason: RevocationReason::KeyCompromise,
+            },
+        )
+        .await?;
+
+        assert!(matches!(
+            call_method(&mut channels, GetCert { id: server.id }).await,
+            Err(Error::IdNotFound { .. })
+        ));
+        assert!(client()?
+            .get(proxy_port.https_url("/"))
+            .send()
+            .await
+            .is_err());
+        Ok(())
+    })
+    .await
+}
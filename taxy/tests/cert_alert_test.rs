@@ -0,0 +1,132 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+use taxy::certs::Cert;
+use taxy_api::{
+    app::{AppConfig, CertExpiryAlertConfig},
+    cert::{CertExpiryStatus, CertKind},
+    event::ServerEvent,
+    port::{Port, PortEntry, PortOptions},
+    tls::TlsTermination,
+};
+use time::OffsetDateTime;
+
+mod common;
+use common::{alloc_port, with_server, TestStorage};
+
+#[tokio::test]
+async fn cert_expiry_alert() -> anyhow::Result<()> {
+    let https_port = alloc_port()?;
+    let now = OffsetDateTime::now_utc();
+    let expiring = new_cert(
+        "expiring.example.com",
+        now - time::Duration::days(80),
+        now + time::Duration::days(3),
+    )?;
+    let expired = new_cert(
+        "expired.example.com",
+        now - time::Duration::days(90),
+        now - time::Duration::days(1),
+    )?;
+    let fine = new_cert(
+        "fine.example.com",
+        now - time::Duration::days(1),
+        now + time::Duration::days(89),
+    )?;
+
+    let mut server = mockito::Server::new_async().await;
+    let expiring_hook = server
+        .mock("POST", "/webhook")
+        .match_header("content-type", "application/json")
+        .match_body(Matcher::PartialJson(json!({
+            "id": expiring.id,
+            "status": "expiring",
+        })))
+        .expect(1)
+        .create_async()
+        .await;
+    let expired_hook = server
+        .mock("POST", "/webhook")
+        .match_header("content-type", "application/json")
+        .match_body(Matcher::PartialJson(json!({
+            "id": expired.id,
+            "status": "expired",
+            "used_by": [{ "name": "expired.example.com", "port": "https" }],
+        })))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            background_task_interval: Duration::from_millis(100),
+            cert_expiry_alert: CertExpiryAlertConfig {
+                webhook_url: Some(format!("{}/webhook", server.url())),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .ports(vec![PortEntry {
+            id: "https".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: https_port.multiaddr_https(),
+                opts: PortOptions {
+                    tls_termination: Some(TlsTermination {
+                        server_names: vec![
+                            "expiring.example.com".into(),
+                            "expired.example.com".into(),
+                            "fine.example.com".into(),
+                        ],
+                    }),
+                },
+            },
+        }])
+        .certs(
+            [expiring.clone(), expired.clone(), fine]
+                .into_iter()
+                .map(|cert| (cert.id, cert))
+                .collect(),
+        )
+        .build();
+
+    with_server(config, |channels| async move {
+        let mut events = channels.event.subscribe();
+        let mut alerts = Vec::new();
+        while alerts.len() < 2 {
+            let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await??;
+            if let ServerEvent::CertExpiryAlerted { alert } = event {
+                alerts.push(alert);
+            }
+        }
+        alerts.sort_by_key(|alert| alert.not_after);
+        assert_eq!(alerts[0].id, expired.id);
+        assert_eq!(alerts[0].status, CertExpiryStatus::Expired);
+        assert_eq!(alerts[1].id, expiring.id);
+        assert_eq!(alerts[1].status, CertExpiryStatus::Expiring);
+
+        // Alerts are not repeated by the following background tasks.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        expiring_hook.assert_async().await;
+        expired_hook.assert_async().await;
+        Ok(())
+    })
+    .await
+}
+
+fn new_cert(
+    name: &str,
+    not_before: OffsetDateTime,
+    not_after: OffsetDateTime,
+) -> anyhow::Result<Arc<Cert>> {
+    let mut params = rcgen::CertificateParams::new(vec![name.to_string()]);
+    params.not_before = not_before;
+    params.not_after = not_after;
+    let cert = rcgen::Certificate::from_params(params)?;
+    Ok(Arc::new(Cert::new(
+        CertKind::Server,
+        cert.serialize_pem()?.into_bytes(),
+        Some(cert.serialize_private_key_pem().into_bytes()),
+    )?))
+}
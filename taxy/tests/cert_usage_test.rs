@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use taxy::{certs::Cert, server::rpc::certs::GetCertUsage};
+use taxy_api::{
+    cert::{CertUsage, KeyAlgorithm},
+    port::{Port, PortEntry, PortOptions},
+    proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind},
+    tls::TlsTermination,
+};
+
+mod common;
+use common::{alloc_port, call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn cert_usage() -> anyhow::Result<()> {
+    let https_port = alloc_port()?;
+    let http_port = alloc_port()?;
+
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default())?);
+    let cert = Arc::new(Cert::new_self_signed(
+        &["localhost".parse()?, "example.com".parse()?],
+        &root,
+        KeyAlgorithm::default(),
+    )?);
+    let unused = Arc::new(Cert::new_self_signed(
+        &["unused.example.com".parse()?],
+        &root,
+        KeyAlgorithm::default(),
+    )?);
+
+    let config = TestStorage::builder()
+        .ports(vec![
+            PortEntry {
+                id: "https".parse().unwrap(),
+                port: Port {
+                    active: true,
+                    name: String::new(),
+                    listen: https_port.multiaddr_https(),
+                    opts: PortOptions {
+                        tls_termination: Some(TlsTermination {
+                            server_names: vec!["localhost".into(), "missing.example.com".into()],
+                        }),
+                    },
+                },
+            },
+            PortEntry {
+                id: "http".parse().unwrap(),
+                port: Port {
+                    active: true,
+                    name: String::new(),
+                    listen: http_port.multiaddr_http(),
+                    opts: PortOptions::default(),
+                },
+            },
+        ])
+        .proxies(vec![ProxyEntry {
+            id: "proxy".parse().unwrap(),
+            proxy: Proxy {
+                ports: vec!["https".parse().unwrap(), "http".parse().unwrap()],
+                kind: ProxyKind::Http(HttpProxy {
+                    vhosts: vec!["example.com".parse()?, "www.example.com".parse()?],
+                    routes: vec![],
+                }),
+                ..Default::default()
+            },
+        }])
+        .certs(
+            [root.clone(), cert.clone(), unused.clone()]
+                .into_iter()
+                .map(|cert| (cert.id, cert))
+                .collect(),
+        )
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let graph = call_method(&mut channels, GetCertUsage).await?;
+        assert_eq!(graph.certs.len(), 2);
+
+        let used = graph
+            .certs
+            .iter()
+            .find(|entry| entry.id == cert.id)
+            .unwrap();
+        assert_eq!(
+            used.used_by,
+            vec![
+                CertUsage {
+                    name: "localhost".parse()?,
+                    port: "https".parse()?,
+                    proxy: None,
+                },
+                CertUsage {
+                    name: "example.com".parse()?,
+                    port: "https".parse()?,
+                    proxy: Some("proxy".parse()?),
+                },
+            ]
+        );
+
+        let unused = graph
+            .certs
+            .iter()
+            .find(|entry| entry.id == unused.id)
+            .unwrap();
+        assert!(unused.used_by.is_empty());
+
+        assert_eq!(
+            graph.uncovered,
+            vec![
+                CertUsage {
+                    name: "missing.example.com".parse()?,
+                    port: "https".parse()?,
+                    proxy: None,
+                },
+                CertUsage {
+                    name: "www.example.com".parse()?,
+                    port: "https".parse()?,
+                    proxy: Some("proxy".parse()?),
+                },
+            ]
+        );
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,113 @@
+use std::{path::Path, time::Duration};
+use taxy::server::{
+    rpc::certs::{DeleteCert, GetCertList},
+    ServerChannels,
+};
+use taxy_api::{
+    app::{AppConfig, CertWatchConfig},
+    cert::CertInfo,
+    error::Error,
+};
+
+mod common;
+use common::{call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn cert_watch() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("taxy-test-{}", rand::random::<u64>()));
+    let site = dir.join("example.com");
+    std::fs::create_dir_all(&site)?;
+    write_cert(&site, "example.com")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            cert_watch: Some(CertWatchConfig {
+                dirs: vec![dir.clone()],
+                interval: Duration::from_millis(100),
+            }),
+            ..Default::default()
+        })
+        .build();
+
+    let result = with_server(config, |mut channels| {
+        let site = site.clone();
+        async move {
+            let certs = wait_for_certs(&mut channels, |certs| certs.len() == 1).await?;
+            let cert = certs[0].clone();
+            assert_eq!(cert.san, vec!["example.com".parse()?]);
+            assert_eq!(cert.source, Some(site.to_string_lossy().into_owned()));
+
+            assert!(matches!(
+                call_method(&mut channels, DeleteCert { id: cert.id }).await,
+                Err(Error::ReadOnlyCert { .. })
+            ));
+
+            // A renewed certificate replaces the old one.
+            write_cert(&site, "www.example.com")?;
+            let certs = wait_for_certs(&mut channels, |certs| {
+                certs.len() == 1 && certs[0].id != cert.id
+            })
+            .await?;
+            assert_eq!(certs[0].san, vec!["www.example.com".parse()?]);
+
+            std::fs::remove_dir_all(&site)?;
+            wait_for_certs(&mut channels, |certs| certs.is_empty()).await?;
+            Ok(())
+        }
+    })
+    .await;
+
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+#[tokio::test]
+async fn cert_watch_zero_interval() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("taxy-test-{}", rand::random::<u64>()));
+    let site = dir.join("example.com");
+    std::fs::create_dir_all(&site)?;
+    write_cert(&site, "example.com")?;
+
+    let config = TestStorage::builder()
+        .config(AppConfig {
+            cert_watch: Some(CertWatchConfig {
+                dirs: vec![dir.clone()],
+                interval: Duration::ZERO,
+            }),
+            ..Default::default()
+        })
+        .build();
+
+    let result = with_server(config, |mut channels| async move {
+        wait_for_certs(&mut channels, |certs| certs.len() == 1).await?;
+        Ok(())
+    })
+    .await;
+
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn write_cert(dir: &Path, name: &str) -> anyhow::Result<()> {
+    let cert = rcgen::generate_simple_self_signed(vec![name.to_string()])?;
+    std::fs::write(dir.join("privkey.pem"), cert.serialize_private_key_pem())?;
+    std::fs::write(dir.join("fullchain.pem"), cert.serialize_pem()?)?;
+    Ok(())
+}
+
+async fn wait_for_certs<F>(
+    channels: &mut ServerChannels,
+    predicate: F,
+) -> anyhow::Result<Vec<CertInfo>>
+where
+    F: Fn(&[CertInfo]) -> bool,
+{
+    for _ in 0..50 {
+        let certs = call_method(channels, GetCertList).await?;
+        if predicate(&certs) {
+            return Ok(certs);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    anyhow::bail!("timed out waiting for certificates")
+}
@@ -0,0 +1,237 @@
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CustomExtension, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa,
+};
+use std::{collections::HashMap, sync::Arc};
+use taxy::{
+    certs::Cert,
+    server::rpc::certs::{GetChainResolver, UploadCert},
+};
+use taxy_api::{cert::CertKind, error::Error};
+
+mod common;
+use common::{call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn chain_from_stored_certs() -> anyhow::Result<()> {
+    let root = new_ca("Test Root")?;
+    let intermediate = new_ca("Test Intermediate")?;
+    let other = new_leaf("other.example.com", None, true)?;
+    let leaf = new_leaf("example.com", None, true)?;
+
+    let root_cert = Arc::new(Cert::new(
+        CertKind::Root,
+        root.serialize_pem()?.into_bytes(),
+        None,
+    )?);
+    let other_cert = Arc::new(Cert::new(
+        CertKind::Server,
+        format!(
+            "{}{}",
+            other.serialize_pem_with_signer(&intermediate)?,
+            intermediate.serialize_pem_with_signer(&root)?
+        )
+        .into_bytes(),
+        Some(other.serialize_private_key_pem().into_bytes()),
+    )?);
+    let config = TestStorage::builder()
+        .certs(
+            [root_cert, other_cert]
+                .into_iter()
+                .map(|cert| (cert.id(), cert))
+                .collect::<HashMap<_, _>>(),
+        )
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let cert = Cert::new(
+            CertKind::Server,
+            leaf.serialize_pem_with_signer(&intermediate)?.into_bytes(),
+            Some(leaf.serialize_private_key_pem().into_bytes()),
+        )?;
+        let result = call_method(&mut channels, UploadCert { cert, strict: true }).await?;
+        assert_eq!(result.warnings, Vec::<String>::new());
+        assert_eq!(
+            result.info.root_cert.as_deref(),
+            Some("CN=Test Intermediate")
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn chain_from_aia() -> anyhow::Result<()> {
+    let root = new_ca("Test Root")?;
+    let intermediate = new_ca("Test Intermediate")?;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/intermediate.der")
+        .with_header("content-type", "application/pkix-cert")
+        .with_body(intermediate.serialize_der_with_signer(&root)?)
+        .create_async()
+        .await;
+    let aia_url = format!("{}/intermediate.der", server.url());
+    let leaf = new_leaf("example.com", Some(&aia_url), true)?;
+
+    let root_cert = Arc::new(Cert::new(
+        CertKind::Root,
+        root.serialize_pem()?.into_bytes(),
+        None,
+    )?);
+    let config = TestStorage::builder()
+        .certs([(root_cert.id(), root_cert)].into_iter().collect())
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let cert = Cert::new(
+            CertKind::Server,
+            leaf.serialize_pem_with_signer(&intermediate)?.into_bytes(),
+            Some(leaf.serialize_private_key_pem().into_bytes()),
+        )?;
+
+        // The server does not fetch missing issuers itself.
+        let result = call_method(
+            &mut channels,
+            UploadCert {
+                cert: cert.clone(),
+                strict: true,
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::InvalidCertChain { .. })));
+
+        let resolver = call_method(&mut channels, GetChainResolver).await?;
+        let cert = resolver.complete(cert).await?;
+        let result = call_method(&mut channels, UploadCert { cert, strict: true }).await?;
+        assert_eq!(result.warnings, Vec::<String>::new());
+        assert_eq!(
+            result.info.root_cert.as_deref(),
+            Some("CN=Test Intermediate")
+        );
+        mock.assert_async().await;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn chain_validation() -> anyhow::Result<()> {
+    let root = new_ca("Test Root")?;
+    let intermediate = new_ca("Test Intermediate")?;
+    let leaf = new_leaf("example.com", None, true)?;
+    let no_eku = new_leaf("example.com", None, false)?;
+    let intermediate_pem = intermediate.serialize_pem_with_signer(&root)?;
+
+    let config = TestStorage::builder().build();
+    with_server(config, |mut channels| async move {
+        // The root is not trusted.
+        let cert = Cert::new(
+            CertKind::Server,
+            format!(
+                "{}{}",
+                leaf.serialize_pem_with_signer(&intermediate)?,
+                intermediate_pem
+            )
+            .into_bytes(),
+            Some(leaf.serialize_private_key_pem().into_bytes()),
+        )?;
+        assert!(matches!(
+            call_method(
+                &mut channels,
+                UploadCert {
+                    cert: cert.clone(),
+                    strict: true
+                }
+            )
+            .await,
+            Err(Error::InvalidCertChain { .. })
+        ));
+        let result = call_method(
+            &mut channels,
+            UploadCert {
+                cert,
+                strict: false,
+            },
+        )
+        .await?;
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].starts_with("certificate chain does not verify"));
+
+        let root_cert = Cert::new(CertKind::Root, root.serialize_pem()?.into_bytes(), None)?;
+        call_method(
+            &mut channels,
+            UploadCert {
+                cert: root_cert,
+                strict: true,
+            },
+        )
+        .await?;
+
+        // The certificate is not allowed for server authentication.
+        let cert = Cert::new(
+            CertKind::Server,
+            format!(
+                "{}{}",
+                no_eku.serialize_pem_with_signer(&intermediate)?,
+                intermediate_pem
+            )
+            .into_bytes(),
+            Some(no_eku.serialize_private_key_pem().into_bytes()),
+        )?;
+        let result = call_method(
+            &mut channels,
+            UploadCert {
+                cert,
+                strict: false,
+            },
+        )
+        .await?;
+        assert_eq!(
+            result.warnings,
+            vec!["certificate lacks the serverAuth extended key usage".to_string()]
+        );
+        Ok(())
+    })
+    .await
+}
+
+fn new_ca(name: &str) -> anyhow::Result<Certificate> {
+    let mut params = CertificateParams::default();
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.distinguished_name = DistinguishedName::new();
+    params.distinguished_name.push(DnType::CommonName, name);
+    Ok(Certificate::from_params(params)?)
+}
+
+fn new_leaf(name: &str, aia_url: Option<&str>, server_auth: bool) -> anyhow::Result<Certificate> {
+    let mut params = CertificateParams::new(vec![name.to_string()]);
+    params.distinguished_name = DistinguishedName::new();
+    params.distinguished_name.push(DnType::CommonName, name);
+    if server_auth {
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    }
+    if let Some(url) = aia_url {
+        params.custom_extensions = vec![CustomExtension::from_oid_content(
+            &[1, 3, 6, 1, 5, 5, 7, 1, 1],
+            authority_info_access(url),
+        )];
+    }
+    Ok(Certificate::from_params(params)?)
+}
+
+/// Encodes an Authority Information Access extension with a single CA Issuers URL.
+fn authority_info_access(url: &str) -> Vec<u8> {
+    const CA_ISSUERS: &[u8] = &[0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x02];
+    let mut location = vec![0x86, url.len() as u8];
+    location.extend_from_slice(url.as_bytes());
+    let description = der_sequence(&[CA_ISSUERS, &location].concat());
+    der_sequence(&description)
+}
+
+fn der_sequence(content: &[u8]) -> Vec<u8> {
+    let mut der = vec![0x30, content.len() as u8];
+    der.extend_from_slice(content);
+    der
+}
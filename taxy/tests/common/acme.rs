@@ -0,0 +1,751 @@
+//! A minimal Pebble-style ACME server for integration tests.
+//!
+//! JWS signatures are not verified; the server only decodes the protected
+//! header and payload. Challenges are validated against the addresses given
+//! in [`AcmeValidation`]; dns-01 records are published through the webhook
+//! at [`AcmeServer::dns_webhook_url`].
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hyper::{Body, Method, Response, StatusCode};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CertificateSigningRequest, DistinguishedName,
+    DnType, IsCa,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    client::TlsStream,
+    rustls::{
+        client::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        ClientConfig, DigitallySignedStruct, ServerName,
+    },
+    TlsConnector,
+};
+use warp::{path::FullPath, Filter};
+use x509_parser::{
+    certificate::X509Certificate,
+    extensions::{GeneralName, ParsedExtension},
+    parse_x509_certificate,
+    pem::parse_x509_pem,
+};
+
+const OID_PE_ACME: &str = "1.3.6.1.5.5.7.1.31";
+
+#[derive(Debug, Default, Clone)]
+pub struct AcmeValidation {
+    pub http_01: Option<SocketAddr>,
+    pub tls_alpn_01: Option<SocketAddr>,
+    /// Protocols offered by an ordinary handshake to the tls-alpn-01 target
+    /// right before each validation.
+    pub tls_alpn_01_probe: Vec<Vec<u8>>,
+    /// The `Authorization` header required by the DNS webhook.
+    pub dns_webhook_auth: Option<String>,
+}
+
+pub struct AcmeServer {
+    base_url: String,
+    ca: Arc<Certificate>,
+    state: Arc<Mutex<State>>,
+}
+
+impl AcmeServer {
+    pub async fn start(validation: AcmeValidation) -> anyhow::Result<Self> {
+        let port = super::alloc_port()?;
+        let base_url = format!("http://localhost:{}", port.socket_addr().port());
+
+        let mut params = CertificateParams::default();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let mut distinguished_name = DistinguishedName::new();
+        distinguished_name.push(DnType::CommonName, "Test ACME CA");
+        params.distinguished_name = distinguished_name;
+        let ca = Arc::new(Certificate::from_params(params)?);
+
+        let state = Arc::new(Mutex::new(State {
+            base_url: base_url.clone(),
+            validation,
+            ca: ca.clone(),
+            nonce: 0,
+            accounts: Vec::new(),
+            orders: Vec::new(),
+            txt_records: Vec::new(),
+            renewal_due: false,
+            renewal_info_requests: 0,
+            revoked: Vec::new(),
+            tls_alpn_01_probes: Vec::new(),
+        }));
+
+        let server_state = state.clone();
+        let routes = warp::any()
+            .and(warp::method())
+            .and(warp::path::full())
+            .and(warp::body::bytes())
+            .and(warp::header::optional::<String>("authorization"))
+            .map(
+                move |method: Method,
+                      path: FullPath,
+                      body: hyper::body::Bytes,
+                      auth: Option<String>| {
+                    handle(&state, method, path.as_str(), &body, auth)
+                },
+            );
+        let (_, server) = warp::serve(routes).bind_ephemeral(port.socket_addr());
+        tokio::spawn(server);
+
+        Ok(Self {
+            base_url,
+            ca,
+            state: server_state,
+        })
+    }
+
+    pub fn directory_url(&self) -> String {
+        format!("{}/directory", self.base_url)
+    }
+
+    pub fn dns_webhook_url(&self) -> String {
+        format!("{}/dns", self.base_url)
+    }
+
+    /// Makes the renewal window of every certificate lie in the past.
+    pub fn set_renewal_due(&self, due: bool) {
+        self.state.lock().unwrap().renewal_due = due;
+    }
+
+    /// Returns how many times the renewal information was requested.
+    pub fn renewal_info_requests(&self) -> usize {
+        self.state.lock().unwrap().renewal_info_requests
+    }
+
+    /// Returns whether each probe handshake sent before a tls-alpn-01
+    /// validation succeeded.
+    pub fn tls_alpn_01_probes(&self) -> Vec<bool> {
+        self.state.lock().unwrap().tls_alpn_01_probes.clone()
+    }
+
+    /// Returns the reason codes of the revoked certificates.
+    pub fn revocation_reasons(&self) -> Vec<u64> {
+        let state = self.state.lock().unwrap();
+        state.revoked.iter().map(|(_, reason)| *reason).collect()
+    }
+
+    pub fn ca_pem(&self) -> String {
+        self.ca.serialize_pem().unwrap()
+    }
+}
+
+struct State {
+    base_url: String,
+    validation: AcmeValidation,
+    ca: Arc<Certificate>,
+    nonce: u64,
+    accounts: Vec<Account>,
+    orders: Vec<Order>,
+    txt_records: Vec<(String, String)>,
+    renewal_due: bool,
+    renewal_info_requests: usize,
+    revoked: Vec<(String, u64)>,
+    tls_alpn_01_probes: Vec<bool>,
+}
+
+struct Account {
+    thumbprint: String,
+    contacts: Vec<String>,
+    status: &'static str,
+}
+
+impl Account {
+    fn json(&self) -> Value {
+        json!({ "status": self.status, "contact": self.contacts })
+    }
+}
+
+struct Order {
+    account: usize,
+    status: &'static str,
+    authorizations: Vec<Authorization>,
+    certificate: Option<String>,
+    cert_id: Option<String>,
+}
+
+struct Authorization {
+    identifier: String,
+    wildcard: bool,
+    status: &'static str,
+    token: String,
+}
+
+const CHALLENGE_TYPES: &[&str] = &["http-01", "dns-01", "tls-alpn-01"];
+
+impl State {
+    fn order_json(&self, id: usize) -> Value {
+        let order = &self.orders[id];
+        let mut value = json!({
+            "status": order.status,
+            "identifiers": order.authorizations.iter().map(|authz| json!({
+                "type": "dns",
+                "value": if authz.wildcard {
+                    format!("*.{}", authz.identifier)
+                } else {
+                    authz.identifier.clone()
+                },
+            })).collect::<Vec<_>>(),
+            "authorizations": (0..order.authorizations.len())
+                .map(|i| format!("{}/authz/{id}/{i}", self.base_url))
+                .collect::<Vec<_>>(),
+            "finalize": format!("{}/finalize/{id}", self.base_url),
+        });
+        if order.certificate.is_some() {
+            value["certificate"] = json!(format!("{}/cert/{id}", self.base_url));
+        }
+        value
+    }
+
+    fn challenge_json(&self, id: usize, index: usize, kind: &str) -> Value {
+        let authz = &self.orders[id].authorizations[index];
+        let status = match authz.status {
+            "pending" => "pending",
+            "valid" => "valid",
+            _ => "invalid",
+        };
+        json!({
+            "type": kind,
+            "url": format!("{}/chall/{id}/{index}/{kind}", self.base_url),
+            "token": authz.token,
+            "status": status,
+        })
+    }
+
+    fn authz_json(&self, id: usize, index: usize) -> Value {
+        let authz = &self.orders[id].authorizations[index];
+        json!({
+            "identifier": { "type": "dns", "value": authz.identifier },
+            "status": authz.status,
+            "wildcard": authz.wildcard,
+            "challenges": CHALLENGE_TYPES
+                .iter()
+                .filter(|kind| !authz.wildcard || **kind == "dns-01")
+                .map(|kind| self.challenge_json(id, index, kind))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn update_order_status(&mut self, id: usize) {
+        let order = &mut self.orders[id];
+        if order
+            .authorizations
+            .iter()
+            .any(|authz| authz.status == "invalid")
+        {
+            order.status = "invalid";
+        } else if order
+            .authorizations
+            .iter()
+            .all(|authz| authz.status == "valid")
+        {
+            order.status = "ready";
+        }
+    }
+}
+
+struct Jws {
+    header: Value,
+    payload: Option<Value>,
+}
+
+fn decode_jws(body: &[u8]) -> Option<Jws> {
+    let body: Value = serde_json::from_slice(body).ok()?;
+    let header = URL_SAFE_NO_PAD.decode(body["protected"].as_str()?).ok()?;
+    let payload = URL_SAFE_NO_PAD.decode(body["payload"].as_str()?).ok()?;
+    Some(Jws {
+        header: serde_json::from_slice(&header).ok()?,
+        payload: serde_json::from_slice(&payload).ok(),
+    })
+}
+
+fn contacts(payload: Option<&Value>) -> Vec<String> {
+    payload
+        .and_then(|payload| payload["contact"].as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|contact| contact.as_str().map(|contact| contact.to_string()))
+        .collect()
+}
+
+fn jwk_thumbprint(jwk: &Value) -> String {
+    let thumb = format!(
+        r#"{{"crv":"{}","kty":"{}","x":"{}","y":"{}"}}"#,
+        jwk["crv"].as_str().unwrap_or_default(),
+        jwk["kty"].as_str().unwrap_or_default(),
+        jwk["x"].as_str().unwrap_or_default(),
+        jwk["y"].as_str().unwrap_or_default(),
+    );
+    URL_SAFE_NO_PAD.encode(Sha256::digest(thumb.as_bytes()))
+}
+
+fn handle(
+    state: &Arc<Mutex<State>>,
+    method: Method,
+    path: &str,
+    body: &[u8],
+    auth: Option<String>,
+) -> Response<Body> {
+    let mut guard = state.lock().unwrap();
+    guard.nonce += 1;
+    let nonce = format!("nonce-{}", guard.nonce);
+    let base_url = guard.base_url.clone();
+
+    let segments = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    let jws = decode_jws(body);
+    let account = jws
+        .as_ref()
+        .and_then(|jws| jws.header["kid"].as_str())
+        .and_then(|kid| kid.rsplit('/').next())
+        .and_then(|id| id.parse::<usize>().ok())
+        .filter(|id| *id < guard.accounts.len());
+    let active_account = account.filter(|id| guard.accounts[*id].status == "valid");
+
+    let (status, location, body) = match (method, segments.as_slice()) {
+        (Method::GET, ["directory"]) => (
+            StatusCode::OK,
+            None,
+            json!({
+                "newNonce": format!("{base_url}/new-nonce"),
+                "newAccount": format!("{base_url}/new-acct"),
+                "newOrder": format!("{base_url}/new-order"),
+                "renewalInfo": format!("{base_url}/renewal-info"),
+                "revokeCert": format!("{base_url}/revoke-cert"),
+                "keyChange": format!("{base_url}/key-change"),
+            })
+            .to_string(),
+        ),
+        (Method::HEAD, ["new-nonce"]) | (Method::GET, ["new-nonce"]) => {
+            (StatusCode::OK, None, String::new())
+        }
+        (Method::POST, ["new-acct"]) => {
+            let jws = jws.unwrap_or(Jws {
+                header: Value::Null,
+                payload: None,
+            });
+            guard.accounts.push(Account {
+                thumbprint: jwk_thumbprint(&jws.header["jwk"]),
+                contacts: contacts(jws.payload.as_ref()),
+                status: "valid",
+            });
+            let id = guard.accounts.len() - 1;
+            (
+                StatusCode::CREATED,
+                Some(format!("{base_url}/acct/{id}")),
+                guard.accounts[id].json().to_string(),
+            )
+        }
+        (Method::POST, ["acct", id]) => match (id.parse::<usize>().ok(), account) {
+            (Some(id), Some(account)) if id == account => {
+                let payload = jws.and_then(|jws| jws.payload);
+                let account = &mut guard.accounts[id];
+                if let Some(payload) = payload.filter(|_| account.status == "valid") {
+                    if payload.get("contact").is_some() {
+                        account.contacts = contacts(Some(&payload));
+                    }
+                    if payload["status"] == "deactivated" {
+                        account.status = "deactivated";
+                    }
+                }
+                (StatusCode::OK, None, account.json().to_string())
+            }
+            _ => problem("unauthorized", StatusCode::UNAUTHORIZED),
+        },
+        (Method::POST, ["key-change"]) if active_account.is_some() => {
+            let kid = jws
+                .as_ref()
+                .and_then(|jws| jws.header["kid"].as_str())
+                .map(|kid| kid.to_string());
+            let inner = jws
+                .and_then(|jws| jws.payload)
+                .and_then(|payload| decode_jws(&serde_json::to_vec(&payload).unwrap()))
+                .filter(|inner| {
+                    let payload = inner.payload.as_ref();
+                    payload.and_then(|payload| payload["account"].as_str()) == kid.as_deref()
+                });
+            match inner {
+                Some(inner) => {
+                    guard.accounts[active_account.unwrap()].thumbprint =
+                        jwk_thumbprint(&inner.header["jwk"]);
+                    (StatusCode::OK, None, String::new())
+                }
+                None => problem("malformed", StatusCode::BAD_REQUEST),
+            }
+        }
+        (Method::POST, ["new-order"]) if active_account.is_some() => {
+            let identifiers = jws
+                .and_then(|jws| jws.payload)
+                .and_then(|payload| payload["identifiers"].as_array().cloned())
+                .unwrap_or_default();
+            let authorizations = identifiers
+                .iter()
+                .filter_map(|id| id["value"].as_str())
+                .map(|identifier| Authorization {
+                    identifier: identifier.trim_start_matches("*.").to_string(),
+                    wildcard: identifier.starts_with("*."),
+                    status: "pending",
+                    token: URL_SAFE_NO_PAD.encode(rand::random::<[u8; 16]>()),
+                })
+                .collect();
+            guard.orders.push(Order {
+                account: active_account.unwrap(),
+                status: "pending",
+                authorizations,
+                certificate: None,
+                cert_id: None,
+            });
+            let id = guard.orders.len() - 1;
+            (
+                StatusCode::CREATED,
+                Some(format!("{base_url}/order/{id}")),
+                guard.order_json(id).to_string(),
+            )
+        }
+        (Method::POST, ["order", id]) => match id.parse::<usize>() {
+            Ok(id) if id < guard.orders.len() => {
+                (StatusCode::OK, None, guard.order_json(id).to_string())
+            }
+            _ => not_found(),
+        },
+        (Method::POST, ["authz", id, index]) => {
+            match (id.parse::<usize>(), index.parse::<usize>()) {
+                (Ok(id), Ok(index))
+                    if id < guard.orders.len() && index < guard.orders[id].authorizations.len() =>
+                {
+                    (
+                        StatusCode::OK,
+                        None,
+                        guard.authz_json(id, index).to_string(),
+                    )
+                }
+                _ => not_found(),
+            }
+        }
+        (Method::POST, ["chall", id, index, kind]) => {
+            match (id.parse::<usize>(), index.parse::<usize>()) {
+                (Ok(id), Ok(index))
+                    if id < guard.orders.len() && index < guard.orders[id].authorizations.len() =>
+                {
+                    let order = &guard.orders[id];
+                    let authz = &order.authorizations[index];
+                    let key_authorization = format!(
+                        "{}.{}",
+                        authz.token, guard.accounts[order.account].thumbprint
+                    );
+                    let target = match *kind {
+                        "http-01" => guard.validation.http_01,
+                        "tls-alpn-01" => guard.validation.tls_alpn_01,
+                        _ => None,
+                    };
+                    let dns_record = (
+                        format!("_acme-challenge.{}.", authz.identifier),
+                        URL_SAFE_NO_PAD.encode(Sha256::digest(key_authorization.as_bytes())),
+                    );
+                    let challenge = guard.challenge_json(id, index, kind);
+                    let state = state.clone();
+                    let kind = kind.to_string();
+                    let identifier = authz.identifier.clone();
+                    let token = authz.token.clone();
+                    let probe = guard.validation.tls_alpn_01_probe.clone();
+                    tokio::spawn(async move {
+                        if let ("tls-alpn-01", Some(addr), false) =
+                            (kind.as_str(), target, probe.is_empty())
+                        {
+                            let ok = tls_handshake(addr, &identifier, probe).await.is_some();
+                            state.lock().unwrap().tls_alpn_01_probes.push(ok);
+                        }
+                        let valid = match (kind.as_str(), target) {
+                            ("http-01", Some(addr)) => {
+                                validate_http_01(addr, &identifier, &token, &key_authorization)
+                                    .await
+                            }
+                            ("tls-alpn-01", Some(addr)) => {
+                                validate_tls_alpn_01(addr, &identifier, &key_authorization).await
+                            }
+                            ("dns-01", _) => {
+                                state.lock().unwrap().txt_records.contains(&dns_record)
+                            }
+                            _ => false,
+                        };
+                        let mut state = state.lock().unwrap();
+                        state.orders[id].authorizations[index].status =
+                            if valid { "valid" } else { "invalid" };
+                        state.update_order_status(id);
+                    });
+                    (StatusCode::OK, None, challenge.to_string())
+                }
+                _ => not_found(),
+            }
+        }
+        (Method::POST, ["finalize", id]) => match id.parse::<usize>() {
+            Ok(id) if id < guard.orders.len() && guard.orders[id].status == "ready" => {
+                let csr = jws
+                    .and_then(|jws| jws.payload)
+                    .and_then(|payload| payload["csr"].as_str().map(|csr| csr.to_string()))
+                    .and_then(|csr| URL_SAFE_NO_PAD.decode(csr).ok())
+                    .and_then(|csr| CertificateSigningRequest::from_der(&csr).ok());
+                match csr {
+                    Some(mut csr) => {
+                        csr.params.use_authority_key_identifier_extension = true;
+                        let cert = csr.serialize_pem_with_signer(&guard.ca).unwrap();
+                        let (_, pem) = parse_x509_pem(cert.as_bytes()).unwrap();
+                        let cert_id = ari_cert_id(&pem.parse_x509().unwrap());
+                        let ca = guard.ca.serialize_pem().unwrap();
+                        let order = &mut guard.orders[id];
+                        order.certificate = Some(format!("{cert}{ca}"));
+                        order.cert_id = Some(cert_id);
+                        order.status = "valid";
+                        (StatusCode::OK, None, guard.order_json(id).to_string())
+                    }
+                    None => problem("badCSR", StatusCode::BAD_REQUEST),
+                }
+            }
+            _ => problem("orderNotReady", StatusCode::FORBIDDEN),
+        },
+        (Method::POST, ["cert", id]) => match id
+            .parse::<usize>()
+            .ok()
+            .and_then(|id| guard.orders.get(id))
+            .and_then(|order| order.certificate.clone())
+        {
+            Some(chain) => (StatusCode::OK, None, chain),
+            None => not_found(),
+        },
+        (Method::GET, ["renewal-info", cert_id]) => {
+            guard.renewal_info_requests += 1;
+            if guard
+                .orders
+                .iter()
+                .any(|order| order.cert_id.as_deref() == Some(*cert_id))
+            {
+                let (start, end) = if guard.renewal_due {
+                    ("2000-01-01T00:00:00Z", "2000-01-02T00:00:00Z")
+                } else {
+                    ("2999-01-01T00:00:00Z", "2999-01-02T00:00:00Z")
+                };
+                (
+                    StatusCode::OK,
+                    None,
+                    json!({ "suggestedWindow": { "start": start, "end": end } }).to_string(),
+                )
+            } else {
+                not_found()
+            }
+        }
+        (Method::POST, ["revoke-cert"]) if account.is_some() => {
+            let payload = jws.and_then(|jws| jws.payload).unwrap_or_default();
+            let cert_id = payload["certificate"]
+                .as_str()
+                .and_then(|cert| URL_SAFE_NO_PAD.decode(cert).ok())
+                .and_then(|der| {
+                    parse_x509_certificate(&der)
+                        .ok()
+                        .map(|(_, cert)| ari_cert_id(&cert))
+                })
+                .filter(|cert_id| {
+                    guard
+                        .orders
+                        .iter()
+                        .any(|order| order.cert_id.as_ref() == Some(cert_id))
+                });
+            match cert_id {
+                Some(cert_id) if guard.revoked.iter().any(|(id, _)| *id == cert_id) => {
+                    problem("alreadyRevoked", StatusCode::BAD_REQUEST)
+                }
+                Some(cert_id) => {
+                    let reason = payload["reason"].as_u64().unwrap_or_default();
+                    guard.revoked.push((cert_id, reason));
+                    (StatusCode::OK, None, String::new())
+                }
+                None => not_found(),
+            }
+        }
+        (Method::POST, ["dns", _])
+            if guard.validation.dns_webhook_auth.is_some()
+                && guard.validation.dns_webhook_auth != auth =>
+        {
+            problem("unauthorized", StatusCode::UNAUTHORIZED)
+        }
+        (Method::POST, ["dns", action]) => {
+            let record = serde_json::from_slice::<Value>(body)
+                .ok()
+                .and_then(|record| {
+                    Some((
+                        record["fqdn"].as_str()?.to_string(),
+                        record["value"].as_str()?.to_string(),
+                    ))
+                });
+            match (*action, record) {
+                ("present", Some(record)) => {
+                    guard.txt_records.push(record);
+                    (StatusCode::OK, None, String::new())
+                }
+                ("cleanup", Some(record)) => {
+                    guard.txt_records.retain(|r| *r != record);
+                    (StatusCode::OK, None, String::new())
+                }
+                _ => problem("malformed", StatusCode::BAD_REQUEST),
+            }
+        }
+        _ => not_found(),
+    };
+
+    let mut builder = Response::builder()
+        .status(status)
+        .header("Replay-Nonce", nonce)
+        .header("Cache-Control", "no-store");
+    if segments.first() == Some(&"renewal-info") {
+        builder = builder.header("Retry-After", "3600");
+    }
+    if let Some(location) = location {
+        builder = builder.header("Location", location);
+    }
+    builder.body(Body::from(body)).unwrap()
+}
+
+fn ari_cert_id(cert: &X509Certificate) -> String {
+    let key_id = cert
+        .extensions()
+        .iter()
+        .find_map(|ext| match ext.parsed_extension() {
+            ParsedExtension::AuthorityKeyIdentifier(aki) => aki.key_identifier.clone(),
+            _ => None,
+        })
+        .unwrap();
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(key_id.0),
+        URL_SAFE_NO_PAD.encode(cert.raw_serial())
+    )
+}
+
+fn not_found() -> (StatusCode, Option<String>, String) {
+    problem("malformed", StatusCode::NOT_FOUND)
+}
+
+fn problem(kind: &str, status: StatusCode) -> (StatusCode, Option<String>, String) {
+    (
+        status,
+        None,
+        json!({
+            "type": format!("urn:ietf:params:acme:error:{kind}"),
+            "detail": kind,
+            "status": status.as_u16(),
+        })
+        .to_string(),
+    )
+}
+
+async fn validate_http_01(
+    addr: SocketAddr,
+    identifier: &str,
+    token: &str,
+    key_authorization: &str,
+) -> bool {
+    let url = format!("http://{addr}/.well-known/acme-challenge/{token}");
+    let client = reqwest::Client::new();
+    match client.get(url).header("Host", identifier).send().await {
+        Ok(res) => res.text().await.ok().as_deref() == Some(key_authorization),
+        Err(_) => false,
+    }
+}
+
+async fn tls_handshake(
+    addr: SocketAddr,
+    identifier: &str,
+    alpn_protocols: Vec<Vec<u8>>,
+) -> Option<TlsStream<TcpStream>> {
+    let mut config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(NoVerifier))
+        .with_no_client_auth();
+    config.alpn_protocols = alpn_protocols;
+
+    let server_name = ServerName::try_from(identifier).ok()?;
+    let stream = TcpStream::connect(addr).await.ok()?;
+    TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .ok()
+}
+
+async fn validate_tls_alpn_01(addr: SocketAddr, identifier: &str, key_authorization: &str) -> bool {
+    let Some(stream) = tls_handshake(addr, identifier, vec![b"acme-tls/1".to_vec()]).await else {
+        return false;
+    };
+
+    let (_, conn) = stream.get_ref();
+    if conn.alpn_protocol() != Some(b"acme-tls/1") {
+        return false;
+    }
+    let Some(cert) = conn.peer_certificates().and_then(|certs| certs.first()) else {
+        return false;
+    };
+    let Ok((_, cert)) = parse_x509_certificate(&cert.0) else {
+        return false;
+    };
+
+    let san_matches = cert
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|san| {
+            san.value.general_names.len() == 1
+                && san.value.general_names[0] == GeneralName::DNSName(identifier)
+        })
+        .unwrap_or_default();
+
+    let mut expected = vec![0x04, 0x20];
+    expected.extend_from_slice(&Sha256::digest(key_authorization.as_bytes()));
+    let identifier_matches = cert
+        .extensions()
+        .iter()
+        .any(|ext| ext.oid.to_id_string() == OID_PE_ACME && ext.critical && ext.value == expected);
+
+    san_matches && identifier_matches
+}
+
+struct NoVerifier;
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &tokio_rustls::rustls::Certificate,
+        _intermediates: &[tokio_rustls::rustls::Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    // webpki rejects the critical acmeIdentifier extension, so the handshake
+    // signatures can't be checked against the challenge certificate.
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &tokio_rustls::rustls::Certificate,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &tokio_rustls::rustls::Certificate,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+}
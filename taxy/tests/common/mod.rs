@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+pub mod acme;
+
 use futures::Future;
 use net2::TcpBuilder;
 use std::{
@@ -9,14 +11,24 @@ use std::{
     sync::Arc,
 };
 use taxy::{
-    certs::{acme::AcmeEntry, Cert},
+    certs::{
+        acme::{AcmeAccountEntry, AcmeEntry},
+        ca::CaEntry,
+        csr::CsrEntry,
+        Cert,
+    },
+    command::ServerCommand,
     config::{new_appinfo, storage::Storage},
-    server::{Server, ServerChannels},
+    server::{
+        rpc::{ErasedRpcMethod, RpcMethod, RpcWrapper},
+        Server, ServerChannels,
+    },
 };
 use taxy_api::{
-    app::AppConfig,
+    app::{AppConfig, AppInfo},
     auth::{Account, LoginMethod, LoginRequest, LoginResponse},
     error::Error,
+    history::ConfigVersion,
     id::ShortId,
     multiaddr::Multiaddr,
     port::PortEntry,
@@ -32,7 +44,17 @@ where
     F: FnOnce(ServerChannels) -> O,
     O: Future<Output = anyhow::Result<()>> + Send + 'static,
 {
-    let app_info = new_appinfo(Path::new("."), Path::new("."));
+    // Keep the log database out of the source tree.
+    let log_path = std::env::temp_dir();
+    with_server_app_info(new_appinfo(Path::new("."), &log_path), s, func).await
+}
+
+pub async fn with_server_app_info<S, F, O>(app_info: AppInfo, s: S, func: F) -> anyhow::Result<()>
+where
+    S: Storage,
+    F: FnOnce(ServerChannels) -> O,
+    O: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
     let (server, channels) = Server::new(app_info, s).await;
     let event_send = channels.event.clone();
     let task = tokio::spawn(server.start());
@@ -42,6 +64,32 @@ where
     Ok(())
 }
 
+pub async fn call_method<T>(channels: &mut ServerChannels, method: T) -> Result<T::Output, Error>
+where
+    T: RpcMethod,
+{
+    let arg = Box::new(RpcWrapper::new(method)) as Box<dyn ErasedRpcMethod>;
+    channels
+        .command
+        .send(ServerCommand::CallMethod {
+            id: 0,
+            author: None,
+            arg,
+        })
+        .await
+        .map_err(|_| Error::FailedToInvokeRpc)?;
+    let callback = channels
+        .callback
+        .recv()
+        .await
+        .ok_or(Error::FailedToInvokeRpc)?;
+    callback
+        .result?
+        .downcast()
+        .map(|output| *output)
+        .map_err(|_| Error::FailedToInvokeRpc)
+}
+
 #[derive(Debug, Default)]
 pub struct TestStorage {
     inner: Mutex<Inner>,
@@ -54,7 +102,11 @@ struct Inner {
     pub proxies: Vec<ProxyEntry>,
     pub certs: HashMap<ShortId, Arc<Cert>>,
     pub acems: HashMap<ShortId, AcmeEntry>,
+    pub acme_accounts: HashMap<ShortId, AcmeAccountEntry>,
+    pub cas: HashMap<ShortId, CaEntry>,
+    pub csrs: HashMap<ShortId, CsrEntry>,
     pub accounts: HashMap<String, String>,
+    pub history: Vec<ConfigVersion>,
 }
 
 impl TestStorage {
@@ -117,6 +169,52 @@ impl Storage for TestStorage {
         self.inner.lock().await.acems.values().cloned().collect()
     }
 
+    async fn save_acme_account(&self, account: &AcmeAccountEntry) {
+        self.inner
+            .lock()
+            .await
+            .acme_accounts
+            .insert(account.id, account.clone());
+    }
+
+    async fn delete_acme_account(&self, id: ShortId) {
+        self.inner.lock().await.acme_accounts.remove(&id);
+    }
+
+    async fn load_acme_accounts(&self) -> Vec<AcmeAccountEntry> {
+        self.inner
+            .lock()
+            .await
+            .acme_accounts
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    async fn save_ca(&self, ca: &CaEntry) {
+        self.inner.lock().await.cas.insert(ca.id, ca.clone());
+    }
+
+    async fn delete_ca(&self, id: ShortId) {
+        self.inner.lock().await.cas.remove(&id);
+    }
+
+    async fn load_cas(&self) -> Vec<CaEntry> {
+        self.inner.lock().await.cas.values().cloned().collect()
+    }
+
+    async fn save_csr(&self, csr: &CsrEntry) {
+        self.inner.lock().await.csrs.insert(csr.id, csr.clone());
+    }
+
+    async fn delete_csr(&self, id: ShortId) {
+        self.inner.lock().await.csrs.remove(&id);
+    }
+
+    async fn load_csrs(&self) -> Vec<CsrEntry> {
+        self.inner.lock().await.csrs.values().cloned().collect()
+    }
+
     async fn load_certs(&self) -> Vec<Arc<Cert>> {
         self.inner.lock().await.certs.values().cloned().collect()
     }
@@ -146,6 +244,22 @@ impl Storage for TestStorage {
         }
         Err(Error::InvalidLoginCredentials)
     }
+
+    async fn save_config_version(&self, version: &ConfigVersion) {
+        self.inner.lock().await.history.push(version.clone());
+    }
+
+    async fn delete_config_version(&self, version: u64) {
+        self.inner
+            .lock()
+            .await
+            .history
+            .retain(|entry| entry.version != version);
+    }
+
+    async fn load_config_history(&self) -> Vec<ConfigVersion> {
+        self.inner.lock().await.history.clone()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -185,6 +299,11 @@ impl TestStorageBuilder {
         self
     }
 
+    pub fn acme_accounts(mut self, acme_accounts: HashMap<ShortId, AcmeAccountEntry>) -> Self {
+        self.inner.acme_accounts = acme_accounts;
+        self
+    }
+
     pub fn accounts(mut self, accounts: HashMap<String, String>) -> Self {
         self.inner.accounts = accounts;
         self
@@ -0,0 +1,175 @@
+use std::time::Duration;
+use taxy::certs::acme::AcmeEntry;
+use taxy::command::ServerCommand;
+use taxy::config::file::FileStorage;
+use taxy::config::managed::{self, ManagedStorage};
+use taxy::config::new_appinfo;
+use taxy::config::storage::Storage;
+use taxy::server::rpc::history::GetConfigHistory;
+use taxy::server::rpc::ports::{DeletePort, GetPortList};
+use taxy::server::rpc::proxies::GetProxyList;
+use taxy::server::ServerChannels;
+use taxy_api::{
+    acme::{Acme, AcmeConfig},
+    error::Error,
+    port::PortEntry,
+};
+
+mod common;
+use common::{alloc_port, call_method, with_server_app_info, TestStorage};
+
+#[tokio::test]
+async fn config_file() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("taxy-test-{}", rand::random::<u64>()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("taxy.toml");
+
+    let http_port = alloc_port()?;
+    let new_port = alloc_port()?;
+    let ports = format!(
+        r#"
+[[ports]]
+id = "http"
+listen = "{}"
+
+[[proxies]]
+id = "proxy"
+ports = ["http"]
+protocol = "http"
+vhosts = ["example.com"]
+routes = [{{ path = "/", servers = [{{ url = "http://127.0.0.1:3000" }}] }}]
+"#,
+        http_port.multiaddr_http()
+    );
+    std::fs::write(&path, &ports)?;
+
+    let config = ManagedStorage::new(TestStorage::builder().build(), &path).await?;
+    let mut app_info = new_appinfo(&dir, &dir);
+    app_info.config_file = Some(path.clone());
+
+    let result = with_server_app_info(app_info, config, |mut channels| {
+        let path = path.clone();
+        async move {
+            assert_eq!(call_method(&mut channels, GetPortList).await?.len(), 1);
+            assert_eq!(call_method(&mut channels, GetProxyList).await?.len(), 1);
+
+            assert!(matches!(
+                call_method(
+                    &mut channels,
+                    DeletePort {
+                        id: "http".parse().unwrap()
+                    }
+                )
+                .await,
+                Err(Error::ManagedByConfigFile { .. })
+            ));
+
+            // An invalid file leaves the current config untouched.
+            let mut snapshot = managed::load(&path).await?;
+            snapshot.ports.push(snapshot.ports[0].clone());
+            channels
+                .command
+                .send(ServerCommand::ApplyConfigFile {
+                    snapshot: Box::new(snapshot),
+                })
+                .await?;
+            assert_eq!(call_method(&mut channels, GetPortList).await?.len(), 1);
+
+            std::fs::write(
+                &path,
+                format!(
+                    "[[ports]]\nid = \"new\"\nlisten = \"{}\"\n",
+                    new_port.multiaddr_http()
+                ),
+            )?;
+            let ports = wait_for_ports(&mut channels, |ports| {
+                ports.len() == 1 && ports[0].id.to_string() == "new"
+            })
+            .await?;
+            assert_eq!(ports[0].port.listen, new_port.multiaddr_http());
+            assert!(call_method(&mut channels, GetProxyList).await?.is_empty());
+
+            let history = call_method(&mut channels, GetConfigHistory).await?;
+            assert_eq!(history.len(), 2);
+            Ok(())
+        }
+    })
+    .await;
+
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+async fn wait_for_ports<F>(channels: &mut ServerChannels, pred: F) -> anyhow::Result<Vec<PortEntry>>
+where
+    F: Fn(&[PortEntry]) -> bool,
+{
+    for _ in 0..150 {
+        let ports = call_method(channels, GetPortList).await?;
+        if pred(&ports) {
+            return Ok(ports);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    anyhow::bail!("config file not reloaded")
+}
+
+#[tokio::test]
+async fn config_file_keeps_on_demand_acmes() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("taxy-test-{}", rand::random::<u64>()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("taxy.toml");
+    std::fs::write(
+        &path,
+        r#"
+[[acme]]
+id = "managed"
+account_id = "account"
+identifiers = ["example.com"]
+challenge_type = "http-01"
+"#,
+    )?;
+
+    let acme = |id: &str, provider: &str, name: &str| {
+        AcmeEntry::new(
+            id.parse().unwrap(),
+            Acme {
+                config: AcmeConfig {
+                    provider: provider.into(),
+                    ..Default::default()
+                },
+                identifiers: vec![name.parse().unwrap()],
+                challenge_type: "http-01".into(),
+                dns_challenge: None,
+            },
+            "account".parse().unwrap(),
+        )
+    };
+
+    // Only the entries added by on-demand TLS are saved, and they survive a restart.
+    let storage = ManagedStorage::new(FileStorage::new(&dir.join("data")), &path).await?;
+    storage
+        .save_acme(&acme("ondemand", "On-Demand TLS", "app.example.com")?)
+        .await;
+    storage
+        .save_acme(&acme("other", "", "other.example.com")?)
+        .await;
+    storage
+        .save_acme(&acme("managed", "", "changed.example.com")?)
+        .await;
+
+    let storage = ManagedStorage::new(FileStorage::new(&dir.join("data")), &path).await?;
+    let acmes = storage.load_acmes().await;
+    let ids = acmes
+        .iter()
+        .map(|acme| acme.id.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["managed", "ondemand"]);
+    assert_eq!(acmes[0].acme.identifiers, ["example.com".parse().unwrap()]);
+
+    storage.delete_acme("ondemand".parse().unwrap()).await;
+    assert_eq!(storage.load_acmes().await.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
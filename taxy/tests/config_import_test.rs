@@ -0,0 +1,157 @@
+use taxy::config::document;
+use taxy::server::rpc::config::{ExportConfig, ImportConfig};
+use taxy::server::rpc::ports::GetPortList;
+use taxy::server::rpc::proxies::GetProxyList;
+use taxy_api::{
+    error::Error,
+    history::{ChangeAction, ConfigFormat, ConfigResource},
+    port::{Port, PortEntry, PortOptions},
+    proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind},
+};
+
+mod common;
+use common::{alloc_port, call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn config_import() -> anyhow::Result<()> {
+    let listen_port = alloc_port()?;
+    let new_port = alloc_port()?;
+
+    let proxy = ProxyEntry {
+        id: "proxy".parse().unwrap(),
+        proxy: Proxy {
+            ports: vec!["http".parse().unwrap()],
+            kind: ProxyKind::Http(HttpProxy {
+                vhosts: vec!["example.com".parse()?],
+                routes: vec![],
+            }),
+            ..Default::default()
+        },
+    };
+
+    let config = TestStorage::builder()
+        .ports(vec![PortEntry {
+            id: "http".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: listen_port.multiaddr_http(),
+                opts: PortOptions::default(),
+            },
+        }])
+        .proxies(vec![proxy.clone()])
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let exported = call_method(&mut channels, ExportConfig).await?;
+        let data = document::to_string(&exported, ConfigFormat::Yaml)?;
+        let mut desired = document::from_slice(data.as_bytes(), ConfigFormat::Yaml)?;
+        assert_eq!(desired, exported);
+
+        let plan = call_method(
+            &mut channels,
+            ImportConfig {
+                snapshot: desired.clone(),
+                dry_run: false,
+            },
+        )
+        .await?;
+        assert!(plan.changes.is_empty());
+        assert!(!plan.applied);
+
+        desired.proxies.clear();
+        desired.ports.push(PortEntry {
+            id: "new".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: new_port.multiaddr_http(),
+                opts: PortOptions::default(),
+            },
+        });
+
+        let plan = call_method(
+            &mut channels,
+            ImportConfig {
+                snapshot: desired.clone(),
+                dry_run: true,
+            },
+        )
+        .await?;
+        assert!(!plan.applied);
+        assert_eq!(plan.changes.len(), 2);
+        assert!(plan
+            .changes
+            .iter()
+            .any(|change| change.resource == ConfigResource::Port
+                && change.action == ChangeAction::Added));
+        assert!(plan
+            .changes
+            .iter()
+            .any(|change| change.resource == ConfigResource::Proxy
+                && change.action == ChangeAction::Removed));
+        assert_eq!(
+            call_method(&mut channels, GetProxyList).await?,
+            vec![proxy.clone()]
+        );
+
+        let mut invalid = desired.clone();
+        invalid.certs.push("missing".parse().unwrap());
+        let err = call_method(
+            &mut channels,
+            ImportConfig {
+                snapshot: invalid,
+                dry_run: false,
+            },
+        )
+        .await;
+        assert!(matches!(err, Err(Error::IdNotFound { .. })));
+
+        let mut invalid = desired.clone();
+        invalid.ports.push(invalid.ports[0].clone());
+        let err = call_method(
+            &mut channels,
+            ImportConfig {
+                snapshot: invalid,
+                dry_run: false,
+            },
+        )
+        .await;
+        assert!(matches!(err, Err(Error::IdAlreadyExists { .. })));
+        assert_eq!(call_method(&mut channels, GetPortList).await?.len(), 1);
+
+        // Proxies are checked against the ports of the imported document.
+        let mut invalid = desired.clone();
+        invalid.proxies.push(ProxyEntry {
+            id: "orphan".parse().unwrap(),
+            proxy: Proxy {
+                ports: vec!["missing".parse().unwrap()],
+                ..proxy.proxy.clone()
+            },
+        });
+        let err = call_method(
+            &mut channels,
+            ImportConfig {
+                snapshot: invalid,
+                dry_run: true,
+            },
+        )
+        .await;
+        assert!(matches!(err, Err(Error::InvalidConfigDocument { .. })));
+
+        let plan = call_method(
+            &mut channels,
+            ImportConfig {
+                snapshot: desired.clone(),
+                dry_run: false,
+            },
+        )
+        .await?;
+        assert!(plan.applied);
+        assert_eq!(plan.changes.len(), 2);
+        assert!(call_method(&mut channels, GetProxyList).await?.is_empty());
+        assert_eq!(call_method(&mut channels, GetPortList).await?.len(), 2);
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,110 @@
+use taxy::{
+    certs::Cert,
+    server::rpc::certs::{CompleteCsr, CreateCsr, DeleteCsr, GetCert, GetCsr, GetCsrList},
+};
+use taxy_api::{
+    cert::{CertKind, CsrRequest, KeyAlgorithm},
+    error::Error,
+};
+
+mod common;
+use common::{call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn csr_complete() -> anyhow::Result<()> {
+    let config = TestStorage::builder().build();
+    let ca = Cert::new_ca(KeyAlgorithm::default())?;
+    let other_ca = Cert::new_ca(KeyAlgorithm::default())?;
+
+    with_server(config, |mut channels| async move {
+        let csr = call_method(
+            &mut channels,
+            CreateCsr {
+                request: CsrRequest {
+                    kind: CertKind::Server,
+                    san: vec!["example.com".parse().unwrap()],
+                    common_name: None,
+                    organization: Some("Example Inc.".into()),
+                    key_algorithm: KeyAlgorithm::Rsa2048,
+                },
+            },
+        )
+        .await?;
+        assert_eq!(csr.common_name, "example.com");
+        assert!(csr.csr.starts_with("-----BEGIN CERTIFICATE REQUEST-----"));
+
+        let list = call_method(&mut channels, GetCsrList).await?;
+        assert_eq!(list, vec![csr.clone()]);
+
+        // A certificate for another key must be rejected.
+        let unrelated = Cert::new_self_signed(
+            &["example.com".parse().unwrap()],
+            &other_ca,
+            KeyAlgorithm::default(),
+        )?;
+        assert!(matches!(
+            call_method(
+                &mut channels,
+                CompleteCsr {
+                    id: csr.id,
+                    chain: unrelated.pem_chain.clone(),
+                },
+            )
+            .await,
+            Err(Error::CsrKeyMismatch { .. })
+        ));
+
+        let chain = sign_csr(&csr.csr, &ca)?;
+        let info = call_method(
+            &mut channels,
+            CompleteCsr {
+                id: csr.id,
+                chain: chain.into_bytes(),
+            },
+        )
+        .await?;
+        assert_eq!(info.san, vec!["example.com".parse().unwrap()]);
+        assert!(info.has_private_key);
+
+        let cert = call_method(&mut channels, GetCert { id: info.id }).await?;
+        assert!(cert.certified_key().is_ok());
+
+        assert!(matches!(
+            call_method(&mut channels, GetCsr { id: csr.id }).await,
+            Err(Error::IdNotFound { .. })
+        ));
+
+        let csr = call_method(
+            &mut channels,
+            CreateCsr {
+                request: CsrRequest {
+                    kind: CertKind::Client,
+                    san: vec!["client.example.com".parse().unwrap()],
+                    common_name: Some("client".into()),
+                    organization: None,
+                    key_algorithm: KeyAlgorithm::default(),
+                },
+            },
+        )
+        .await?;
+        call_method(&mut channels, DeleteCsr { id: csr.id }).await?;
+        assert!(call_method(&mut channels, GetCsrList).await?.is_empty());
+
+        Ok(())
+    })
+    .await
+}
+
+fn sign_csr(csr: &str, ca: &Cert) -> anyhow::Result<String> {
+    let key = rcgen::KeyPair::from_pem(std::str::from_utf8(ca.pem_key.as_ref().unwrap())?)?;
+    let params =
+        rcgen::CertificateParams::from_ca_cert_pem(std::str::from_utf8(&ca.pem_chain)?, key)?;
+    let signer = rcgen::Certificate::from_params(params)?;
+    let csr = rcgen::CertificateSigningRequest::from_pem(csr)?;
+    let cert = csr.serialize_pem_with_signer(&signer)?;
+    Ok(format!(
+        "{}\r\n{}",
+        cert,
+        std::str::from_utf8(&ca.pem_chain)?
+    ))
+}
@@ -0,0 +1,108 @@
+use taxy::server::rpc::history::{
+    GetConfigDiff, GetConfigHistory, GetConfigVersion, RollbackConfig,
+};
+use taxy::server::rpc::proxies::{GetProxyList, UpdateProxy};
+use taxy_api::{
+    error::Error,
+    history::{ChangeAction, ConfigResource},
+    port::{Port, PortEntry, PortOptions},
+    proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind},
+};
+
+mod common;
+use common::{alloc_port, call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn config_history() -> anyhow::Result<()> {
+    let listen_port = alloc_port()?;
+
+    let proxy = ProxyEntry {
+        id: "proxy".parse().unwrap(),
+        proxy: Proxy {
+            ports: vec!["http".parse().unwrap()],
+            kind: ProxyKind::Http(HttpProxy {
+                vhosts: vec!["example.com".parse()?],
+                routes: vec![],
+            }),
+            ..Default::default()
+        },
+    };
+
+    let config = TestStorage::builder()
+        .ports(vec![PortEntry {
+            id: "http".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: listen_port.multiaddr_http(),
+                opts: PortOptions::default(),
+            },
+        }])
+        .proxies(vec![proxy.clone()])
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let history = call_method(&mut channels, GetConfigHistory).await?;
+        assert_eq!(history.len(), 1);
+        let initial = history[0].version;
+
+        let mut updated = proxy.clone();
+        updated.proxy.name = "renamed".into();
+        call_method(
+            &mut channels,
+            UpdateProxy {
+                entry: updated.clone(),
+            },
+        )
+        .await?;
+
+        let history = call_method(&mut channels, GetConfigHistory).await?;
+        assert_eq!(history.len(), 2);
+        let latest = history[0].version;
+        assert!(latest > initial);
+        assert_eq!(history[0].changes.len(), 1);
+        assert!(history[0].changes[0].before.is_none());
+
+        let diff = call_method(
+            &mut channels,
+            GetConfigDiff {
+                version: latest,
+                from: None,
+            },
+        )
+        .await?;
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].resource, ConfigResource::Proxy);
+        assert_eq!(diff[0].action, ChangeAction::Updated);
+        assert_eq!(diff[0].id.as_deref(), Some("proxy"));
+        assert_eq!(diff[0].after.as_ref().unwrap()["name"], "renamed");
+
+        let version = call_method(&mut channels, GetConfigVersion { version: latest }).await?;
+        assert_eq!(version.snapshot.proxies, vec![updated]);
+
+        call_method(&mut channels, RollbackConfig { version: initial }).await?;
+        let proxies = call_method(&mut channels, GetProxyList).await?;
+        assert_eq!(proxies, vec![proxy.clone()]);
+
+        let history = call_method(&mut channels, GetConfigHistory).await?;
+        assert_eq!(history.len(), 3);
+
+        let diff = call_method(
+            &mut channels,
+            GetConfigDiff {
+                version: history[0].version,
+                from: Some(initial),
+            },
+        )
+        .await?;
+        assert!(diff.is_empty());
+
+        let err = call_method(&mut channels, RollbackConfig { version: 999 }).await;
+        assert!(matches!(err, Err(Error::IdNotFound { .. })));
+
+        let history = call_method(&mut channels, GetConfigHistory).await?;
+        assert_eq!(history.len(), 3);
+        Ok(())
+    })
+    .await
+}
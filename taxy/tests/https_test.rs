@@ -3,6 +3,7 @@ use reqwest::redirect::Policy;
 use std::sync::Arc;
 use taxy::certs::Cert;
 use taxy_api::{
+    cert::KeyAlgorithm,
     port::{Port, PortEntry, PortOptions},
     proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind, Route},
     tls::TlsTermination,
@@ -17,8 +18,15 @@ async fn https_proxy() -> anyhow::Result<()> {
     let listen_port = alloc_port()?;
     let proxy_port = alloc_port()?;
 
-    let root = Arc::new(Cert::new_ca().unwrap());
-    let cert = Arc::new(Cert::new_self_signed(&["localhost".parse().unwrap()], &root).unwrap());
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default()).unwrap());
+    let cert = Arc::new(
+        Cert::new_self_signed(
+            &["localhost".parse().unwrap()],
+            &root,
+            KeyAlgorithm::default(),
+        )
+        .unwrap(),
+    );
 
     let addr = listen_port.socket_addr();
     let hello = warp::path!("hello").map(|| "Hello".to_string());
@@ -113,8 +121,15 @@ async fn https_proxy_invalid_cert() -> anyhow::Result<()> {
     let listen_port = alloc_port()?;
     let proxy_port = alloc_port()?;
 
-    let root = Arc::new(Cert::new_ca().unwrap());
-    let cert = Arc::new(Cert::new_self_signed(&["localhost".parse().unwrap()], &root).unwrap());
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default()).unwrap());
+    let cert = Arc::new(
+        Cert::new_self_signed(
+            &["localhost".parse().unwrap()],
+            &root,
+            KeyAlgorithm::default(),
+        )
+        .unwrap(),
+    );
 
     let addr = listen_port.socket_addr();
     let hello = warp::path!("hello").map(|| "Hello".to_string());
@@ -190,8 +205,15 @@ async fn https_proxy_automatic_upgrade() -> anyhow::Result<()> {
     let listen_port = alloc_port()?;
     let proxy_port = alloc_port()?;
 
-    let root = Arc::new(Cert::new_ca().unwrap());
-    let cert = Arc::new(Cert::new_self_signed(&["localhost".parse().unwrap()], &root).unwrap());
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default()).unwrap());
+    let cert = Arc::new(
+        Cert::new_self_signed(
+            &["localhost".parse().unwrap()],
+            &root,
+            KeyAlgorithm::default(),
+        )
+        .unwrap(),
+    );
 
     let addr = listen_port.socket_addr();
     let hello = warp::path!("hello").map(|| "Hello".to_string());
@@ -282,8 +304,15 @@ async fn https_proxy_domain_fronting() -> anyhow::Result<()> {
     let listen_port = alloc_port()?;
     let proxy_port = alloc_port()?;
 
-    let root = Arc::new(Cert::new_ca().unwrap());
-    let cert = Arc::new(Cert::new_self_signed(&["localhost".parse().unwrap()], &root).unwrap());
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default()).unwrap());
+    let cert = Arc::new(
+        Cert::new_self_signed(
+            &["localhost".parse().unwrap()],
+            &root,
+            KeyAlgorithm::default(),
+        )
+        .unwrap(),
+    );
 
     let addr = listen_port.socket_addr();
     let hello = warp::path!("hello").map(|| "Hello".to_string());
@@ -369,3 +398,56 @@ async fn https_proxy_domain_fronting() -> anyhow::Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn https_key_algorithms() -> anyhow::Result<()> {
+    // Larger RSA keys only make the test slower.
+    for key_algorithm in [
+        KeyAlgorithm::EcdsaP256,
+        KeyAlgorithm::EcdsaP384,
+        KeyAlgorithm::Ed25519,
+        KeyAlgorithm::Rsa2048,
+    ] {
+        let proxy_port = alloc_port()?;
+
+        let root = Arc::new(Cert::new_ca(key_algorithm)?);
+        let cert = Arc::new(Cert::new_self_signed(
+            &["localhost".parse().unwrap()],
+            &root,
+            key_algorithm,
+        )?);
+
+        let config = TestStorage::builder()
+            .ports(vec![PortEntry {
+                id: "test".parse().unwrap(),
+                port: Port {
+                    active: true,
+                    name: String::new(),
+                    listen: proxy_port.multiaddr_https(),
+                    opts: PortOptions {
+                        tls_termination: Some(TlsTermination {
+                            server_names: vec!["localhost".into()],
+                        }),
+                    },
+                },
+            }])
+            .certs(
+                [(root.id, root.clone()), (cert.id, cert.clone())]
+                    .into_iter()
+                    .collect(),
+            )
+            .build();
+
+        let ca = reqwest::Certificate::from_pem(&root.pem_chain)?;
+        with_server(config, |_| async move {
+            let client = reqwest::Client::builder()
+                .add_root_certificate(ca)
+                .build()?;
+            let resp = client.get(proxy_port.https_url("/")).send().await?;
+            assert_eq!(resp.status(), 502);
+            Ok(())
+        })
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use taxy::server::rpc::proxies::{GetProxyStatus, UpdateProxy};
+use taxy_api::{
+    port::{Port, PortEntry, PortOptions},
+    proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind, Route, RouteConflictKind},
+};
+
+mod common;
+use common::{alloc_port, call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn route_conflict() -> anyhow::Result<()> {
+    let listen_port = alloc_port()?;
+
+    let proxy = |id: &str, vhost: &str| ProxyEntry {
+        id: id.parse().unwrap(),
+        proxy: Proxy {
+            ports: vec!["http".parse().unwrap()],
+            kind: ProxyKind::Http(HttpProxy {
+                vhosts: vec![vhost.parse().unwrap()],
+                routes: vec![Route {
+                    path: "/".into(),
+                    servers: vec![],
+                }],
+            }),
+            ..Default::default()
+        },
+    };
+
+    let config = TestStorage::builder()
+        .ports(vec![PortEntry {
+            id: "http".parse().unwrap(),
+            port: Port {
+                active: true,
+                name: String::new(),
+                listen: listen_port.multiaddr_http(),
+                opts: PortOptions::default(),
+            },
+        }])
+        .proxies(vec![
+            proxy("first", "*.example.com"),
+            proxy("second", "app.example.com"),
+        ])
+        .build();
+
+    with_server(config, |mut channels| async move {
+        let status = call_method(
+            &mut channels,
+            GetProxyStatus {
+                id: "first".parse().unwrap(),
+            },
+        )
+        .await?;
+        assert!(status.conflicts.is_empty());
+
+        let status = call_method(
+            &mut channels,
+            GetProxyStatus {
+                id: "second".parse().unwrap(),
+            },
+        )
+        .await?;
+        assert_eq!(status.conflicts.len(), 1);
+        assert_eq!(status.conflicts[0].kind, RouteConflictKind::Shadowed);
+        assert_eq!(status.conflicts[0].proxy, "first".parse().unwrap());
+        assert_eq!(status.conflicts[0].port, "http".parse().unwrap());
+
+        call_method(
+            &mut channels,
+            UpdateProxy {
+                entry: proxy("second", "app.example.org"),
+            },
+        )
+        .await?;
+        let status = call_method(
+            &mut channels,
+            GetProxyStatus {
+                id: "second".parse().unwrap(),
+            },
+        )
+        .await?;
+        assert!(status.conflicts.is_empty());
+        Ok(())
+    })
+    .await
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use taxy::certs::Cert;
 use taxy_api::{
+    cert::KeyAlgorithm,
     port::{Port, PortEntry, PortOptions, UpstreamServer},
     proxy::{Proxy, ProxyEntry, ProxyKind, TcpProxy},
     tls::TlsTermination,
@@ -15,8 +16,15 @@ async fn tls_proxy() -> anyhow::Result<()> {
     let listen_port = alloc_port()?;
     let proxy_port = alloc_port()?;
 
-    let root = Arc::new(Cert::new_ca().unwrap());
-    let cert = Arc::new(Cert::new_self_signed(&["localhost".parse().unwrap()], &root).unwrap());
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default()).unwrap());
+    let cert = Arc::new(
+        Cert::new_self_signed(
+            &["localhost".parse().unwrap()],
+            &root,
+            KeyAlgorithm::default(),
+        )
+        .unwrap(),
+    );
 
     let addr = listen_port.socket_addr();
     let hello = warp::path!("hello").map(|| "Hello".to_string());
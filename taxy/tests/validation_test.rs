@@ -0,0 +1,154 @@
+use taxy::server::rpc::ports::ValidatePort;
+use taxy::server::rpc::proxies::ValidateProxy;
+use taxy_api::{
+    port::{Port, PortEntry, PortOptions},
+    proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind, Route, Server},
+    tls::TlsTermination,
+    validation::{PortValidationRequest, ProxyValidationRequest, ValidationIssueKind},
+};
+
+mod common;
+use common::{alloc_port, call_method, with_server, TestStorage};
+
+#[tokio::test]
+async fn validate_port_and_proxy() -> anyhow::Result<()> {
+    let listen_port = alloc_port()?;
+    let free_port = alloc_port()?;
+    let used_port = alloc_port()?;
+    let _listener = std::net::TcpListener::bind(used_port.socket_addr())?;
+
+    let route = |url: &str| Route {
+        path: "/api/".into(),
+        servers: vec![Server {
+            url: url.parse().unwrap(),
+        }],
+    };
+    let proxy = Proxy {
+        ports: vec!["http".parse().unwrap()],
+        kind: ProxyKind::Http(HttpProxy {
+            vhosts: vec!["example.com".parse()?],
+            routes: vec![route("http://localhost:8080/")],
+        }),
+        ..Default::default()
+    };
+
+    let port = Port {
+        active: true,
+        name: String::new(),
+        listen: listen_port.multiaddr_http(),
+        opts: PortOptions::default(),
+    };
+    let config = TestStorage::builder()
+        .ports(vec![PortEntry {
+            id: "http".parse().unwrap(),
+            port: port.clone(),
+        }])
+        .proxies(vec![ProxyEntry {
+            id: "proxy".parse().unwrap(),
+            proxy: proxy.clone(),
+        }])
+        .build();
+
+    with_server(config, |mut channels| async move {
+        // The port itself is already listening on the address.
+        let request = PortValidationRequest {
+            id: Some("http".parse().unwrap()),
+            port: port.clone(),
+        };
+        let result = call_method(&mut channels, ValidatePort { request }).await?;
+        assert!(result.is_valid());
+
+        let request = PortValidationRequest {
+            id: None,
+            port: port.clone(),
+        };
+        let result = call_method(&mut channels, ValidatePort { request }).await?;
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].kind, ValidationIssueKind::AddressConflict);
+        assert_eq!(result.errors[0].conflict, Some("http".parse().unwrap()));
+
+        let request = PortValidationRequest {
+            id: None,
+            port: Port {
+                listen: used_port.multiaddr_http(),
+                ..port.clone()
+            },
+        };
+        let result = call_method(&mut channels, ValidatePort { request }).await?;
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(
+            result.errors[0].kind,
+            ValidationIssueKind::AddressAlreadyInUse
+        );
+
+        let request = PortValidationRequest {
+            id: None,
+            port: Port {
+                listen: free_port.multiaddr_https(),
+                ..port.clone()
+            },
+        };
+        let result = call_method(&mut channels, ValidatePort { request }).await?;
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].kind, ValidationIssueKind::InvalidConfig);
+
+        let request = PortValidationRequest {
+            id: None,
+            port: Port {
+                listen: free_port.multiaddr_https(),
+                opts: PortOptions {
+                    tls_termination: Some(TlsTermination {
+                        server_names: vec!["example.com".into()],
+                    }),
+                },
+                ..port.clone()
+            },
+        };
+        let result = call_method(&mut channels, ValidatePort { request }).await?;
+        assert!(result.is_valid());
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].kind, ValidationIssueKind::UncoveredName);
+
+        let request = ProxyValidationRequest {
+            id: Some("proxy".parse().unwrap()),
+            proxy: proxy.clone(),
+        };
+        let result = call_method(&mut channels, ValidateProxy { request }).await?;
+        assert!(result.is_valid());
+        assert!(result.warnings.is_empty());
+
+        let request = ProxyValidationRequest {
+            id: None,
+            proxy: Proxy {
+                ports: vec!["http".parse().unwrap(), "missing".parse().unwrap()],
+                kind: ProxyKind::Http(HttpProxy {
+                    vhosts: vec!["example.com".parse()?],
+                    routes: vec![route("file:///var/run/app.sock")],
+                }),
+                ..Default::default()
+            },
+        };
+        let result = call_method(&mut channels, ValidateProxy { request }).await?;
+        let errors = result
+            .errors
+            .iter()
+            .map(|issue| (issue.kind, issue.field.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                (ValidationIssueKind::PortNotFound, Some("ports[1]")),
+                (
+                    ValidationIssueKind::InvalidConfig,
+                    Some("routes[0].servers[0].url")
+                ),
+            ]
+        );
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].kind, ValidationIssueKind::RouteConflict);
+        assert_eq!(result.warnings[0].conflict, Some("proxy".parse().unwrap()));
+        assert!(result.warnings[0].message.contains("is shadowed by"));
+        Ok(())
+    })
+    .await
+}
@@ -3,6 +3,7 @@ use futures::{FutureExt, SinkExt, StreamExt};
 use std::sync::Arc;
 use taxy::certs::Cert;
 use taxy_api::{
+    cert::KeyAlgorithm,
     port::{Port, PortEntry, PortOptions},
     proxy::{HttpProxy, Proxy, ProxyEntry, ProxyKind, Route},
     tls::TlsTermination,
@@ -20,8 +21,15 @@ async fn wss_proxy() -> anyhow::Result<()> {
     let listen_port = alloc_port()?;
     let proxy_port = alloc_port()?;
 
-    let root = Arc::new(Cert::new_ca().unwrap());
-    let cert = Arc::new(Cert::new_self_signed(&["localhost".parse().unwrap()], &root).unwrap());
+    let root = Arc::new(Cert::new_ca(KeyAlgorithm::default()).unwrap());
+    let cert = Arc::new(
+        Cert::new_self_signed(
+            &["localhost".parse().unwrap()],
+            &root,
+            KeyAlgorithm::default(),
+        )
+        .unwrap(),
+    );
 
     let routes = warp::path("ws").and(warp::ws()).map(|ws: warp::ws::Ws| {
         ws.on_upgrade(|websocket| {